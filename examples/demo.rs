@@ -6,7 +6,10 @@ fn main() -> AppExit {
         .add_plugins((
             DefaultPlugins
                 .set(AdwaitaWindowPlugin::window_plugin())
-                .set(AdwaitaWindowPlugin::render_plugin(WgpuSettings::default())),
+                .set(
+                    AdwaitaWindowPlugin::render_plugin(WgpuSettings::default())
+                        .expect("failed to create renderer"),
+                ),
             AdwaitaWindowPlugin {
                 primary_window_config: Some(AdwaitaWindowConfig {
                     header_bar: AdwaitaHeaderBar::OverContent,
@@ -0,0 +1,113 @@
+use bevy::{
+    core_pipeline::prepass::DepthPrepass,
+    prelude::*,
+    render::{render_resource::TextureUsages, settings::WgpuSettings},
+};
+use bevy_mod_adwaita::{
+    AdwaitaDepthReadback, AdwaitaWindow, AdwaitaWindowPlugin, PrimaryAdwaitaWindow,
+};
+
+fn main() -> AppExit {
+    App::new()
+        .add_plugins((
+            DefaultPlugins
+                .set(AdwaitaWindowPlugin::window_plugin())
+                .set(
+                    AdwaitaWindowPlugin::render_plugin(WgpuSettings::default())
+                        .expect("failed to create renderer"),
+                ),
+            AdwaitaWindowPlugin::default(),
+        ))
+        // depth can't be read back from a multisampled depth buffer
+        .insert_resource(Msaa::Off)
+        .add_systems(PreStartup, setup_scene)
+        .add_systems(Update, (rotate_cubes, read_depth_on_click, log_depth))
+        .run()
+}
+
+#[derive(Debug, Component)]
+struct Rotated;
+
+/// set up two cubes cutting through each other, which only look right with depth
+/// testing
+fn setup_scene(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let cube = meshes.add(Cuboid::new(1.5, 1.5, 1.5));
+    for (color, rotation) in [
+        (Color::srgb_u8(124, 144, 255), Quat::IDENTITY),
+        (
+            Color::srgb_u8(255, 144, 124),
+            Quat::from_rotation_y(std::f32::consts::FRAC_PI_4),
+        ),
+    ] {
+        commands.spawn((
+            PbrBundle {
+                mesh: cube.clone(),
+                material: materials.add(color),
+                transform: Transform::from_rotation(rotation),
+                ..default()
+            },
+            Rotated,
+        ));
+    }
+    // light
+    commands.spawn(PointLightBundle {
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    // camera, whose depth and prepass textures Bevy allocates at the size of the
+    // window's render target
+    commands.spawn((
+        Camera3dBundle {
+            camera_3d: Camera3d {
+                depth_texture_usages: (TextureUsages::RENDER_ATTACHMENT
+                    | TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_SRC)
+                    .into(),
+                ..default()
+            },
+            transform: Transform::from_xyz(-2.0, 2.5, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..default()
+        },
+        DepthPrepass,
+    ));
+}
+
+fn rotate_cubes(time: Res<Time>, mut query: Query<&mut Transform, With<Rotated>>) {
+    for mut transform in &mut query {
+        transform.rotate_x(0.3 * time.delta_seconds());
+    }
+}
+
+/// reads the depth under the cursor whenever the left mouse button is clicked
+fn read_depth_on_click(
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut cursor_moved_events: EventReader<CursorMoved>,
+    mut cursor_position: Local<Option<Vec2>>,
+    windows: Query<&AdwaitaWindow, With<PrimaryAdwaitaWindow>>,
+    cameras: Query<&Camera>,
+) {
+    if let Some(event) = cursor_moved_events.read().last() {
+        *cursor_position = Some(event.position);
+    }
+    if !buttons.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let (Some(position), Ok(window), Ok(camera)) =
+        (*cursor_position, windows.get_single(), cameras.get_single())
+    else {
+        return;
+    };
+    let scale = camera.target_scaling_factor().unwrap_or(1.0);
+    let min = (position * scale).as_uvec2();
+    window.read_depth(URect::from_corners(min, min + UVec2::ONE));
+}
+
+fn log_depth(mut readbacks: EventReader<AdwaitaDepthReadback>) {
+    for readback in readbacks.read() {
+        info!("Depth at {:?}: {:?}", readback.rect.min, readback.depth);
+    }
+}
@@ -0,0 +1,85 @@
+use bevy::{prelude::*, render::settings::WgpuSettings};
+use bevy_mod_adwaita::{AdwaitaWindow, AdwaitaWindowPlugin, PrimaryAdwaitaWindow};
+
+fn main() -> AppExit {
+    App::new()
+        .add_plugins((
+            DefaultPlugins
+                .set(AdwaitaWindowPlugin::window_plugin())
+                .set(
+                    AdwaitaWindowPlugin::render_plugin(WgpuSettings::default())
+                        .expect("failed to create renderer"),
+                ),
+            AdwaitaWindowPlugin::default(),
+        ))
+        // Bevy renders into its own multisampled texture, and only writes the
+        // resolved image into the render target shared with GTK
+        .insert_resource(Msaa::Sample4)
+        .add_systems(PreStartup, setup_scene)
+        .add_systems(Update, (rotate_bars, toggle_msaa, show_msaa_in_title))
+        .run()
+}
+
+#[derive(Debug, Component)]
+struct Rotated;
+
+/// set up thin bars at an angle, whose edges are visibly jagged without MSAA
+fn setup_scene(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let bar = meshes.add(Cuboid::new(3.0, 0.05, 0.05));
+    let material = materials.add(Color::srgb_u8(124, 144, 255));
+    for i in 0..8 {
+        commands.spawn((
+            PbrBundle {
+                mesh: bar.clone(),
+                material: material.clone(),
+                transform: Transform::from_rotation(Quat::from_rotation_z(
+                    i as f32 * std::f32::consts::PI / 8.0,
+                )),
+                ..default()
+            },
+            Rotated,
+        ));
+    }
+    // light
+    commands.spawn(PointLightBundle {
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    // camera
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 0.0, 4.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn rotate_bars(time: Res<Time>, mut query: Query<&mut Transform, With<Rotated>>) {
+    for mut transform in &mut query {
+        transform.rotate_z(0.1 * time.delta_seconds());
+    }
+}
+
+/// switches MSAA on and off whenever the space bar is pressed
+fn toggle_msaa(keys: Res<ButtonInput<KeyCode>>, mut msaa: ResMut<Msaa>) {
+    if keys.just_pressed(KeyCode::Space) {
+        *msaa = match *msaa {
+            Msaa::Off => Msaa::Sample4,
+            _ => Msaa::Off,
+        };
+    }
+}
+
+fn show_msaa_in_title(msaa: Res<Msaa>, windows: Query<&AdwaitaWindow, With<PrimaryAdwaitaWindow>>) {
+    if !msaa.is_changed() {
+        return;
+    }
+    for window in &windows {
+        window.set_title(format!(
+            "{} samples - press space to toggle MSAA",
+            msaa.samples()
+        ));
+    }
+}
@@ -0,0 +1,83 @@
+use std::{thread, time::Duration};
+
+use bevy::{
+    app::ScheduleRunnerPlugin, prelude::*, render::settings::WgpuSettings, winit::WinitPlugin,
+};
+use bevy_mod_adwaita::{AdwaitaWindowConfig, AdwaitaWindowPlugin};
+
+fn main() {
+    // each app runs its own simulation and renderer on its own thread,
+    // while GTK runs on a thread shared between all of them
+    let apps = [
+        ("Red", Color::srgb(0.8, 0.2, 0.2)),
+        ("Blue", Color::srgb(0.2, 0.3, 0.8)),
+    ]
+    .map(|(title, color)| thread::spawn(move || run_app(title, color)));
+    for app in apps {
+        _ = app.join();
+    }
+}
+
+fn run_app(title: &'static str, color: Color) -> AppExit {
+    App::new()
+        .add_plugins((
+            DefaultPlugins
+                .set(AdwaitaWindowPlugin::window_plugin())
+                .set(
+                    AdwaitaWindowPlugin::render_plugin(WgpuSettings::default())
+                        .expect("failed to create renderer"),
+                )
+                // winit must run on the main thread, and we don't use it for windows anyway
+                .disable::<WinitPlugin>(),
+            ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(1.0 / 60.0)),
+            AdwaitaWindowPlugin {
+                primary_window_config: Some(AdwaitaWindowConfig {
+                    title: title.into(),
+                    width: 640,
+                    height: 480,
+                    ..default()
+                }),
+                ..default()
+            },
+        ))
+        .insert_resource(CubeColor(color))
+        .add_systems(PreStartup, setup_scene)
+        .add_systems(Update, rotate_cube)
+        .run()
+}
+
+#[derive(Debug, Resource)]
+struct CubeColor(Color);
+
+#[derive(Debug, Component)]
+struct Rotated;
+
+fn setup_scene(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    cube_color: Res<CubeColor>,
+) {
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Cuboid::new(1.0, 1.0, 1.0)),
+            material: materials.add(cube_color.0),
+            ..default()
+        },
+        Rotated,
+    ));
+    commands.spawn(PointLightBundle {
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(-2.0, 2.5, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn rotate_cube(time: Res<Time>, mut query: Query<&mut Transform, With<Rotated>>) {
+    for mut transform in &mut query {
+        transform.rotate_y(time.delta_seconds());
+    }
+}
@@ -0,0 +1,81 @@
+use bevy::{prelude::*, render::settings::WgpuSettings};
+use bevy_mod_adwaita::{
+    AdwaitaWindow, AdwaitaWindowConfig, AdwaitaWindowPlugin, RenderToAdwaitaWindow,
+};
+
+fn main() -> AppExit {
+    App::new()
+        .add_plugins((
+            DefaultPlugins
+                .set(AdwaitaWindowPlugin::window_plugin())
+                .set(
+                    AdwaitaWindowPlugin::render_plugin(WgpuSettings::default())
+                        .expect("failed to create renderer"),
+                ),
+            AdwaitaWindowPlugin {
+                primary_window_config: Some(AdwaitaWindowConfig {
+                    title: "Scene".into(),
+                    ..default()
+                }),
+                ..default()
+            },
+        ))
+        .add_systems(PreStartup, setup_scene)
+        .add_systems(Startup, open_inspector)
+        .add_systems(Update, rotate_cube)
+        .run()
+}
+
+#[derive(Debug, Component)]
+struct Rotated;
+
+fn setup_scene(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Cuboid::new(1.0, 1.0, 1.0)),
+            material: materials.add(Color::srgb(0.8, 0.7, 0.6)),
+            ..default()
+        },
+        Rotated,
+    ));
+    commands.spawn(PointLightBundle {
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    // rendered to the primary window
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(-2.0, 2.5, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+// every window gets its own render target and channels to GTK,
+// so any number of them can be opened from the same app
+fn open_inspector(mut commands: Commands) {
+    let window = commands
+        .spawn_empty()
+        .add(AdwaitaWindow::open(AdwaitaWindowConfig {
+            title: "Inspector".into(),
+            width: 400,
+            height: 400,
+            ..default()
+        }))
+        .id();
+    commands.spawn((
+        Camera3dBundle {
+            transform: Transform::from_xyz(0.0, 6.0, 0.0).looking_at(Vec3::ZERO, Vec3::Z),
+            ..default()
+        },
+        RenderToAdwaitaWindow(window),
+    ));
+}
+
+fn rotate_cube(time: Res<Time>, mut query: Query<&mut Transform, With<Rotated>>) {
+    for mut transform in &mut query {
+        transform.rotate_y(time.delta_seconds());
+    }
+}
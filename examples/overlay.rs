@@ -0,0 +1,86 @@
+use std::f64::consts::TAU;
+
+use bevy::{prelude::*, render::settings::WgpuSettings};
+use bevy_mod_adwaita::{
+    gtk::{self, prelude::*},
+    AdwaitaLayout, AdwaitaWindowConfig, AdwaitaWindowPlugin,
+};
+
+fn main() -> AppExit {
+    App::new()
+        .add_plugins((
+            DefaultPlugins
+                .set(AdwaitaWindowPlugin::window_plugin())
+                .set(
+                    AdwaitaWindowPlugin::render_plugin(WgpuSettings::default())
+                        .expect("failed to create renderer"),
+                ),
+            AdwaitaWindowPlugin {
+                primary_window_config: Some(AdwaitaWindowConfig {
+                    layout: Some(AdwaitaLayout::new(|cx| {
+                        cx.render_target_with_overlay(&crosshair()).upcast()
+                    })),
+                    ..default()
+                }),
+                ..default()
+            },
+        ))
+        .add_systems(PreStartup, setup_scene)
+        .add_systems(Update, rotate_cube)
+        .run()
+}
+
+/// HUD drawn by GTK over the scene
+fn crosshair() -> gtk::DrawingArea {
+    let area = gtk::DrawingArea::builder()
+        .hexpand(true)
+        .vexpand(true)
+        // let input through to the scene
+        .can_target(false)
+        .build();
+    area.set_draw_func(|_, cr, width, height| {
+        let (x, y) = (f64::from(width) / 2.0, f64::from(height) / 2.0);
+        cr.set_source_rgba(1.0, 1.0, 1.0, 0.8);
+        cr.set_line_width(2.0);
+        cr.arc(x, y, 12.0, 0.0, TAU);
+        cr.move_to(x - 20.0, y);
+        cr.line_to(x + 20.0, y);
+        cr.move_to(x, y - 20.0);
+        cr.line_to(x, y + 20.0);
+        _ = cr.stroke();
+    });
+    area
+}
+
+#[derive(Debug, Component)]
+struct Rotated;
+
+fn setup_scene(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Cuboid::new(1.0, 1.0, 1.0)),
+            material: materials.add(Color::srgb_u8(124, 144, 255)),
+            ..default()
+        },
+        Rotated,
+    ));
+    commands.spawn(PointLightBundle {
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..default()
+    });
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 1.5, 4.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn rotate_cube(time: Res<Time>, mut query: Query<&mut Transform, With<Rotated>>) {
+    for mut transform in &mut query {
+        transform.rotate_x(0.9 * time.delta_seconds());
+        transform.rotate_y(0.7 * time.delta_seconds());
+    }
+}
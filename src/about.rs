@@ -0,0 +1,64 @@
+use adw::prelude::*;
+use bevy::prelude::*;
+
+/// Details about the app, shown with [`AdwaitaWindow::show_about`].
+///
+/// Empty fields are left out of the about window.
+///
+/// [`AdwaitaWindow::show_about`]: crate::AdwaitaWindow::show_about
+#[derive(Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Default)]
+pub struct AdwaitaAboutInfo {
+    pub application_name: String,
+    pub version: String,
+    /// Names of the developers, optionally followed by an email address or URL in
+    /// angle brackets, such as `Jane Doe <jane@example.com>`.
+    pub developers: Vec<String>,
+    /// License text shown on the legal page, such as the name of an SPDX license.
+    pub license: Option<String>,
+    pub website: Option<String>,
+    /// Name of the icon from the icon theme, usually the app ID.
+    pub icon_name: Option<String>,
+}
+
+impl AdwaitaAboutInfo {
+    #[must_use]
+    pub fn new(application_name: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            application_name: application_name.into(),
+            version: version.into(),
+            ..default()
+        }
+    }
+}
+
+/// Presents an about window for `info` over `window`.
+///
+/// This returns straight away, and the about window runs on the GTK main loop like
+/// any other window, so `window` keeps receiving frames while it's open.
+pub(crate) fn show_about(window: &adw::Window, info: &AdwaitaAboutInfo) -> adw::AboutWindow {
+    let about = adw::AboutWindow::builder()
+        .transient_for(window)
+        .modal(true)
+        .destroy_with_parent(true)
+        .application_name(info.application_name.as_str())
+        .version(info.version.as_str())
+        .build();
+    let developers = info
+        .developers
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    about.set_developers(&developers);
+    if let Some(license) = &info.license {
+        about.set_license(license);
+    }
+    if let Some(website) = &info.website {
+        about.set_website(website);
+    }
+    if let Some(icon_name) = &info.icon_name {
+        about.set_application_icon(icon_name);
+    }
+    about.present();
+    about
+}
@@ -0,0 +1,56 @@
+use std::sync::{Arc, Mutex};
+
+use adw::gdk;
+use adw::prelude::*;
+use bevy::prelude::*;
+
+/// User's accent color, as set in the desktop's appearance settings.
+///
+/// This is updated whenever the user changes their accent color. Accent colors are
+/// only supported since libadwaita 1.6, and on older versions this always holds
+/// the default Adwaita blue.
+#[derive(Debug, Clone, Copy, PartialEq, Resource, Reflect)]
+#[reflect(Resource, Default)]
+pub struct AdwaitaAccentColor(pub Color);
+
+impl Default for AdwaitaAccentColor {
+    fn default() -> Self {
+        // `@accent_bg_color` in the default Adwaita stylesheet
+        Self(Color::srgb_u8(0x35, 0x84, 0xe4))
+    }
+}
+
+/// Accent color read on the GTK thread, if it changed since Bevy last read it.
+#[derive(Debug, Clone, Default, Resource, Deref)]
+pub(crate) struct SharedAccentColor(Arc<Mutex<Option<Color>>>);
+
+// looked up dynamically so that we still run on libadwaita versions without it
+const ACCENT_COLOR_PROPERTY: &str = "accent-color-rgba";
+
+/// Must be called on the GTK thread after Adwaita is initialized.
+pub(crate) fn watch_accent_color(shared: SharedAccentColor) {
+    let style_manager = adw::StyleManager::default();
+    if style_manager.find_property(ACCENT_COLOR_PROPERTY).is_none() {
+        tracing::debug!("Accent colors are not supported by this libadwaita version");
+        return;
+    }
+
+    let update = move |style_manager: &adw::StyleManager| {
+        let rgba = style_manager.property::<gdk::RGBA>(ACCENT_COLOR_PROPERTY);
+        let color = Color::srgba(rgba.red(), rgba.green(), rgba.blue(), rgba.alpha());
+        *shared.lock().expect("poisoned") = Some(color);
+    };
+    update(&style_manager);
+    style_manager.connect_notify_local(Some(ACCENT_COLOR_PROPERTY), move |style_manager, _| {
+        update(style_manager);
+    });
+}
+
+pub(crate) fn sync_accent_color(
+    shared: Res<SharedAccentColor>,
+    mut accent_color: ResMut<AdwaitaAccentColor>,
+) {
+    if let Some(color) = shared.lock().expect("poisoned").take() {
+        accent_color.0 = color;
+    }
+}
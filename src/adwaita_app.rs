@@ -1,22 +1,81 @@
-use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
-use std::sync::Arc;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::mem;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
 
 use adw::prelude::*;
-use adw::{glib, gtk};
+use adw::{gdk, gio, glib, gtk};
 use atomicbox::AtomicOptionBox;
+use bevy::color::ColorToPacked;
+use bevy::ecs::system::Resource;
+use bevy::input::keyboard::{Key, KeyCode};
+use bevy::input::mouse::{MouseButton, MouseScrollUnit};
+use bevy::input::touch::TouchPhase;
+use bevy::input::ButtonState;
+use bevy::math::{IVec2, Rect, UVec2, Vec2};
+use bevy::window::{CursorGrabMode, CursorIcon};
 
-use crate::render::{self, FrameInfo};
-use crate::{AdwaitaHeaderBar, AdwaitaWindowConfig};
+use crate::about::{self, AdwaitaAboutInfo};
+use crate::accent_color::{self, SharedAccentColor};
+use crate::attention;
+use crate::clipboard;
+use crate::color_scheme::{self, AdwaitaColorSchemePreference, SharedColorScheme};
+use crate::cursor;
+use crate::dialog::{self, AdwaitaDialog, AdwaitaDialogId};
+use crate::file_drop;
+use crate::frame_clock;
+use crate::frame_stats::{self, SharedFrameCounters};
+use crate::gesture::{self, AdwaitaGestureKind, AdwaitaGesturePhase};
+use crate::grab::{CursorGrab, SharedPointerMotion};
+use crate::header_bar;
+use crate::icon;
+use crate::ime;
+use crate::input::{self, AdwaitaInputFilter, AdwaitaKeyPropagation, SharedInputFilter};
+use crate::layout::AdwaitaLayoutContext;
+use crate::monitor::{self, AdwaitaMonitorInfo, SharedMonitorInfo};
+use crate::pointer;
+use crate::position;
+use crate::render::{
+    self, AdwaitaPresentMode, FrameDamage, FrameInfo, SharedConsumedFrame, SharedPresentWaiters,
+};
+use crate::scroll;
+use crate::toast::{self, AdwaitaToast, AdwaitaToastId};
+use crate::touch;
+use crate::{
+    AdwaitaHeaderBar, AdwaitaMaximizeAction, AdwaitaPresentedFrame, AdwaitaTextDirection,
+    AdwaitaWindowConfig, AdwaitaWindowIcon,
+};
 
 #[derive(Debug)]
 pub struct WindowOpen {
     pub config: AdwaitaWindowConfig,
-    pub recv_command: flume::Receiver<WindowCommand>,
+    pub commands: SharedCommandQueue,
+    pub send_event: flume::Sender<WindowEvent>,
     pub render_target_width: Arc<AtomicI32>,
     pub render_target_height: Arc<AtomicI32>,
-    pub scale_factor: Arc<AtomicI32>,
+    /// Bits of the `f32` scale factor of the window's surface, or `0.0` until it's
+    /// known.
+    pub scale_factor: Arc<AtomicU32>,
+    pub max_render_target_size: Arc<OnceLock<UVec2>>,
+    pub max_import_size: Arc<Mutex<Option<UVec2>>>,
     pub shared_next_frame: Arc<AtomicOptionBox<FrameInfo>>,
+    pub shared_damage: Arc<Mutex<FrameDamage>>,
+    pub rendered_frame_count: Arc<AtomicU32>,
+    pub consumed_frame: SharedConsumedFrame,
+    pub frame_counters: Option<SharedFrameCounters>,
+    pub presented_frame: Arc<Mutex<Option<AdwaitaPresentedFrame>>>,
+    pub present_waiters: SharedPresentWaiters,
+    pub monitor: SharedMonitorInfo,
     pub closed: Arc<AtomicBool>,
+    pub close_requested: Arc<AtomicBool>,
+    pub suspended: Arc<AtomicBool>,
+    pub title_bar_visible: Arc<AtomicBool>,
+    pub right_to_left: Arc<AtomicBool>,
 }
 
 #[derive(Debug)]
@@ -24,20 +83,385 @@ pub enum WindowCommand {
     SetMaximized(bool),
     SetFullscreen(bool),
     SetTitle(String),
+    SetIcon(Option<AdwaitaWindowIcon>),
+    SetTitleBarVisible(bool),
+    SetDecorated(bool),
+    SetTextDirection(AdwaitaTextDirection),
+    SetColorScheme(AdwaitaColorSchemePreference),
+    SetInputFilter(Option<AdwaitaInputFilter>),
+    SetKeyPropagation(AdwaitaKeyPropagation),
+    SetImeEnabled(bool),
+    SetImeCursorArea(Rect),
+    ShowDialog(AdwaitaDialogId, AdwaitaDialog),
+    ShowToast(AdwaitaToastId, AdwaitaToast),
+    ShowAbout(AdwaitaAboutInfo),
+    SetClipboardText(String),
+    RequestClipboardText,
+    WarpCursor(Vec2),
+    SetCursorIcon(CursorIcon),
+    SetCursorVisible(bool),
+    SetCursorGrab(CursorGrabMode),
+    SetUrgent(bool),
+    SetPosition(IVec2),
+    DismissLoadingOverlay,
+    ResetImportLimit,
+    SetMinSize {
+        width: Option<u32>,
+        height: Option<u32>,
+    },
+    Close,
+    #[cfg(feature = "gstreamer")]
+    StartRecording(std::path::PathBuf),
+    #[cfg(feature = "gstreamer")]
+    StopRecording,
+    Batch(Vec<WindowCommand>),
 }
 
-pub fn main_thread_loop(recv_window_open: flume::Receiver<WindowOpen>) {
+impl WindowCommand {
+    /// Kind of the command if it only sets some state of the window, so that a
+    /// later command of the same kind makes it redundant.
+    fn coalesce_key(&self) -> Option<mem::Discriminant<Self>> {
+        match self {
+            Self::SetMaximized(_)
+            | Self::SetFullscreen(_)
+            | Self::SetTitle(_)
+            | Self::SetIcon(_)
+            | Self::SetTitleBarVisible(_)
+            | Self::SetDecorated(_)
+            | Self::SetTextDirection(_)
+            | Self::SetColorScheme(_)
+            | Self::SetInputFilter(_)
+            | Self::SetKeyPropagation(_)
+            | Self::SetImeCursorArea(_)
+            | Self::SetClipboardText(_)
+            | Self::SetCursorIcon(_)
+            | Self::SetCursorVisible(_)
+            | Self::SetCursorGrab(_)
+            | Self::SetUrgent(_)
+            | Self::SetPosition(_)
+            | Self::ResetImportLimit
+            | Self::SetMinSize { .. } => Some(mem::discriminant(self)),
+            _ => None,
+        }
+    }
+}
+
+/// Commands sent from an [`AdwaitaWindow`] to its GTK window, which are applied on
+/// the window's next frame clock tick.
+///
+/// Sending never blocks the app, even while the window is hidden and its frame
+/// clock is paused. Commands which only set some state of the window replace any
+/// queued command of the same kind, so that state updated every frame, such as a
+/// title showing the frame rate, doesn't pile up.
+///
+/// [`AdwaitaWindow`]: crate::AdwaitaWindow
+#[derive(Debug, Default)]
+pub struct CommandQueue(Mutex<VecDeque<WindowCommand>>);
+
+/// Shared by an [`AdwaitaWindow`] and its GTK window only, so once there's a single
+/// reference left, the other side is gone.
+///
+/// [`AdwaitaWindow`]: crate::AdwaitaWindow
+pub type SharedCommandQueue = Arc<CommandQueue>;
+
+impl CommandQueue {
+    pub fn push(&self, command: WindowCommand) {
+        let mut commands = self.0.lock().expect("poisoned");
+        if let Some(key) = command.coalesce_key() {
+            // the replacement goes to the back, so it's still applied after the
+            // commands sent before it
+            commands.retain(|queued| queued.coalesce_key() != Some(key));
+        }
+        commands.push_back(command);
+    }
+
+    fn take(&self) -> VecDeque<WindowCommand> {
+        mem::take(&mut *self.0.lock().expect("poisoned"))
+    }
+}
+
+/// Sent from GTK to the app when something happens in a window.
+#[derive(Debug)]
+pub enum WindowEvent {
+    Gesture {
+        phase: AdwaitaGesturePhase,
+        kind: AdwaitaGestureKind,
+    },
+    DialogResponse {
+        id: AdwaitaDialogId,
+        response: Option<String>,
+    },
+    ToastDismissed {
+        id: AdwaitaToastId,
+        button_clicked: bool,
+    },
+    ClipboardText(Option<String>),
+    MonitorChanged(AdwaitaMonitorInfo),
+    /// ID of the header bar button or menu item that was activated.
+    HeaderAction(String),
+    Scroll {
+        unit: MouseScrollUnit,
+        delta: Vec2,
+    },
+    Key {
+        key_code: KeyCode,
+        logical_key: Key,
+        state: ButtonState,
+    },
+    /// Whether the window is the active window, which gets keyboard input.
+    Focused(bool),
+    /// Position of the window on the screen in physical pixels, only known on X11.
+    Moved(IVec2),
+    StateChanged {
+        /// Default size of the window in logical pixels, which is the size it's
+        /// restored to when not maximized or fullscreen.
+        size: UVec2,
+        maximized: bool,
+        fullscreen: bool,
+    },
+    /// Position in logical coordinates of the render target widget.
+    CursorMoved {
+        position: Vec2,
+        widget_size: Vec2,
+    },
+    CursorEntered,
+    CursorLeft,
+    /// Motion of the pointer in physical pixels, sent while the cursor is grabbed.
+    MouseMotion(Vec2),
+    FileHovered(Vec<PathBuf>),
+    /// Position in logical coordinates of the render target widget.
+    FileDragMoved {
+        position: Vec2,
+        widget_size: Vec2,
+    },
+    FileHoverCanceled,
+    FileDropped {
+        paths: Vec<PathBuf>,
+        position: Vec2,
+        widget_size: Vec2,
+    },
+    ImeEnabled(bool),
+    ImePreedit {
+        value: String,
+        /// Byte index of the cursor in `value`.
+        cursor: Option<usize>,
+    },
+    ImeCommit(String),
+    Touch {
+        id: u64,
+        phase: TouchPhase,
+        position: Vec2,
+        widget_size: Vec2,
+    },
+    MouseButton {
+        button: MouseButton,
+        state: ButtonState,
+    },
+    FrameTick {
+        frame_counter: i64,
+        frame_time: Duration,
+        predicted_presentation_time: Option<Duration>,
+        refresh_interval: Option<Duration>,
+    },
+}
+
+/// State of the display, updated whenever a monitor is added or removed.
+#[derive(Debug, Clone, Default)]
+pub struct DisplayInfo {
+    /// `(fourcc, modifier)` pairs which the display can import as dmabufs.
+    pub dmabuf_formats: Vec<(u32, u64)>,
+    pub monitor_count: u32,
+    /// Incremented on every change, starting at 0 when GTK is initialized.
+    pub generation: u64,
+}
+
+/// [`None`] until GTK is initialized.
+pub type SharedDisplayInfo = Arc<Mutex<Option<DisplayInfo>>>;
+
+#[derive(Debug)]
+pub enum MainThreadRequest {
+    OpenWindow(Box<WindowOpen>),
+    WatchAccentColor(SharedAccentColor),
+    WatchColorScheme(SharedColorScheme),
+    #[cfg(feature = "upower")]
+    WatchPowerState(crate::power::SharedPowerState),
+    #[cfg(feature = "idle-monitor")]
+    WatchIdleState(crate::idle::SharedIdleState),
+    /// Destroys every window and stops the thread.
+    Shutdown,
+}
+
+/// How long an exiting app waits for the GTK thread to stop.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Handle to the thread running GTK.
+///
+/// GTK can only be initialized once per process, so this thread is shared by all
+/// Bevy apps using [`AdwaitaWindowPlugin`], and lives until every app registered
+/// with [`MainThread::register_app`] has exited. Each app only talks to its own
+/// windows through their channels.
+///
+/// [`AdwaitaWindowPlugin`]: crate::AdwaitaWindowPlugin
+#[derive(Debug)]
+pub struct MainThread {
+    pub send_request: flume::Sender<MainThreadRequest>,
+    pub display_info: SharedDisplayInfo,
+    application_id: Option<String>,
+    // taken when the thread is shut down
+    thread: Mutex<Option<thread::JoinHandle<()>>>,
+    // disconnected once the thread has stopped
+    recv_stopped: flume::Receiver<()>,
+    running_apps: Mutex<usize>,
+}
+
+/// Returned by [`MainThread::register_app`] once the thread has been shut down.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "GTK was shut down when all previous apps exited, and can't be started again in \
+     this process"
+)]
+pub struct MainThreadStopped;
+
+/// Keeps the [`MainThread`] running until it's dropped, and the thread has no
+/// other apps registered.
+///
+/// This is a resource of each app, removed when the app exits.
+#[derive(Debug, Resource)]
+pub struct AppRegistration(&'static MainThread);
+
+impl Drop for AppRegistration {
+    fn drop(&mut self) {
+        self.0.release_app();
+    }
+}
+
+impl MainThread {
+    /// Gets the main thread, spawning it on first use with `application_id`.
+    pub fn get(application_id: Option<&str>) -> &'static Self {
+        static MAIN_THREAD: OnceLock<MainThread> = OnceLock::new();
+        let main_thread = MAIN_THREAD.get_or_init(|| {
+            let (send_request, recv_request) = flume::unbounded();
+            let (send_stopped, recv_stopped) = flume::bounded::<()>(0);
+            let display_info = SharedDisplayInfo::default();
+            let application_id = application_id.map(ToOwned::to_owned);
+            let thread = thread::Builder::new()
+                .name("adwaita".into())
+                .spawn({
+                    let display_info = display_info.clone();
+                    let application_id = application_id.clone();
+                    move || {
+                        main_thread_loop(recv_request, display_info, application_id);
+                        // also dropped if the loop panics
+                        drop(send_stopped);
+                    }
+                })
+                .expect("failed to spawn Adwaita main thread");
+            Self {
+                send_request,
+                display_info,
+                application_id,
+                thread: Mutex::new(Some(thread)),
+                recv_stopped,
+                running_apps: Mutex::new(0),
+            }
+        });
+        if let Some(application_id) = application_id {
+            if main_thread.application_id.as_deref() != Some(application_id) {
+                tracing::warn!(
+                    "GTK is already running for another app, ignoring application ID \
+                     \"{application_id}\""
+                );
+            }
+        }
+        main_thread
+    }
+
+    /// Registers an app as using this thread, so that it's shut down once the
+    /// returned registration and those of all other apps are dropped.
+    pub fn register_app(&'static self) -> Result<AppRegistration, MainThreadStopped> {
+        let mut running_apps = self.running_apps.lock().expect("poisoned");
+        if self.thread.lock().expect("poisoned").is_none() {
+            return Err(MainThreadStopped);
+        }
+        *running_apps += 1;
+        Ok(AppRegistration(self))
+    }
+
+    fn release_app(&self) {
+        let thread = {
+            let mut running_apps = self.running_apps.lock().expect("poisoned");
+            *running_apps -= 1;
+            if *running_apps > 0 {
+                return;
+            }
+            // taken while still locked, so that no app can register in the meantime
+            let Some(thread) = self.thread.lock().expect("poisoned").take() else {
+                return;
+            };
+            thread
+        };
+        _ = self.send_request.send(MainThreadRequest::Shutdown);
+
+        if let Err(flume::RecvTimeoutError::Timeout) =
+            self.recv_stopped.recv_timeout(SHUTDOWN_TIMEOUT)
+        {
+            tracing::warn!(
+                "GTK thread didn't stop within {SHUTDOWN_TIMEOUT:?}, leaving it running"
+            );
+            return;
+        }
+        if thread.join().is_err() {
+            tracing::warn!("GTK thread panicked while shutting down");
+        }
+    }
+}
+
+fn main_thread_loop(
+    recv_request: flume::Receiver<MainThreadRequest>,
+    display_info: SharedDisplayInfo,
+    application_id: Option<String>,
+) {
+    // without a `gtk::Application`, GDK takes the Wayland app ID and X11 `WM_CLASS`
+    // from the program name
+    if let Some(application_id) = &application_id {
+        glib::set_prgname(Some(application_id));
+    }
     // when we `init`, this thread is marked as the main thread
     adw::init().expect("failed to initialize Adwaita");
+    watch_display(display_info);
     let main_context = glib::MainContext::default();
     let mut windows = Vec::new();
 
     loop {
-        match recv_window_open.try_recv() {
-            Ok(request) => {
-                let window_state = WindowState::new(request);
+        match recv_request.try_recv() {
+            Ok(MainThreadRequest::OpenWindow(request)) => {
+                let window_state = WindowState::new(*request);
                 windows.push(window_state);
             }
+            Ok(MainThreadRequest::WatchAccentColor(accent_color)) => {
+                accent_color::watch_accent_color(accent_color);
+            }
+            Ok(MainThreadRequest::WatchColorScheme(color_scheme)) => {
+                color_scheme::watch_color_scheme(color_scheme);
+            }
+            #[cfg(feature = "upower")]
+            Ok(MainThreadRequest::WatchPowerState(power_state)) => {
+                crate::power::watch_power_state(power_state);
+            }
+            #[cfg(feature = "idle-monitor")]
+            Ok(MainThreadRequest::WatchIdleState(idle_state)) => {
+                crate::idle::watch_idle_state(idle_state);
+            }
+            Ok(MainThreadRequest::Shutdown) => {
+                for window in windows.drain(..) {
+                    window.window.destroy();
+                }
+                // lets GDK tell the display server that the windows are gone
+                while main_context.pending() {
+                    main_context.iteration(false);
+                }
+                return;
+            }
             Err(flume::TryRecvError::Disconnected) => return,
             Err(flume::TryRecvError::Empty) => {}
         }
@@ -50,37 +474,177 @@ pub fn main_thread_loop(recv_window_open: flume::Receiver<WindowOpen>) {
     }
 }
 
+// GTK sizes the window so that the render target fits in it
+fn set_min_size(render_target: &gtk::Picture, width: Option<u32>, height: Option<u32>) {
+    render_target.set_size_request(
+        width.map_or(-1, |width| assert_i32(width, "min width")),
+        height.map_or(-1, |height| assert_i32(height, "min height")),
+    );
+}
+
+fn store_scale_factor(scale_factor: &AtomicU32, surface: &gdk::Surface) {
+    scale_factor.store((surface.scale() as f32).to_bits(), Ordering::SeqCst);
+}
+
+/// Largest physical size of any monitor connected to the display.
+fn max_monitor_size() -> Option<UVec2> {
+    let display = gdk::Display::default()?;
+    let monitors = display.monitors();
+    (0..monitors.n_items())
+        .filter_map(|index| monitors.item(index)?.downcast::<gdk::Monitor>().ok())
+        .map(|monitor| {
+            let geometry = monitor.geometry();
+            let scale = monitor.scale();
+            UVec2::new(
+                (f64::from(geometry.width()) * scale).round() as u32,
+                (f64::from(geometry.height()) * scale).round() as u32,
+            )
+        })
+        .reduce(UVec2::max)
+}
+
+fn watch_display(display_info: SharedDisplayInfo) {
+    let Some(display) = gdk::Display::default() else {
+        *display_info.lock().expect("poisoned") = Some(DisplayInfo::default());
+        return;
+    };
+
+    let update = move |display: &gdk::Display| {
+        let mut display_info = display_info.lock().expect("poisoned");
+        let generation = display_info
+            .as_ref()
+            .map_or(0, |display_info| display_info.generation + 1);
+        *display_info = Some(DisplayInfo {
+            dmabuf_formats: query_dmabuf_formats(display),
+            monitor_count: display.monitors().n_items(),
+            generation,
+        });
+    };
+    update(&display);
+    // when a monitor is plugged into or out of another GPU, the formats
+    // which the compositor can import may change
+    display.monitors().connect_items_changed({
+        let display = display.clone();
+        move |_, _, _, _| update(&display)
+    });
+}
+
+fn query_dmabuf_formats(display: &gdk::Display) -> Vec<(u32, u64)> {
+    let formats = display.dmabuf_formats();
+    (0..formats.n_formats())
+        .map(|index| formats.format(index))
+        .collect()
+}
+
 #[derive(Debug)]
 struct WindowState {
     window: adw::Window,
     render_target: gtk::Picture,
+    previews: Vec<gtk::Picture>,
+    title_bar: Option<gtk::Widget>,
+    title_bar_visible: Arc<AtomicBool>,
+    text_direction: gtk::TextDirection,
+    right_to_left: Arc<AtomicBool>,
+    input_filter: SharedInputFilter,
+    key_controller: gtk::EventControllerKey,
+    im_context: gtk::IMMulticontext,
+    send_event: flume::Sender<WindowEvent>,
     shared_next_frame: Arc<AtomicOptionBox<FrameInfo>>,
-    recv_command: flume::Receiver<WindowCommand>,
+    // whether new frames are shown as soon as they arrive, instead of on the next
+    // frame clock tick
+    present_immediately: bool,
+    shared_damage: Arc<Mutex<FrameDamage>>,
+    rendered_frame_count: Arc<AtomicU32>,
+    consumed_frame: SharedConsumedFrame,
+    max_import_size: Arc<Mutex<Option<UVec2>>>,
+    // size of the last frame which GTK imported successfully
+    accepted_import_size: Option<UVec2>,
+    // set once shrinking render targets can't make GTK import them
+    import_failed: bool,
+    // set when we give GTK a new texture, and moved to `presented_frame`
+    // once GTK has actually painted it
+    pending_presented_frame: Rc<Cell<Option<AdwaitaPresentedFrame>>>,
+    commands: SharedCommandQueue,
     closed: Arc<AtomicBool>,
     should_poll: Arc<AtomicBool>,
     current_frame: Option<FrameInfo>,
+    current_texture: Option<gdk::Texture>,
+    loading_overlay: Option<gtk::Revealer>,
+    toast_overlay: adw::ToastOverlay,
+    // warp requested before there was a frame to map its position onto
+    pending_cursor_warp: Option<Vec2>,
+    cursor_icon: CursorIcon,
+    cursor_visible: bool,
+    cursor_grab: CursorGrab,
+    last_position: Option<IVec2>,
+    #[cfg(feature = "gstreamer")]
+    recording: Option<crate::recording::Recording>,
 }
 
 impl WindowState {
     fn new(request: WindowOpen) -> Self {
         let WindowOpen {
             config,
-            recv_command,
+            commands,
+            send_event,
             render_target_width,
             render_target_height,
             scale_factor,
+            max_render_target_size,
+            max_import_size,
             shared_next_frame,
+            shared_damage,
+            rendered_frame_count,
+            consumed_frame,
+            frame_counters,
+            presented_frame,
+            present_waiters,
+            monitor,
             closed,
+            close_requested,
+            suspended,
+            title_bar_visible,
+            right_to_left,
         } = request;
 
+        if config.preallocate_render_target {
+            if let Some(size) = max_monitor_size() {
+                _ = max_render_target_size.set(size);
+            }
+        }
+
         let render_target = gtk::Picture::new();
+        set_min_size(&render_target, config.min_width, config.min_height);
+        // added first, so that it also sees the events which the filter suppresses
+        if config.debug_input {
+            input::attach_input_debug(&render_target);
+        }
+        let input_filter = SharedInputFilter::default();
+        input::attach_input_filter(&render_target, &input_filter);
+        let pointer_motion = SharedPointerMotion::default();
+        pointer::attach_pointer(&render_target, &send_event, &pointer_motion);
+        touch::attach_touch(&render_target, &send_event);
+        file_drop::attach_file_drop(&render_target, &send_event);
+        gesture::attach_gestures(&render_target, &send_event);
+        scroll::attach_scroll(&render_target, &send_event, config.scroll_filter);
         let render_target_container = {
+            // the picture keeps the aspect ratio of the render target, and the
+            // offload's background fills the bars around it
             let graphics_offload = gtk::GraphicsOffload::builder()
-                .black_background(true)
+                .black_background(config.letterbox_color.is_none() && !config.transparent)
                 .child(&render_target)
                 .hexpand(true)
                 .vexpand(true)
                 .build();
+            if let Some(color) = config.letterbox_color {
+                let [r, g, b, a] = color.to_srgba().to_u8_array();
+                add_scoped_css(&graphics_offload, "letterbox", |class| {
+                    format!(
+                        ".{class} {{ background-color: rgba({r}, {g}, {b}, {}); }}",
+                        f32::from(a) / 255.0
+                    )
+                });
+            }
 
             // Use a trick to detect when the actual render target
             // is resized, and send this new frame size to the app.
@@ -121,14 +685,53 @@ impl WindowState {
             frame_content_v
         };
 
-        let content: gtk::Widget = match config.header_bar {
-            AdwaitaHeaderBar::Full => {
-                let header_bar = adw::HeaderBar::new();
+        let loading_overlay = config.loading_overlay.then(loading_overlay);
+        let frame_stats_overlay = frame_counters.clone().map(frame_stats::overlay);
+        let render_target_container: gtk::Widget =
+            if loading_overlay.is_some() || frame_stats_overlay.is_some() {
+                let overlay = gtk::Overlay::new();
+                overlay.set_child(Some(&render_target_container));
+                if let Some(frame_stats_overlay) = &frame_stats_overlay {
+                    overlay.add_overlay(frame_stats_overlay);
+                }
+                // covers the stats too, which would be meaningless while loading
+                if let Some(loading_overlay) = &loading_overlay {
+                    overlay.add_overlay(loading_overlay);
+                }
+                overlay.upcast()
+            } else {
+                render_target_container.upcast()
+            };
+        // toasts are shown over the render target, even if the layout puts other
+        // widgets around it
+        let toast_overlay = adw::ToastOverlay::new();
+        toast_overlay.set_child(Some(&render_target_container));
+        let render_target_container: gtk::Widget = toast_overlay.clone().upcast();
+
+        let (render_target_container, previews): (gtk::Widget, _) = match &config.layout {
+            Some(layout) => {
+                let cx = AdwaitaLayoutContext::new(render_target_container);
+                let content = layout.build(&cx);
+                (content, cx.into_previews())
+            }
+            None => (render_target_container, Vec::new()),
+        };
+
+        // the title bar is only ever hidden or shown, never rebuilt, so that
+        // the header bar style is preserved when it's shown again
+        let (content, title_bar): (gtk::Widget, Option<gtk::Widget>) = match &config.header_bar {
+            header @ (AdwaitaHeaderBar::Full | AdwaitaHeaderBar::Custom(_)) => {
+                let header_bar = match header {
+                    AdwaitaHeaderBar::Custom(items) => {
+                        header_bar::custom_header_bar(items, &send_event)
+                    }
+                    _ => adw::HeaderBar::new(),
+                };
 
                 let content = gtk::Box::new(gtk::Orientation::Vertical, 0);
                 content.append(&header_bar);
                 content.append(&render_target_container);
-                content.upcast()
+                (content.upcast(), Some(header_bar.upcast()))
             }
             AdwaitaHeaderBar::OverContent => {
                 // this margin makes the window controls looks exactly like in an `adw::HeaderBar`
@@ -147,10 +750,23 @@ impl WindowState {
                 let content = gtk::Overlay::new();
                 content.set_child(Some(&render_target_container));
                 content.add_overlay(&window_controls);
-                content.upcast()
+                (content.upcast(), Some(window_controls.upcast()))
             }
-            AdwaitaHeaderBar::None => render_target_container.upcast(),
+            AdwaitaHeaderBar::None => (render_target_container, None),
         };
+        if let Some(title_bar) = &title_bar {
+            title_bar.set_visible(title_bar_visible.load(Ordering::SeqCst));
+        }
+        if config.clip_rounded_corners {
+            clip_rounded_corners(&content);
+        }
+        if config.content_margin > 0 {
+            let margin = assert_i32(config.content_margin, "window content margin");
+            content.set_margin_start(margin);
+            content.set_margin_end(margin);
+            content.set_margin_top(margin);
+            content.set_margin_bottom(margin);
+        }
 
         let window = adw::Window::builder()
             .handle_menubar_accel(false)
@@ -158,49 +774,183 @@ impl WindowState {
             .default_height(assert_i32(config.height, "window request height"))
             .title(config.title)
             .resizable(config.resizable)
+            .decorated(config.decorated)
             .maximized(config.maximized)
             .fullscreened(config.fullscreen)
             .content(&content)
             .build();
 
+        let key_controller = input::attach_key_controller(
+            &window,
+            config.key_propagation,
+            &input_filter,
+            &send_event,
+        );
+        let im_context = ime::create_im_context(&render_target, &send_event);
+        if config.maximize_action == AdwaitaMaximizeAction::Fullscreen {
+            maximize_to_fullscreen(&window, &content);
+        }
+        if let Some(workspace) = config.workspace {
+            request_workspace(&window, workspace);
+        }
+        if let Some(icon) = &config.icon {
+            icon::set_icon(&window, Some(icon));
+        }
+        if let Some(scale) = config.chrome_text_scale {
+            set_chrome_text_scale(&window, scale);
+        }
+        if let Some(scale) = config.chrome_icon_scale {
+            set_chrome_icon_scale(&window, scale);
+        }
+        if config.transparent {
+            // GTK surfaces always have an alpha channel, only the stylesheet's
+            // window background hides it
+            add_scoped_css(&window, "transparent", |class| {
+                format!("window.{class} {{ background: none; }}")
+            });
+        }
+        let text_direction = gtk_text_direction(config.text_direction);
+        set_text_direction(window.upcast_ref(), text_direction);
+        right_to_left.store(
+            window.direction() == gtk::TextDirection::Rtl,
+            Ordering::SeqCst,
+        );
+
         window.connect_close_request({
             let closed = closed.clone();
             move |_| {
-                closed.store(true, Ordering::SeqCst);
-                glib::Propagation::Proceed
+                // `WindowCommand::Close` marks the window as closed before closing it
+                if closed.load(Ordering::SeqCst) {
+                    return glib::Propagation::Proceed;
+                }
+                // the app decides whether the window actually closes
+                close_requested.store(true, Ordering::SeqCst);
+                glib::Propagation::Stop
+            }
+        });
+
+        monitor::watch_monitor(&window, &monitor, &send_event);
+
+        window.connect_is_active_notify({
+            let send_event = send_event.clone();
+            move |window| {
+                if window.is_active() {
+                    attention::set_urgent(window, false);
+                }
+                _ = send_event.send(WindowEvent::Focused(window.is_active()));
             }
         });
 
-        window.connect_scale_factor_notify({
+        watch_window_state(&window, &send_event);
+
+        window.connect_suspended_notify(move |window| {
+            suspended.store(window.is_suspended(), Ordering::SeqCst);
+        });
+
+        // the widget scale factor is rounded up to an integer, but the surface has the
+        // fractional scale from `wp_fractional_scale_v1`, such as 1.25
+        window.connect_realize({
             let scale_factor = scale_factor.clone();
             move |window| {
-                scale_factor.store(window.scale_factor(), Ordering::SeqCst);
+                let Some(surface) = window.surface() else {
+                    return;
+                };
+                store_scale_factor(&scale_factor, &surface);
+                surface.connect_scale_notify({
+                    let scale_factor = scale_factor.clone();
+                    move |surface| store_scale_factor(&scale_factor, surface)
+                });
+            }
+        });
+
+        let pending_presented_frame = Rc::new(Cell::new(None));
+        // the frame clock only exists once the window is realized
+        window.connect_realize({
+            let pending_presented_frame = pending_presented_frame.clone();
+            move |window| {
+                let Some(frame_clock) = window.frame_clock() else {
+                    return;
+                };
+                let pending_presented_frame = pending_presented_frame.clone();
+                let presented_frame = presented_frame.clone();
+                let present_waiters = present_waiters.clone();
+                let frame_counters = frame_counters.clone();
+                frame_clock.connect_after_paint(move |_| {
+                    if let Some(frame) = pending_presented_frame.take() {
+                        if let Some(counters) = &frame_counters {
+                            counters.presented.fetch_add(1, Ordering::Relaxed);
+                        }
+                        *presented_frame.lock().expect("poisoned") = Some(frame);
+                        for waiter in present_waiters.lock().expect("poisoned").drain(..) {
+                            _ = waiter.send.send(frame);
+                        }
+                    }
+                });
             }
         });
 
         let should_poll = Arc::new(AtomicBool::new(false));
         window.add_tick_callback({
             let should_poll = should_poll.clone();
-            move |_, _| {
+            let send_event = send_event.clone();
+            move |_, frame_clock| {
                 should_poll.store(true, Ordering::SeqCst);
+                _ = send_event.send(frame_clock::frame_tick_event(frame_clock));
                 glib::ControlFlow::Continue
             }
         });
 
-        window.present();
+        if config.focus_on_open {
+            if let Some(startup_id) = &config.startup_id {
+                window.set_startup_id(startup_id);
+            }
+            window.present();
+        } else {
+            open_unfocused(&window);
+        }
 
         Self {
             window,
             render_target,
+            previews,
+            title_bar,
+            title_bar_visible,
+            text_direction,
+            right_to_left,
+            input_filter,
+            key_controller,
+            im_context,
+            send_event,
             shared_next_frame,
-            recv_command,
+            present_immediately: config.present_mode == Some(AdwaitaPresentMode::Immediate),
+            shared_damage,
+            rendered_frame_count,
+            consumed_frame,
+            max_import_size,
+            pending_presented_frame,
+            commands,
             closed,
             should_poll,
+            accepted_import_size: None,
+            import_failed: false,
             current_frame: None,
+            current_texture: None,
+            loading_overlay,
+            toast_overlay,
+            pending_cursor_warp: config.initial_cursor_position,
+            cursor_icon: CursorIcon::Default,
+            cursor_visible: true,
+            cursor_grab: CursorGrab::new(pointer_motion),
+            last_position: None,
+            #[cfg(feature = "gstreamer")]
+            recording: None,
         }
     }
 
     fn poll(&mut self) -> Result<(), ()> {
+        if self.present_immediately {
+            self.show_next_frame();
+        }
         let Ok(true) =
             self.should_poll
                 .compare_exchange(true, false, Ordering::SeqCst, Ordering::SeqCst)
@@ -211,49 +961,597 @@ impl WindowState {
         if self.closed.load(Ordering::SeqCst) {
             return Err(());
         }
+        // whatever Bevy has rendered by now is what we draw this frame
+        self.consumed_frame
+            .consume(self.rendered_frame_count.load(Ordering::SeqCst));
+
+        self.show_next_frame();
+        // GTK 4 has no signal for when the window is moved
+        self.poll_position();
+
+        for command in self.commands.take() {
+            self.apply_command(command);
+        }
+        // the app dropped its window, after sending any last commands
+        if Arc::strong_count(&self.commands) == 1 {
+            return Err(());
+        }
 
+        Ok(())
+    }
+
+    fn show_next_frame(&mut self) {
+        // every frame is rendered into another image of the render target, so
+        // there's nothing new to draw until the next one arrives
         if let Some(frame_info) = self.shared_next_frame.take(Ordering::SeqCst) {
-            self.current_frame = Some(*frame_info);
+            self.show_frame(*frame_info);
         }
+    }
 
-        if let Some(frame_info) = self.current_frame.as_ref() {
-            let frame = render::create_dmabuf_texture(&frame_info.dmabuf);
-            self.render_target.set_paintable(Some(&frame));
-            self.render_target.queue_draw();
-        } else {
-            tracing::info!("Don't have a frame yet...");
+    fn poll_position(&mut self) {
+        let Some(position) = self
+            .window
+            .surface()
+            .and_then(|surface| position::surface_position(&surface))
+        else {
+            return;
+        };
+        if self.last_position != Some(position) {
+            self.last_position = Some(position);
+            _ = self.send_event.send(WindowEvent::Moved(position));
         }
+    }
 
-        loop {
-            let command = match self.recv_command.try_recv() {
-                Ok(command) => command,
-                Err(flume::TryRecvError::Disconnected) => return Err(()),
-                Err(flume::TryRecvError::Empty) => break,
-            };
+    fn show_frame(&mut self, frame_info: FrameInfo) {
+        // a frame in an image of another size isn't an update of the current texture
+        let same_size = self.current_frame.as_ref().is_some_and(|current| {
+            current.dmabuf.size == frame_info.dmabuf.size
+                && current.dmabuf.visible_size == frame_info.dmabuf.visible_size
+        });
+        if !same_size {
+            self.current_texture = None;
+        }
+        // damage is only taken together with a frame, since it may be stored before
+        // the frame it belongs to
+        let damage = std::mem::replace(
+            &mut *self.shared_damage.lock().expect("poisoned"),
+            FrameDamage::none(),
+        );
+        // the previous frame's image goes back into rotation once GTK also releases
+        // the textures created from it
+        let frame_info = self.current_frame.insert(frame_info);
+
+        let update = match (&self.current_texture, &damage) {
+            (Some(texture), FrameDamage::Partial(rects)) => Some((texture, rects.as_slice())),
+            _ => None,
+        };
+        // if nothing has changed since the last texture, keep showing it
+        if update.is_some_and(|(_, rects)| rects.is_empty()) {
+            return;
+        }
+        let frame = match render::create_dmabuf_texture(frame_info, update) {
+            Ok(frame) => frame,
+            Err(err) => {
+                self.reject_frame(&err);
+                return;
+            }
+        };
+        self.render_target.set_paintable(Some(&frame));
+        self.render_target.queue_draw();
+        // a paintable can be shown by any number of widgets at once,
+        // and GTK keeps the texture alive for as long as any of them use it
+        for preview in &self.previews {
+            preview.set_paintable(Some(&frame));
+        }
+        self.current_texture = Some(frame);
+        self.accepted_import_size = Some(frame_info.dmabuf.size);
+        #[cfg(feature = "gstreamer")]
+        if let Some(recording) = &mut self.recording {
+            if let Err(err) = recording.push_frame(&frame_info.dmabuf) {
+                tracing::error!("Failed to record frame, stopping recording: {err}");
+                self.recording = None;
+            }
+        }
+        self.pending_presented_frame
+            .set(Some(AdwaitaPresentedFrame {
+                buffer_id: frame_info.buffer_id,
+                size: frame_info.dmabuf.visible_size,
+                frame_count: self.rendered_frame_count.load(Ordering::SeqCst),
+            }));
+        if let Some(position) = self.pending_cursor_warp.take() {
+            self.warp_cursor(position);
+        }
+    }
+
+    /// Called when GTK can't import the current frame's buffer.
+    ///
+    /// If the compositor may be refusing buffers of that size, render targets are
+    /// shrunk until a frame is accepted, down to [`WindowState::min_import_size`].
+    fn reject_frame(&mut self, err: &glib::Error) {
+        let Some(frame_info) = self.current_frame.take() else {
+            return;
+        };
+        self.current_texture = None;
+        if self.import_failed {
+            return;
+        }
+
+        // the compositor doesn't tell us its limit, so the only hints that the size
+        // is the problem are that a smaller buffer worked, or that the buffer
+        // couldn't be created at all
+        let size = frame_info.dmabuf.size;
+        let smaller_accepted = self
+            .accepted_import_size
+            .is_some_and(|accepted| accepted != size && accepted.cmple(size).all());
+        let size_rejected = smaller_accepted || err.matches(gdk::DmabufError::CreationFailed);
+        let limit = (size * 3 / 4).max(self.min_import_size()).min(size);
+        if !size_rejected || limit == size {
+            self.import_failed = true;
+            tracing::error!(
+                "Compositor rejected a {}x{} render target ({err}), not shrinking render targets any further",
+                size.x,
+                size.y,
+            );
+            return;
+        }
+
+        let mut max_import_size = self.max_import_size.lock().expect("poisoned");
+        let limit = max_import_size.map_or(limit, |max| max.min(limit));
+        *max_import_size = Some(limit);
+        tracing::warn!(
+            "Compositor rejected a {}x{} render target ({err}), limiting render targets to {}x{} and upscaling",
+            size.x,
+            size.y,
+            limit.x,
+            limit.y,
+        );
+    }
 
-            match command {
-                WindowCommand::SetMaximized(true) => {
-                    self.window.maximize();
+    /// Smallest physical size that [`WindowState::reject_frame`] shrinks render
+    /// targets to, which is the window's min size, but at least 64 pixels.
+    fn min_import_size(&self) -> UVec2 {
+        const MIN_IMPORT_SIZE: u32 = 64;
+
+        let scale = self.window.surface().map_or(1.0, |surface| surface.scale());
+        let (width, height) = self.render_target.size_request();
+        let physical = |logical: i32| (f64::from(logical.max(0)) * scale).ceil() as u32;
+        UVec2::new(physical(width), physical(height)).max(UVec2::splat(MIN_IMPORT_SIZE))
+    }
+
+    fn apply_command(&mut self, command: WindowCommand) {
+        match command {
+            WindowCommand::SetMaximized(true) => {
+                self.window.maximize();
+            }
+            WindowCommand::SetMaximized(false) => {
+                self.window.unmaximize();
+            }
+            WindowCommand::SetFullscreen(true) => {
+                self.window.fullscreen();
+            }
+            WindowCommand::SetFullscreen(false) => {
+                self.window.unfullscreen();
+            }
+            WindowCommand::SetTitle(title) => {
+                self.window.set_title(Some(&title));
+            }
+            WindowCommand::SetIcon(icon) => icon::set_icon(&self.window, icon.as_ref()),
+            WindowCommand::SetTitleBarVisible(visible) => {
+                // hiding the header bar frees up its space for the render target,
+                // which gets picked up by the size listeners
+                if let Some(title_bar) = &self.title_bar {
+                    title_bar.set_visible(visible);
+                    self.title_bar_visible.store(visible, Ordering::SeqCst);
                 }
-                WindowCommand::SetMaximized(false) => {
-                    self.window.unmaximize();
+            }
+            WindowCommand::SetDecorated(decorated) => {
+                self.window.set_decorated(decorated);
+            }
+            WindowCommand::SetColorScheme(preference) => {
+                adw::StyleManager::default().set_color_scheme(preference.adw());
+            }
+            WindowCommand::SetTextDirection(direction) => {
+                self.text_direction = gtk_text_direction(direction);
+                set_text_direction(self.window.upcast_ref(), self.text_direction);
+                self.right_to_left.store(
+                    self.window.direction() == gtk::TextDirection::Rtl,
+                    Ordering::SeqCst,
+                );
+            }
+            #[cfg(feature = "gstreamer")]
+            WindowCommand::StartRecording(path) => {
+                // drop the previous recording first, so that it finishes its file
+                self.recording = None;
+                match crate::recording::Recording::start(&path) {
+                    Ok(recording) => {
+                        tracing::info!("Started recording to {}", path.display());
+                        self.recording = Some(recording);
+                    }
+                    Err(err) => {
+                        tracing::error!("Failed to start recording to {}: {err}", path.display());
+                    }
                 }
-                WindowCommand::SetFullscreen(true) => {
-                    self.window.fullscreen();
+            }
+            #[cfg(feature = "gstreamer")]
+            WindowCommand::StopRecording => {
+                self.recording = None;
+            }
+            WindowCommand::SetInputFilter(filter) => {
+                *self.input_filter.borrow_mut() = filter;
+            }
+            WindowCommand::SetKeyPropagation(propagation) => {
+                self.key_controller
+                    .set_propagation_phase(propagation.phase());
+            }
+            WindowCommand::SetImeEnabled(enabled) => {
+                ime::set_ime_enabled(
+                    &self.window,
+                    &self.key_controller,
+                    &self.im_context,
+                    enabled,
+                    &self.send_event,
+                );
+            }
+            WindowCommand::SetImeCursorArea(area) => {
+                if let Some(frame) = &self.current_frame {
+                    ime::set_cursor_area(
+                        &self.im_context,
+                        &self.render_target,
+                        frame.dmabuf.visible_size,
+                        area,
+                    );
                 }
-                WindowCommand::SetFullscreen(false) => {
-                    self.window.unfullscreen();
+            }
+            WindowCommand::ShowDialog(id, dialog) => {
+                let dialog = dialog::show_dialog(&self.window, id, dialog, self.send_event.clone());
+                set_text_direction(dialog.upcast_ref(), self.text_direction);
+            }
+            WindowCommand::ShowToast(id, toast) => {
+                toast::show_toast(&self.toast_overlay, id, toast, self.send_event.clone());
+            }
+            WindowCommand::ShowAbout(info) => {
+                let about = about::show_about(&self.window, &info);
+                set_text_direction(about.upcast_ref(), self.text_direction);
+            }
+            WindowCommand::SetClipboardText(text) => {
+                clipboard::set_clipboard_text(&self.window, &text);
+            }
+            WindowCommand::RequestClipboardText => {
+                clipboard::request_clipboard_text(&self.window, self.send_event.clone());
+            }
+            WindowCommand::WarpCursor(position) => {
+                if self.current_frame.is_some() {
+                    self.warp_cursor(position);
+                } else {
+                    self.pending_cursor_warp = Some(position);
+                }
+            }
+            WindowCommand::SetPosition(position) => {
+                position::set_position(&self.window, position);
+            }
+            WindowCommand::SetCursorIcon(icon) => {
+                self.cursor_icon = icon;
+                cursor::set_cursor(&self.render_target, icon, self.cursor_visible);
+            }
+            WindowCommand::SetCursorVisible(visible) => {
+                self.cursor_visible = visible;
+                cursor::set_cursor(&self.render_target, self.cursor_icon, visible);
+            }
+            WindowCommand::SetCursorGrab(mode) => {
+                self.cursor_grab.set(&self.window, mode, &self.send_event);
+            }
+            WindowCommand::SetUrgent(urgent) => attention::set_urgent(&self.window, urgent),
+            WindowCommand::DismissLoadingOverlay => {
+                if let Some(loading_overlay) = self.loading_overlay.take() {
+                    // let input through to the render target while fading out
+                    loading_overlay.set_can_target(false);
+                    loading_overlay.set_reveal_child(false);
                 }
-                WindowCommand::SetTitle(title) => {
-                    self.window.set_title(Some(&title));
+            }
+            WindowCommand::SetMinSize { width, height } => {
+                set_min_size(&self.render_target, width, height);
+            }
+            WindowCommand::ResetImportLimit => {
+                self.accepted_import_size = None;
+                self.import_failed = false;
+            }
+            WindowCommand::Close => {
+                self.closed.store(true, Ordering::SeqCst);
+                self.window.close();
+            }
+            WindowCommand::Batch(commands) => {
+                for command in commands {
+                    self.apply_command(command);
                 }
             }
         }
+    }
 
-        Ok(())
+    fn warp_cursor(&self, position: Vec2) {
+        if let Some(frame) = &self.current_frame {
+            cursor::warp_cursor(
+                &self.window,
+                &self.render_target,
+                frame.dmabuf.visible_size,
+                position,
+            );
+        }
     }
 }
 
+/// Spinner covering the render target until the app calls
+/// [`AdwaitaWindow::signal_ready`].
+///
+/// [`AdwaitaWindow::signal_ready`]: crate::AdwaitaWindow::signal_ready
+fn loading_overlay() -> gtk::Revealer {
+    let spinner = gtk::Spinner::builder()
+        .spinning(true)
+        .width_request(32)
+        .height_request(32)
+        .halign(gtk::Align::Center)
+        .valign(gtk::Align::Center)
+        .hexpand(true)
+        .vexpand(true)
+        .build();
+    // opaque, so that partially loaded frames don't show through
+    let background = gtk::Box::builder().css_classes(["view"]).build();
+    background.append(&spinner);
+
+    gtk::Revealer::builder()
+        .transition_type(gtk::RevealerTransitionType::Crossfade)
+        .reveal_child(true)
+        .child(&background)
+        .build()
+}
+
+const fn gtk_text_direction(direction: AdwaitaTextDirection) -> gtk::TextDirection {
+    match direction {
+        // falls back to the default direction, which GTK picks from the locale
+        AdwaitaTextDirection::System => gtk::TextDirection::None,
+        AdwaitaTextDirection::Ltr => gtk::TextDirection::Ltr,
+        AdwaitaTextDirection::Rtl => gtk::TextDirection::Rtl,
+    }
+}
+
+// widgets don't inherit the direction of their parent, so it's set on every widget
+// in the tree
+fn set_text_direction(widget: &gtk::Widget, direction: gtk::TextDirection) {
+    widget.set_direction(direction);
+    let mut child = widget.first_child();
+    while let Some(widget) = child {
+        set_text_direction(&widget, direction);
+        child = widget.next_sibling();
+    }
+}
+
+/// Sends a [`WindowEvent::StateChanged`] whenever the window is maximized,
+/// fullscreened or resized by the user.
+fn watch_window_state(window: &adw::Window, send_event: &flume::Sender<WindowEvent>) {
+    let send_state = {
+        let send_event = send_event.clone();
+        move |window: &adw::Window, _: &glib::ParamSpec| {
+            // GTK keeps the default size at the last size the window had while it
+            // wasn't maximized or fullscreen
+            let (width, height) = window.default_size();
+            _ = send_event.send(WindowEvent::StateChanged {
+                size: UVec2::new(
+                    u32::try_from(width).unwrap_or(0),
+                    u32::try_from(height).unwrap_or(0),
+                ),
+                maximized: window.is_maximized(),
+                fullscreen: window.is_fullscreen(),
+            });
+        }
+    };
+    for property in [
+        "maximized",
+        "fullscreened",
+        "default-width",
+        "default-height",
+    ] {
+        window.connect_notify_local(Some(property), send_state.clone());
+    }
+}
+
+fn maximize_to_fullscreen(window: &adw::Window, content: &gtk::Widget) {
+    // The maximize button and double-clicking the title bar both activate
+    // `window.toggle-maximized`. Actions are looked up from the activating widget
+    // upwards, so a group inserted on the content shadows the window's own actions
+    // for everything inside it, including the header bar.
+    let toggle_maximized = gio::SimpleAction::new("toggle-maximized", None);
+    toggle_maximized.connect_activate({
+        let window = window.downgrade();
+        move |_, _| {
+            if let Some(window) = window.upgrade() {
+                window.set_fullscreened(!window.is_fullscreen());
+            }
+        }
+    });
+    // the group replaces the whole `window` prefix, so forward the other
+    // actions used by the window controls
+    let minimize = gio::SimpleAction::new("minimize", None);
+    minimize.connect_activate({
+        let window = window.downgrade();
+        move |_, _| {
+            if let Some(window) = window.upgrade() {
+                window.minimize();
+            }
+        }
+    });
+    let close = gio::SimpleAction::new("close", None);
+    close.connect_activate({
+        let window = window.downgrade();
+        move |_, _| {
+            if let Some(window) = window.upgrade() {
+                window.close();
+            }
+        }
+    });
+
+    let actions = gio::SimpleActionGroup::new();
+    actions.add_action(&toggle_maximized);
+    actions.add_action(&minimize);
+    actions.add_action(&close);
+    content.insert_action_group("window", Some(&actions));
+}
+
+#[cfg(feature = "x11")]
+fn open_unfocused(window: &adw::Window) {
+    // a user time of 0 tells the window manager not to focus the window when it's
+    // mapped, and the surface only exists once the window is realized
+    window.connect_realize(|window| {
+        if let Some(surface) = window
+            .surface()
+            .and_then(|surface| surface.downcast::<gdk4_x11::X11Surface>().ok())
+        {
+            surface.set_user_time(0);
+        }
+    });
+    // unlike `present`, this doesn't request activation from the compositor
+    window.set_visible(true);
+}
+
+#[cfg(not(feature = "x11"))]
+fn open_unfocused(window: &adw::Window) {
+    // unlike `present`, this doesn't request activation from the compositor
+    window.set_visible(true);
+}
+
+#[cfg(feature = "x11")]
+fn request_workspace(window: &adw::Window, workspace: u32) {
+    // the window manager only moves windows which are already mapped
+    window.connect_map(move |window| {
+        let surface = window.surface();
+        if let Some(surface) = surface
+            .as_ref()
+            .and_then(|surface| surface.downcast_ref::<gdk4_x11::X11Surface>())
+        {
+            surface.move_to_desktop(workspace);
+        } else {
+            tracing::warn!("Cannot open window on workspace {workspace}, only supported on X11");
+        }
+    });
+}
+
+#[cfg(not(feature = "x11"))]
+fn request_workspace(_window: &adw::Window, workspace: u32) {
+    tracing::warn!(
+        "Cannot open window on workspace {workspace}, requires the `x11` feature and an X11 display"
+    );
+}
+
+// radius of the window corners in the default Adwaita stylesheet
+const WINDOW_RADIUS: u32 = 12;
+
+fn clip_rounded_corners(content: &gtk::Widget) {
+    content.set_overflow(gtk::Overflow::Hidden);
+    add_scoped_css(content, "rounded", |class| {
+        format!(
+            ".{class} {{ border-radius: {WINDOW_RADIUS}px; }}
+            window.maximized .{class}, window.fullscreen .{class}, window.tiled .{class},
+            window.tiled-top .{class}, window.tiled-bottom .{class},
+            window.tiled-left .{class}, window.tiled-right .{class} {{ border-radius: 0; }}"
+        )
+    });
+}
+
+// size of the icons in header bars and menus, in logical pixels
+const DEFAULT_ICON_SIZE: f32 = 16.0;
+
+fn set_chrome_text_scale(window: &adw::Window, scale: f32) {
+    // the window inherits the system font size, and Adwaita sizes all other text
+    // relative to it
+    let percent = scale * 100.0;
+    add_scoped_css(window, "text-scale", |class| {
+        format!("window.{class} {{ font-size: {percent}%; }}")
+    });
+}
+
+fn set_chrome_icon_scale(window: &adw::Window, scale: f32) {
+    let icon_size = (DEFAULT_ICON_SIZE * scale).round();
+    add_scoped_css(window, "icon-scale", |class| {
+        format!("window.{class} image {{ -gtk-icon-size: {icon_size}px; }}")
+    });
+}
+
+/// Adds CSS which only applies to `widget`, given the unique CSS class added to it.
+fn add_scoped_css(widget: &impl IsA<gtk::Widget>, name: &str, css: impl FnOnce(&str) -> String) {
+    // CSS providers apply to the whole display, so scope this one to the widget
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    let class = format!(
+        "bevy-mod-adwaita-{name}-{}",
+        NEXT_ID.fetch_add(1, Ordering::SeqCst)
+    );
+    widget.add_css_class(&class);
+
+    let provider = gtk::CssProvider::new();
+    provider.load_from_string(&css(&class));
+    gtk::style_context_add_provider_for_display(
+        &WidgetExt::display(widget),
+        &provider,
+        gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+    );
+}
+
 fn assert_i32(n: u32, value_name: &str) -> i32 {
     i32::try_from(n).unwrap_or_else(|_| panic!("{value_name} must fit into an `i32`, was {n}"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_queue_replaces_commands_of_the_same_kind() {
+        let queue = CommandQueue::default();
+        queue.push(WindowCommand::SetTitle("first".to_owned()));
+        queue.push(WindowCommand::SetMaximized(true));
+        queue.push(WindowCommand::SetTitle("second".to_owned()));
+
+        // the replacement is applied after the commands sent before it
+        let commands = queue.take();
+        assert_eq!(commands.len(), 2);
+        assert!(matches!(commands[0], WindowCommand::SetMaximized(true)));
+        assert!(matches!(&commands[1], WindowCommand::SetTitle(title) if title == "second"));
+    }
+
+    #[test]
+    fn command_queue_keeps_commands_of_other_kinds() {
+        let queue = CommandQueue::default();
+        queue.push(WindowCommand::SetMaximized(true));
+        queue.push(WindowCommand::SetFullscreen(true));
+        queue.push(WindowCommand::SetMaximized(false));
+
+        let commands = queue.take();
+        assert_eq!(commands.len(), 2);
+        assert!(matches!(commands[0], WindowCommand::SetFullscreen(true)));
+        assert!(matches!(commands[1], WindowCommand::SetMaximized(false)));
+    }
+
+    #[test]
+    fn command_queue_keeps_every_one_off_command() {
+        let queue = CommandQueue::default();
+        queue.push(WindowCommand::DismissLoadingOverlay);
+        queue.push(WindowCommand::SetTitle("title".to_owned()));
+        queue.push(WindowCommand::DismissLoadingOverlay);
+        queue.push(WindowCommand::Close);
+
+        let commands = queue.take();
+        assert_eq!(commands.len(), 4);
+        assert!(matches!(commands[0], WindowCommand::DismissLoadingOverlay));
+        assert!(matches!(commands[2], WindowCommand::DismissLoadingOverlay));
+        assert!(matches!(commands[3], WindowCommand::Close));
+    }
+
+    #[test]
+    fn command_queue_take_empties_queue() {
+        let queue = CommandQueue::default();
+        queue.push(WindowCommand::SetTitle("title".to_owned()));
+        assert_eq!(queue.take().len(), 1);
+        assert!(queue.take().is_empty());
+
+        // nothing of a taken command is left to coalesce with
+        queue.push(WindowCommand::SetTitle("title".to_owned()));
+        assert_eq!(queue.take().len(), 1);
+    }
+}
@@ -0,0 +1,140 @@
+use adw::prelude::*;
+
+/// Asks the desktop to draw the user's attention to `window`, such as by flashing
+/// its taskbar entry, or to stop if not `urgent`.
+///
+/// | Backend | Support |
+/// |---------|---------|
+/// | X11     | Requires the `x11` feature, sets the urgency hint |
+/// | Wayland | Requires the `wayland` feature, and a compositor supporting `xdg_activation_v1` |
+///
+/// Wayland has no way to take back a request, so compositors stop drawing
+/// attention to the window once it's focused. A focused window already has the
+/// user's attention, so requests for it are ignored.
+pub(crate) fn set_urgent(window: &adw::Window, urgent: bool) {
+    if urgent && window.is_active() {
+        return;
+    }
+    let Some(surface) = window.surface() else {
+        tracing::debug!("Cannot request attention, window is not realized yet");
+        return;
+    };
+
+    #[cfg(feature = "x11")]
+    if let Some(surface) = surface.downcast_ref::<gdk4_x11::X11Surface>() {
+        surface.set_urgency_hint(urgent);
+        return;
+    }
+
+    #[cfg(feature = "wayland")]
+    if crate::wayland::is_wayland(&surface.display()) {
+        if urgent {
+            if let Err(err) = wayland::request_activation(&surface) {
+                tracing::debug!("Cannot request attention: {err}");
+            }
+        }
+        return;
+    }
+    #[cfg(not(any(feature = "x11", feature = "wayland")))]
+    let _ = surface;
+
+    if urgent {
+        tracing::debug!(
+            "Cannot request attention, this is only supported on X11 with the `x11` feature, \
+             and on Wayland with the `wayland` feature"
+        );
+    }
+}
+
+#[cfg(feature = "wayland")]
+mod wayland {
+    use adw::gdk;
+    use adw::prelude::*;
+    use wayland_client::{
+        globals::{registry_queue_init, BindError, GlobalError, GlobalListContents},
+        protocol::wl_registry::WlRegistry,
+        Connection, Dispatch, DispatchError, Proxy, QueueHandle,
+    };
+    use wayland_protocols::xdg::activation::v1::client::{
+        xdg_activation_token_v1::{self, XdgActivationTokenV1},
+        xdg_activation_v1::XdgActivationV1,
+    };
+
+    #[derive(Debug, thiserror::Error)]
+    pub enum ActivationError {
+        #[error("window has no Wayland surface")]
+        NoSurface,
+        #[error("failed to list Wayland globals")]
+        Globals(#[from] GlobalError),
+        #[error("compositor doesn't support `xdg_activation_v1`")]
+        Unsupported(#[from] BindError),
+        #[error("failed to get an activation token")]
+        Dispatch(#[from] DispatchError),
+        #[error("compositor didn't send an activation token")]
+        NoToken,
+    }
+
+    #[derive(Debug, Default)]
+    struct State {
+        token: Option<String>,
+    }
+
+    /// Activates `surface` with a token that isn't tied to any user input, which
+    /// compositors take as a request for attention rather than focusing the window.
+    pub fn request_activation(surface: &gdk::Surface) -> Result<(), ActivationError> {
+        let conn =
+            crate::wayland::connection(&surface.display()).ok_or(ActivationError::NoSurface)?;
+        let wl_surface =
+            crate::wayland::surface(&conn, surface).ok_or(ActivationError::NoSurface)?;
+        let (globals, mut queue) = registry_queue_init::<State>(&conn)?;
+        let activation = globals.bind::<XdgActivationV1, _, _>(&queue.handle(), 1..=1, ())?;
+
+        let token = activation.get_activation_token(&queue.handle(), ());
+        token.set_surface(&wl_surface);
+        token.commit();
+        let mut state = State::default();
+        // compositors answer as soon as the token is committed, and a roundtrip
+        // only blocks for a moment
+        queue.roundtrip(&mut state)?;
+        token.destroy();
+        let result = match state.token {
+            Some(token) => {
+                activation.activate(token, &wl_surface);
+                Ok(())
+            }
+            None => Err(ActivationError::NoToken),
+        };
+        activation.destroy();
+        _ = queue.flush();
+        result
+    }
+
+    impl Dispatch<WlRegistry, GlobalListContents> for State {
+        fn event(
+            _: &mut Self,
+            _: &WlRegistry,
+            _: <WlRegistry as Proxy>::Event,
+            _: &GlobalListContents,
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    impl Dispatch<XdgActivationTokenV1, ()> for State {
+        fn event(
+            state: &mut Self,
+            _: &XdgActivationTokenV1,
+            event: xdg_activation_token_v1::Event,
+            _: &(),
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+            if let xdg_activation_token_v1::Event::Done { token } = event {
+                state.token = Some(token);
+            }
+        }
+    }
+
+    wayland_client::delegate_noop!(State: XdgActivationV1);
+}
@@ -0,0 +1,221 @@
+use std::sync::{atomic::Ordering, Arc, Mutex};
+
+use bevy::{
+    color::ColorToPacked,
+    prelude::*,
+    render::{
+        render_resource::Buffer,
+        renderer::{RenderDevice, RenderQueue},
+    },
+};
+
+use crate::{render::AdwaitaRenderTargetFormat, AdwaitaWindow, RenderWindow};
+
+/// Pixels of a frame rendered to an [`AdwaitaWindow`], in response to
+/// [`AdwaitaWindow::capture_frame`].
+///
+/// [`AdwaitaWindow`]: crate::AdwaitaWindow
+/// [`AdwaitaWindow::capture_frame`]: crate::AdwaitaWindow::capture_frame
+#[derive(Debug, Clone, PartialEq, Eq, Event, Reflect)]
+pub struct AdwaitaFrameCaptured {
+    /// Entity of the [`AdwaitaWindow`] the frame was rendered to.
+    ///
+    /// [`AdwaitaWindow`]: crate::AdwaitaWindow
+    pub window: Entity,
+    /// Width of the frame in physical pixels.
+    pub width: u32,
+    pub height: u32,
+    /// RGBA8 pixels of the frame, row by row from the top left.
+    ///
+    /// Colors are stored the same way as in the render target, so they're
+    /// sRGB-encoded for [`AdwaitaRenderTargetFormat::Srgb`], and linear for
+    /// [`AdwaitaRenderTargetFormat::Linear`]. Frames rendered in
+    /// [`AdwaitaRenderTargetFormat::Float16`] are encoded to sRGB, with values outside
+    /// of `0.0..=1.0` clamped.
+    pub data: Vec<u8>,
+}
+
+/// Frames read on the render thread, waiting to be sent as events.
+pub(crate) type SharedFrameCaptures = Arc<Mutex<Vec<(UVec2, Vec<u8>)>>>;
+
+pub(crate) fn send_frame_captures(
+    windows: Query<(Entity, &AdwaitaWindow)>,
+    mut events: EventWriter<AdwaitaFrameCaptured>,
+) {
+    for (entity, window) in &windows {
+        let captures = std::mem::take(&mut *window.frame_captures.lock().expect("poisoned"));
+        events.send_batch(
+            captures
+                .into_iter()
+                .map(|(size, data)| AdwaitaFrameCaptured {
+                    window: entity,
+                    width: size.x,
+                    height: size.y,
+                    data,
+                }),
+        );
+    }
+}
+
+struct PendingCapture {
+    buffer: Buffer,
+    size: UVec2,
+    format: AdwaitaRenderTargetFormat,
+    padded_bytes_per_row: u32,
+    mapped: flume::Receiver<Result<(), wgpu::BufferAsyncError>>,
+    results: SharedFrameCaptures,
+}
+
+/// Captures waiting for the GPU to finish copying.
+#[derive(Default, Resource)]
+pub(crate) struct PendingFrameCaptures(Vec<PendingCapture>);
+
+/// Copies the frames that windows requested captures of into buffers, which are
+/// read by [`finish_frame_captures`] once the GPU is done.
+///
+/// Must run after the frame is rendered, and before it's sent to GTK.
+pub(crate) fn copy_frame_captures(
+    windows: Query<&RenderWindow>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut pending: ResMut<PendingFrameCaptures>,
+) {
+    for window in &windows {
+        // requests stay pending until a frame is actually rendered
+        if window.skip_frame {
+            continue;
+        }
+        let Some(frame) = &window.next_frame_to_send else {
+            continue;
+        };
+        if !window.capture_requested.swap(false, Ordering::SeqCst) {
+            continue;
+        }
+
+        let size = frame.dmabuf.visible_size;
+        let format = frame.dmabuf.format;
+        let texture = &frame.texture;
+        let bytes_per_pixel = texture
+            .format()
+            .block_copy_size(None)
+            .expect("render target formats are copyable");
+        let padded_bytes_per_row =
+            (size.x * bytes_per_pixel).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = render_device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("adwaita_frame_capture"),
+            size: u64::from(padded_bytes_per_row * size.y),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = render_device.create_command_encoder(&default());
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+        );
+        render_queue.submit([encoder.finish()]);
+
+        // mapping only starts once the copy is submitted
+        let (send_mapped, mapped) = flume::bounded(1);
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                _ = send_mapped.send(result);
+            });
+        pending.0.push(PendingCapture {
+            buffer,
+            size,
+            format,
+            padded_bytes_per_row,
+            mapped,
+            results: window.frame_captures.clone(),
+        });
+    }
+}
+
+/// Reads the buffers of captures which the GPU has finished copying.
+pub(crate) fn finish_frame_captures(
+    render_device: Res<RenderDevice>,
+    mut pending: ResMut<PendingFrameCaptures>,
+) {
+    if pending.0.is_empty() {
+        return;
+    }
+    // runs the map callbacks of finished copies
+    render_device.wgpu_device().poll(wgpu::Maintain::Poll);
+
+    pending.0.retain(|capture| {
+        let result = match capture.mapped.try_recv() {
+            Ok(result) => result,
+            Err(flume::TryRecvError::Empty) => return true,
+            Err(flume::TryRecvError::Disconnected) => return false,
+        };
+        if let Err(err) = result {
+            warn!("Failed to capture frame: {err}");
+            return false;
+        }
+
+        let data = {
+            let mapped = capture.buffer.slice(..).get_mapped_range();
+            mapped
+                .chunks_exact(capture.padded_bytes_per_row as usize)
+                .flat_map(|row| rgba8_row(row, capture.size.x, capture.format))
+                .collect::<Vec<_>>()
+        };
+        capture.buffer.unmap();
+        capture
+            .results
+            .lock()
+            .expect("poisoned")
+            .push((capture.size, data));
+        false
+    });
+}
+
+/// Converts the first `width` pixels of a row in `format` to RGBA8.
+fn rgba8_row(row: &[u8], width: u32, format: AdwaitaRenderTargetFormat) -> Vec<u8> {
+    let width = width as usize;
+    match format {
+        AdwaitaRenderTargetFormat::Srgb | AdwaitaRenderTargetFormat::Linear => {
+            row[..width * 4].to_vec()
+        }
+        AdwaitaRenderTargetFormat::BgraSrgb => row[..width * 4]
+            .chunks_exact(4)
+            .flat_map(|bgra| [bgra[2], bgra[1], bgra[0], bgra[3]])
+            .collect(),
+        AdwaitaRenderTargetFormat::Float16 => row[..width * 8]
+            .chunks_exact(8)
+            .flat_map(|pixel| {
+                let [r, g, b, a] =
+                    [0, 2, 4, 6].map(|i| f16_to_f32(u16::from_le_bytes([pixel[i], pixel[i + 1]])));
+                Srgba::from(LinearRgba::new(r, g, b, a)).to_u8_array()
+            })
+            .collect(),
+    }
+}
+
+// not worth a dependency for the one place we read half floats
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 == 0 { 1.0 } else { -1.0 };
+    let exponent = i32::from((bits >> 10) & 0x1f);
+    let mantissa = f32::from(bits & 0x3ff);
+    match exponent {
+        // subnormal
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
@@ -0,0 +1,42 @@
+use adw::gio;
+use adw::prelude::*;
+use bevy::prelude::*;
+
+use crate::adwaita_app::WindowEvent;
+
+/// Sent in response to [`AdwaitaWindow::request_clipboard_text`].
+///
+/// [`AdwaitaWindow::request_clipboard_text`]: crate::AdwaitaWindow::request_clipboard_text
+#[derive(Debug, Clone, PartialEq, Eq, Event, Reflect)]
+pub struct AdwaitaClipboardText {
+    /// Entity of the [`AdwaitaWindow`] the clipboard was read from.
+    ///
+    /// [`AdwaitaWindow`]: crate::AdwaitaWindow
+    pub window: Entity,
+    /// Text on the clipboard, or [`None`] if it's empty or holds something other
+    /// than text, such as an image.
+    pub text: Option<String>,
+}
+
+pub(crate) fn set_clipboard_text(window: &adw::Window, text: &str) {
+    window.clipboard().set_text(text);
+}
+
+/// Reads the clipboard of the display `window` is on, sending a
+/// [`WindowEvent::ClipboardText`] once its content has been received.
+pub(crate) fn request_clipboard_text(window: &adw::Window, send_event: flume::Sender<WindowEvent>) {
+    // the content may come from another app, so this can't be read synchronously
+    window
+        .clipboard()
+        .read_text_async(None::<&gio::Cancellable>, move |result| {
+            let text = match result {
+                Ok(text) => text.map(String::from),
+                Err(err) => {
+                    // also the case if there's no text representation of the content
+                    tracing::debug!("Failed to read clipboard text: {err}");
+                    None
+                }
+            };
+            _ = send_event.send(WindowEvent::ClipboardText(text));
+        });
+}
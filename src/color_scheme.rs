@@ -0,0 +1,67 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+
+/// Whether the app is currently shown in dark mode.
+///
+/// This follows the user's light/dark preference from the desktop's appearance
+/// settings, unless overridden with [`AdwaitaWindow::set_color_scheme`], and is
+/// updated whenever either changes.
+///
+/// [`AdwaitaWindow::set_color_scheme`]: crate::AdwaitaWindow::set_color_scheme
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource, Reflect)]
+#[reflect(Resource, Default)]
+pub struct AdwaitaColorScheme {
+    pub dark: bool,
+}
+
+/// Which color scheme the app asks libadwaita for, mirroring [`adw::ColorScheme`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Default)]
+pub enum AdwaitaColorSchemePreference {
+    /// Same as [`AdwaitaColorSchemePreference::PreferLight`].
+    #[default]
+    Default,
+    /// Always light.
+    ForceLight,
+    /// Follows the user's preference, and is light if they have none.
+    PreferLight,
+    /// Follows the user's preference, and is dark if they have none.
+    PreferDark,
+    /// Always dark.
+    ForceDark,
+}
+
+impl AdwaitaColorSchemePreference {
+    pub(crate) fn adw(self) -> adw::ColorScheme {
+        match self {
+            Self::Default => adw::ColorScheme::Default,
+            Self::ForceLight => adw::ColorScheme::ForceLight,
+            Self::PreferLight => adw::ColorScheme::PreferLight,
+            Self::PreferDark => adw::ColorScheme::PreferDark,
+            Self::ForceDark => adw::ColorScheme::ForceDark,
+        }
+    }
+}
+
+/// Dark mode read on the GTK thread, if it changed since Bevy last read it.
+#[derive(Debug, Clone, Default, Resource, Deref)]
+pub(crate) struct SharedColorScheme(Arc<Mutex<Option<bool>>>);
+
+/// Must be called on the GTK thread after Adwaita is initialized.
+pub(crate) fn watch_color_scheme(shared: SharedColorScheme) {
+    let style_manager = adw::StyleManager::default();
+    *shared.lock().expect("poisoned") = Some(style_manager.is_dark());
+    style_manager.connect_dark_notify(move |style_manager| {
+        *shared.lock().expect("poisoned") = Some(style_manager.is_dark());
+    });
+}
+
+pub(crate) fn sync_color_scheme(
+    shared: Res<SharedColorScheme>,
+    mut color_scheme: ResMut<AdwaitaColorScheme>,
+) {
+    if let Some(dark) = shared.lock().expect("poisoned").take() {
+        color_scheme.set_if_neq(AdwaitaColorScheme { dark });
+    }
+}
@@ -0,0 +1,230 @@
+use adw::prelude::*;
+use adw::{gdk, gtk};
+use bevy::math::{UVec2, Vec2};
+use bevy::window::CursorIcon;
+
+/// Shows `icon` while the pointer is over `widget`, or no cursor at all if not
+/// `visible`.
+pub fn set_cursor(widget: &impl IsA<gtk::Widget>, icon: CursorIcon, visible: bool) {
+    if !visible {
+        widget.set_cursor_from_name(Some("none"));
+        return;
+    }
+    let Some(name) = cursor_name(icon) else {
+        widget.set_cursor(None);
+        return;
+    };
+    // cursor themes don't have to provide every cursor
+    let fallback = gdk::Cursor::from_name("default", None);
+    let cursor = gdk::Cursor::from_name(name, fallback.as_ref());
+    widget.set_cursor(cursor.as_ref());
+}
+
+/// Name of the GDK cursor matching `icon`, or [`None`] for the default cursor.
+///
+/// Both use the cursor names from the CSS spec.
+fn cursor_name(icon: CursorIcon) -> Option<&'static str> {
+    Some(match icon {
+        CursorIcon::Default => return None,
+        CursorIcon::ContextMenu => "context-menu",
+        CursorIcon::Help => "help",
+        CursorIcon::Pointer => "pointer",
+        CursorIcon::Progress => "progress",
+        CursorIcon::Wait => "wait",
+        CursorIcon::Cell => "cell",
+        CursorIcon::Crosshair => "crosshair",
+        CursorIcon::Text => "text",
+        CursorIcon::VerticalText => "vertical-text",
+        CursorIcon::Alias => "alias",
+        CursorIcon::Copy => "copy",
+        CursorIcon::Move => "move",
+        CursorIcon::NoDrop => "no-drop",
+        CursorIcon::NotAllowed => "not-allowed",
+        CursorIcon::Grab => "grab",
+        CursorIcon::Grabbing => "grabbing",
+        CursorIcon::EResize => "e-resize",
+        CursorIcon::NResize => "n-resize",
+        CursorIcon::NeResize => "ne-resize",
+        CursorIcon::NwResize => "nw-resize",
+        CursorIcon::SResize => "s-resize",
+        CursorIcon::SeResize => "se-resize",
+        CursorIcon::SwResize => "sw-resize",
+        CursorIcon::WResize => "w-resize",
+        CursorIcon::EwResize => "ew-resize",
+        CursorIcon::NsResize => "ns-resize",
+        CursorIcon::NeswResize => "nesw-resize",
+        CursorIcon::NwseResize => "nwse-resize",
+        CursorIcon::ColResize => "col-resize",
+        CursorIcon::RowResize => "row-resize",
+        CursorIcon::AllScroll => "all-scroll",
+        CursorIcon::ZoomIn => "zoom-in",
+        CursorIcon::ZoomOut => "zoom-out",
+    })
+}
+
+/// Moves the pointer to `position` in the render target of `window`.
+///
+/// `position` is in physical pixels of a render target of `frame_size`, which is
+/// mapped to the area of `render_target` that the frame is actually drawn in.
+pub fn warp_cursor(
+    window: &adw::Window,
+    render_target: &gtk::Picture,
+    frame_size: UVec2,
+    position: Vec2,
+) {
+    let Some(surface) = window.surface() else {
+        tracing::warn!("Cannot warp cursor, window is not realized yet");
+        return;
+    };
+    let widget_size = Vec2::new(render_target.width() as f32, render_target.height() as f32);
+    let Some(point) = widget_position(widget_size, frame_size, position)
+        .and_then(|point| surface_point(window, render_target, point))
+    else {
+        tracing::warn!("Cannot warp cursor, render target is not visible");
+        return;
+    };
+    warp_in_surface(&surface, point);
+}
+
+/// Moves the pointer to `point`, in logical coordinates of `widget`.
+///
+/// Does nothing if the widget isn't shown, or if warping isn't supported, as told
+/// by [`can_warp`].
+pub fn warp_in_widget(widget: &gtk::Widget, point: Vec2) {
+    let Some(native) = widget.native() else {
+        return;
+    };
+    let Some(surface) = native.surface() else {
+        return;
+    };
+    if can_warp(&surface) {
+        if let Some(point) = surface_point(&native, widget, point) {
+            warp_in_surface(&surface, point);
+        }
+    }
+}
+
+/// Whether the pointer can be moved by [`warp_cursor`] in `surface`.
+#[cfg(feature = "x11")]
+pub fn can_warp(surface: &gdk::Surface) -> bool {
+    surface.is::<gdk4_x11::X11Surface>()
+}
+
+/// Whether the pointer can be moved by [`warp_cursor`] in `surface`.
+#[cfg(not(feature = "x11"))]
+pub fn can_warp(_surface: &gdk::Surface) -> bool {
+    false
+}
+
+/// Maps `point`, in logical coordinates of a render target widget of `widget_size`,
+/// to physical pixels of the frame of `frame_size` drawn in it.
+///
+/// Points over the letterboxing around the frame are clamped to its edges.
+pub fn frame_position(widget_size: Vec2, frame_size: UVec2, point: Vec2) -> Option<Vec2> {
+    let (scale, offset) = frame_placement(widget_size, frame_size)?;
+    Some(((point - offset) / scale).clamp(Vec2::ZERO, frame_size.as_vec2()))
+}
+
+/// Maps `point`, in physical pixels of a frame of `frame_size`, to logical
+/// coordinates of the render target widget of `widget_size` it's drawn in.
+///
+/// This is the inverse of [`frame_position`].
+pub fn widget_position(widget_size: Vec2, frame_size: UVec2, point: Vec2) -> Option<Vec2> {
+    let (scale, offset) = frame_placement(widget_size, frame_size)?;
+    Some(offset + point.clamp(Vec2::ZERO, frame_size.as_vec2()) * scale)
+}
+
+// the picture scales the frame to fit, keeping its aspect ratio
+fn frame_placement(widget_size: Vec2, frame_size: UVec2) -> Option<(f32, Vec2)> {
+    let frame_size = frame_size.as_vec2();
+    if frame_size.cmple(Vec2::ZERO).any() || widget_size.cmple(Vec2::ZERO).any() {
+        return None;
+    }
+    let scale = (widget_size / frame_size).min_element();
+    let offset = (widget_size - frame_size * scale) / 2.0;
+    Some((scale, offset))
+}
+
+// converts from logical coordinates in `widget` to logical coordinates in the
+// surface of `native`
+fn surface_point(
+    native: &(impl IsA<gtk::Native> + IsA<gtk::Widget>),
+    widget: &impl IsA<gtk::Widget>,
+    point: Vec2,
+) -> Option<Vec2> {
+    let point = widget.compute_point(native, &gtk::graphene::Point::new(point.x, point.y))?;
+    // the surface also contains the client-side shadows around the window
+    let (surface_x, surface_y) = native.surface_transform();
+    Some(Vec2::new(
+        point.x() + surface_x as f32,
+        point.y() + surface_y as f32,
+    ))
+}
+
+#[cfg(feature = "x11")]
+fn warp_in_surface(surface: &gdk::Surface, point: Vec2) {
+    use std::os::raw::{c_int, c_uint, c_ulong, c_void};
+
+    use adw::glib::translate::ToGlibPtr;
+
+    #[link(name = "X11")]
+    extern "C" {
+        fn XWarpPointer(
+            display: *mut c_void,
+            src_w: c_ulong,
+            dest_w: c_ulong,
+            src_x: c_int,
+            src_y: c_int,
+            src_width: c_uint,
+            src_height: c_uint,
+            dest_x: c_int,
+            dest_y: c_int,
+        ) -> c_int;
+        fn XFlush(display: *mut c_void) -> c_int;
+    }
+
+    let Some(x11_surface) = surface.downcast_ref::<gdk4_x11::X11Surface>() else {
+        unsupported();
+        return;
+    };
+    let Some(display) = x11_surface
+        .display()
+        .downcast::<gdk4_x11::X11Display>()
+        .ok()
+    else {
+        unsupported();
+        return;
+    };
+
+    // X11 works in device pixels
+    let point = (point * surface.scale() as f32).round();
+    // SAFETY: the display and window are owned by GDK, and stay alive for the
+    // duration of this call
+    unsafe {
+        let xdisplay =
+            gdk4_x11::ffi::gdk_x11_display_get_xdisplay(display.to_glib_none().0).cast::<c_void>();
+        XWarpPointer(
+            xdisplay,
+            0,
+            x11_surface.xid(),
+            0,
+            0,
+            0,
+            0,
+            point.x as c_int,
+            point.y as c_int,
+        );
+        XFlush(xdisplay);
+    }
+}
+
+#[cfg(not(feature = "x11"))]
+fn warp_in_surface(_surface: &gdk::Surface, _point: Vec2) {
+    unsupported();
+}
+
+fn unsupported() {
+    tracing::warn!(
+        "Cannot warp cursor, this is only supported on X11 with the `x11` feature enabled"
+    );
+}
@@ -0,0 +1,294 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    prelude::*,
+    render::{
+        camera::{ExtractedCamera, ManualTextureViewHandle, NormalizedRenderTarget},
+        render_resource::{Buffer, TextureFormat},
+        renderer::{RenderDevice, RenderQueue},
+        view::ViewDepthTexture,
+    },
+};
+
+use crate::{AdwaitaWindow, RenderWindow};
+
+/// Depth values read back from the depth buffer of an [`AdwaitaWindow`], in
+/// response to [`AdwaitaWindow::read_depth`].
+///
+/// [`AdwaitaWindow`]: crate::AdwaitaWindow
+/// [`AdwaitaWindow::read_depth`]: crate::AdwaitaWindow::read_depth
+#[derive(Debug, Clone, PartialEq, Event, Reflect)]
+pub struct AdwaitaDepthReadback {
+    /// Entity of the [`AdwaitaWindow`] the depth was read from.
+    ///
+    /// [`AdwaitaWindow`]: crate::AdwaitaWindow
+    pub window: Entity,
+    /// Region of the render target that was read, in physical pixels.
+    ///
+    /// This may be smaller than the requested region, if it extended past the edge
+    /// of the depth buffer.
+    pub rect: URect,
+    /// Depth of every pixel in [`rect`](Self::rect), row by row from the top left.
+    ///
+    /// Values are normalized to `0.0..=1.0`. Bevy uses a reversed depth range, so
+    /// `1.0` is on the near plane and `0.0` is infinitely far away.
+    pub depth: Vec<f32>,
+}
+
+/// Depth read on the render thread, waiting to be sent as events.
+pub(crate) type SharedDepthReadbacks = Arc<Mutex<Vec<(URect, Vec<f32>)>>>;
+
+pub(crate) fn send_depth_readbacks(
+    windows: Query<(Entity, &AdwaitaWindow)>,
+    mut events: EventWriter<AdwaitaDepthReadback>,
+) {
+    for (entity, window) in &windows {
+        let readbacks = std::mem::take(&mut *window.depth_readbacks.lock().expect("poisoned"));
+        events.send_batch(
+            readbacks
+                .into_iter()
+                .map(|(rect, depth)| AdwaitaDepthReadback {
+                    window: entity,
+                    rect,
+                    depth,
+                }),
+        );
+    }
+}
+
+struct PendingReadback {
+    buffer: Buffer,
+    rect: URect,
+    padded_bytes_per_row: u32,
+    mapped: flume::Receiver<Result<(), wgpu::BufferAsyncError>>,
+    results: SharedDepthReadbacks,
+}
+
+/// Readbacks waiting for the GPU to finish copying.
+#[derive(Default, Resource)]
+pub(crate) struct PendingDepthReadbacks(Vec<PendingReadback>);
+
+// only this format can be copied out of a depth texture directly
+const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+
+/// Copies the depth buffer regions requested by windows into buffers, which are
+/// read by [`finish_depth_readbacks`] once the GPU is done.
+pub(crate) fn copy_depth_readbacks(
+    mut windows: Query<&mut RenderWindow>,
+    views: Query<(&ExtractedCamera, &ViewDepthTexture)>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut pending: ResMut<PendingDepthReadbacks>,
+) {
+    for mut window in &mut windows {
+        if window.depth_requests.is_empty() {
+            continue;
+        }
+        let rects = std::mem::take(&mut window.depth_requests);
+        let render_target = window.render_target_handle;
+
+        // the camera drawn last has the depth of what's actually on screen
+        let view = views
+            .iter()
+            .filter(|(camera, _)| is_target(camera, render_target))
+            .max_by_key(|(camera, _)| camera.order);
+        let Some((_, depth)) = view else {
+            warn!("Cannot read depth, no camera with a depth buffer renders to this window");
+            continue;
+        };
+        let texture = &depth.texture;
+        if texture.format() != DEPTH_FORMAT
+            || texture.sample_count() != 1
+            || !texture.usage().contains(wgpu::TextureUsages::COPY_SRC)
+        {
+            warn!(
+                "Cannot read depth, the depth buffer must be {DEPTH_FORMAT:?} without MSAA, and \
+                 include `TextureUsages::COPY_SRC` in `Camera3d::depth_texture_usages`"
+            );
+            continue;
+        }
+
+        let size = texture.size();
+        let bounds = URect::new(0, 0, size.width, size.height);
+        let mut encoder = render_device.create_command_encoder(&default());
+        let mut readbacks = Vec::new();
+        for rect in rects {
+            let rect = rect.intersect(bounds);
+            if rect.is_empty() {
+                continue;
+            }
+            let unpadded_bytes_per_row = rect.width() * 4;
+            let padded_bytes_per_row =
+                unpadded_bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+            let buffer = render_device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("adwaita_depth_readback"),
+                size: u64::from(padded_bytes_per_row * rect.height()),
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            });
+            encoder.copy_texture_to_buffer(
+                wgpu::ImageCopyTexture {
+                    texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: rect.min.x,
+                        y: rect.min.y,
+                        z: 0,
+                    },
+                    aspect: wgpu::TextureAspect::DepthOnly,
+                },
+                wgpu::ImageCopyBuffer {
+                    buffer: &buffer,
+                    layout: wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(padded_bytes_per_row),
+                        rows_per_image: None,
+                    },
+                },
+                wgpu::Extent3d {
+                    width: rect.width(),
+                    height: rect.height(),
+                    depth_or_array_layers: 1,
+                },
+            );
+
+            readbacks.push((buffer, rect, padded_bytes_per_row));
+        }
+        render_queue.submit([encoder.finish()]);
+
+        // a buffer can't be submitted while it's being mapped, so the mapping is only
+        // requested once the copy into it is submitted
+        for (buffer, rect, padded_bytes_per_row) in readbacks {
+            let (send_mapped, mapped) = flume::bounded(1);
+            buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    _ = send_mapped.send(result);
+                });
+            pending.0.push(PendingReadback {
+                buffer,
+                rect,
+                padded_bytes_per_row,
+                mapped,
+                results: window.depth_readbacks.clone(),
+            });
+        }
+    }
+}
+
+fn is_target(camera: &ExtractedCamera, render_target: ManualTextureViewHandle) -> bool {
+    matches!(
+        camera.target,
+        Some(NormalizedRenderTarget::TextureView(target)) if target == render_target
+    )
+}
+
+/// Reads the buffers of readbacks which the GPU has finished copying.
+pub(crate) fn finish_depth_readbacks(
+    render_device: Res<RenderDevice>,
+    mut pending: ResMut<PendingDepthReadbacks>,
+) {
+    if pending.0.is_empty() {
+        return;
+    }
+    // runs the map callbacks of finished copies
+    render_device.wgpu_device().poll(wgpu::Maintain::Poll);
+
+    pending.0.retain(|readback| {
+        let result = match readback.mapped.try_recv() {
+            Ok(result) => result,
+            Err(flume::TryRecvError::Empty) => return true,
+            Err(flume::TryRecvError::Disconnected) => return false,
+        };
+        if let Err(err) = result {
+            warn!("Failed to read depth buffer: {err}");
+            return false;
+        }
+
+        let rect = readback.rect;
+        let depth = {
+            let data = readback.buffer.slice(..).get_mapped_range();
+            data.chunks_exact(readback.padded_bytes_per_row as usize)
+                .flat_map(|row| {
+                    row[..(rect.width() * 4) as usize]
+                        .chunks_exact(4)
+                        .map(|bytes| f32::from_le_bytes(bytes.try_into().expect("4 bytes")))
+                })
+                .collect::<Vec<_>>()
+        };
+        readback.buffer.unmap();
+        readback
+            .results
+            .lock()
+            .expect("poisoned")
+            .push((rect, depth));
+        false
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+    use crate::tests::open_windows;
+
+    #[test]
+    fn depth_requests_stay_with_their_window() {
+        let mut world = World::new();
+        let windows = open_windows(&mut world, 2);
+        let rect = URect::new(10, 20, 30, 40);
+        world
+            .get::<AdwaitaWindow>(windows[0].0)
+            .unwrap()
+            .read_depth(rect);
+
+        let requests = |entity| {
+            world
+                .get::<AdwaitaWindow>(entity)
+                .unwrap()
+                .depth_requests
+                .lock()
+                .expect("poisoned")
+                .clone()
+        };
+        assert_eq!(requests(windows[0].0), [rect]);
+        assert!(requests(windows[1].0).is_empty());
+    }
+
+    #[test]
+    fn depth_readbacks_are_sent_for_their_window() {
+        let mut world = World::new();
+        world.init_resource::<Events<AdwaitaDepthReadback>>();
+        let windows = open_windows(&mut world, 2);
+        let rect = URect::new(0, 0, 2, 1);
+        world
+            .get::<AdwaitaWindow>(windows[1].0)
+            .unwrap()
+            .depth_readbacks
+            .lock()
+            .expect("poisoned")
+            .push((rect, vec![0.25, 1.0]));
+
+        world.run_system_once(send_depth_readbacks);
+        let events = world.resource::<Events<AdwaitaDepthReadback>>();
+        let readbacks = events
+            .get_reader()
+            .read(events)
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            readbacks,
+            [AdwaitaDepthReadback {
+                window: windows[1].0,
+                rect,
+                depth: vec![0.25, 1.0],
+            }]
+        );
+
+        // each readback is only sent once
+        world.run_system_once(send_depth_readbacks);
+        let events = world.resource::<Events<AdwaitaDepthReadback>>();
+        assert_eq!(events.len(), 1);
+    }
+}
@@ -0,0 +1,564 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+use bevy::{
+    ecs::schedule::InternedScheduleLabel,
+    prelude::*,
+    render::{
+        renderer::{render_system, RenderAdapter, RenderAdapterInfo, RenderDevice, RenderQueue},
+        Render, RenderApp, RenderSet,
+    },
+};
+use wgpu_hal::vulkan;
+
+use crate::{
+    render, AdwaitaDrmNode, AdwaitaRenderTargetFormat, AdwaitaSet, AdwaitaWindow, SharedDisplay,
+};
+
+/// Number of measured frames that [`AdwaitaRenderStats::avg_gpu_frame_time`] is averaged over.
+pub const GPU_FRAME_TIME_SAMPLES: usize = 32;
+
+/// Rendering statistics of the Adwaita render pipeline.
+///
+/// GPU timings are only measured if [`wgpu::Features::TIMESTAMP_QUERY`] was requested
+/// in the [`WgpuSettings`] passed to [`AdwaitaWindowPlugin::render_plugin`], and the
+/// adapter supports it. Otherwise, they are always [`None`].
+///
+/// [`WgpuSettings`]: bevy::render::settings::WgpuSettings
+/// [`AdwaitaWindowPlugin::render_plugin`]: crate::AdwaitaWindowPlugin::render_plugin
+#[derive(Debug, Clone, Default, Resource, Reflect)]
+#[reflect(Default, Resource)]
+pub struct AdwaitaRenderStats {
+    pub gpu_timing_supported: bool,
+    pub last_gpu_frame_time: Option<Duration>,
+    pub avg_gpu_frame_time: Option<Duration>,
+}
+
+/// Information on which GPU adapter the app is rendering with, and whether it's
+/// the one the display is connected to.
+///
+/// If the adapter doesn't match the display, the compositor may be unable to
+/// import the rendered frames, resulting in a black window. The adapter is chosen
+/// when creating the renderer in [`AdwaitaWindowPlugin::render_plugin`], so make
+/// sure that you use it instead of creating the renderer yourself.
+///
+/// [`AdwaitaWindowPlugin::render_plugin`]: crate::AdwaitaWindowPlugin::render_plugin
+#[derive(Debug, Clone, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct AdwaitaAdapterDiagnostics {
+    pub adapter_name: String,
+    pub adapter_type: AdwaitaAdapterType,
+    pub adapter_drm_node: Option<AdwaitaDrmNode>,
+    pub display_drm_node: Option<AdwaitaDrmNode>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum AdwaitaAdapterType {
+    DiscreteGpu,
+    IntegratedGpu,
+    VirtualGpu,
+    /// Software rasterizer, such as llvmpipe.
+    Cpu,
+    Other,
+}
+
+impl From<wgpu::DeviceType> for AdwaitaAdapterType {
+    fn from(value: wgpu::DeviceType) -> Self {
+        match value {
+            wgpu::DeviceType::DiscreteGpu => Self::DiscreteGpu,
+            wgpu::DeviceType::IntegratedGpu => Self::IntegratedGpu,
+            wgpu::DeviceType::VirtualGpu => Self::VirtualGpu,
+            wgpu::DeviceType::Cpu => Self::Cpu,
+            wgpu::DeviceType::Other => Self::Other,
+        }
+    }
+}
+
+impl AdwaitaAdapterDiagnostics {
+    /// Whether the adapter is on the same DRM device as the display, or [`None`]
+    /// if this couldn't be determined.
+    #[must_use]
+    pub fn matches_display(&self) -> Option<bool> {
+        Some(self.adapter_drm_node? == self.display_drm_node?)
+    }
+}
+
+/// DRM format modifiers which both the Vulkan device and the display support for
+/// the render target's dmabuf format.
+///
+/// This is inserted once GTK has reported the formats the display can import, and
+/// updated after every [`AdwaitaDisplayChanged`].
+/// If no modifiers are listed, one or both sides couldn't report their supported
+/// modifiers, which doesn't necessarily mean that importing will fail.
+#[derive(Debug, Clone, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct AdwaitaDmabufModifiers {
+    /// DRM fourcc of the render target format.
+    pub fourcc: u32,
+    /// Modifiers supported by both sides, sorted by modifier value.
+    pub modifiers: Vec<AdwaitaDmabufModifier>,
+}
+
+/// Render target formats which can be used on this system, so that apps can check
+/// a format before opening a window with it in
+/// [`AdwaitaWindowConfig::render_target_format`].
+///
+/// A format is listed if the Vulkan device can export render targets of that
+/// format, and the display can import them. This is inserted once GTK has reported
+/// the formats the display can import, and updated after every
+/// [`AdwaitaDisplayChanged`].
+///
+/// [`AdwaitaWindowConfig::render_target_format`]: crate::AdwaitaWindowConfig::render_target_format
+#[derive(Debug, Clone, Resource, Reflect)]
+#[reflect(Resource)]
+pub struct AdwaitaRenderTargetFormats {
+    pub formats: Vec<AdwaitaSupportedFormat>,
+}
+
+impl AdwaitaRenderTargetFormats {
+    #[must_use]
+    pub fn supports(&self, format: AdwaitaRenderTargetFormat) -> bool {
+        self.formats
+            .iter()
+            .any(|supported| supported.format == format)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub struct AdwaitaSupportedFormat {
+    pub format: AdwaitaRenderTargetFormat,
+    /// Whether this is the [default](AdwaitaRenderTargetFormat::default) format.
+    pub default: bool,
+}
+
+/// Sent when a monitor is connected to or disconnected from the display.
+///
+/// This can change which GPU the compositor runs on, so all render targets are
+/// recreated, and [`AdwaitaDmabufModifiers`] is negotiated again. The renderer
+/// itself can't be recreated, so if the display can no longer import frames from the
+/// adapter, a warning is logged and the windows stop updating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Event, Reflect)]
+pub struct AdwaitaDisplayChanged {
+    pub monitor_count: u32,
+    /// Whether the display can still import the render target's dmabuf format and
+    /// modifier.
+    pub dmabuf_supported: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub struct AdwaitaDmabufModifier {
+    pub modifier: u64,
+    /// Whether this is the linear modifier, which render targets fall back to if
+    /// the driver can't create them with any other modifier the display supports.
+    pub selected: bool,
+}
+
+#[derive(Debug, Clone, Default, Resource, Deref)]
+struct SharedRenderStats(Arc<Mutex<AdwaitaRenderStats>>);
+
+pub(crate) struct DiagnosticsPlugin {
+    pub schedule: InternedScheduleLabel,
+}
+
+impl Plugin for DiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        let shared_stats = SharedRenderStats::default();
+
+        app.register_type::<AdwaitaRenderStats>()
+            .register_type::<AdwaitaAdapterDiagnostics>()
+            .register_type::<AdwaitaDmabufModifiers>()
+            .register_type::<AdwaitaRenderTargetFormats>()
+            .register_type::<AdwaitaDisplayChanged>()
+            .add_event::<AdwaitaDisplayChanged>()
+            .init_resource::<AdwaitaRenderStats>()
+            .insert_resource(shared_stats.clone())
+            .add_systems(
+                self.schedule,
+                (
+                    sync_render_stats,
+                    (
+                        detect_display_changes,
+                        negotiate_dmabuf_modifiers
+                            .run_if(not(resource_exists::<AdwaitaDmabufModifiers>)),
+                        query_render_target_formats
+                            .run_if(not(resource_exists::<AdwaitaRenderTargetFormats>)),
+                    )
+                        .chain(),
+                )
+                    .before(AdwaitaSet::PollWindows),
+            );
+
+        app.sub_app_mut(RenderApp)
+            .insert_resource(shared_stats)
+            .add_systems(
+                Render,
+                (
+                    begin_gpu_timer.before(render_system),
+                    end_gpu_timer.after(render_system),
+                )
+                    .in_set(RenderSet::Render),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let adapter_diagnostics = AdwaitaAdapterDiagnostics::new(
+            app.world().resource::<RenderAdapter>(),
+            app.world().resource::<RenderAdapterInfo>(),
+        );
+        match adapter_diagnostics.matches_display() {
+            Some(true) => info!(
+                "Rendering on adapter \"{}\", which matches the display",
+                adapter_diagnostics.adapter_name
+            ),
+            Some(false) => warn!(
+                "Rendering on adapter \"{}\" ({:?}), but the display is on {:?} - \
+                 frames may fail to display",
+                adapter_diagnostics.adapter_name,
+                adapter_diagnostics.adapter_drm_node,
+                adapter_diagnostics.display_drm_node,
+            ),
+            None => info!(
+                "Rendering on adapter \"{}\", could not determine if it matches the display",
+                adapter_diagnostics.adapter_name
+            ),
+        }
+        if adapter_diagnostics.adapter_type == AdwaitaAdapterType::Cpu {
+            warn!(
+                "Rendering on software rasterizer \"{}\" - expect very poor performance",
+                adapter_diagnostics.adapter_name
+            );
+        }
+        app.insert_resource(adapter_diagnostics);
+
+        let render_app = app.sub_app_mut(RenderApp);
+        let Some(gpu_timer) = GpuTimer::new(
+            render_app.world().resource::<RenderDevice>(),
+            render_app.world().resource::<RenderQueue>(),
+        ) else {
+            info!("GPU timestamp queries are not supported, GPU frame times will not be measured");
+            return;
+        };
+
+        render_app
+            .world()
+            .resource::<SharedRenderStats>()
+            .lock()
+            .expect("poisoned")
+            .gpu_timing_supported = true;
+        render_app.insert_resource(gpu_timer);
+    }
+}
+
+impl AdwaitaAdapterDiagnostics {
+    fn new(adapter: &RenderAdapter, adapter_info: &RenderAdapterInfo) -> Self {
+        let adapter_drm_node = unsafe {
+            adapter
+                .as_hal::<vulkan::Api, _, _>(|adapter| adapter.and_then(render::adapter_drm_node))
+        };
+
+        Self {
+            adapter_name: adapter_info.name.clone(),
+            adapter_type: adapter_info.device_type.into(),
+            adapter_drm_node,
+            display_drm_node: render::display_drm_node(),
+        }
+    }
+}
+
+fn detect_display_changes(
+    mut commands: Commands,
+    display: Res<SharedDisplay>,
+    mut last_generation: Local<Option<u64>>,
+    mut windows: Query<&mut AdwaitaWindow>,
+    mut display_changed_events: EventWriter<AdwaitaDisplayChanged>,
+) {
+    let display_info = display.0.lock().expect("poisoned");
+    let Some(display_info) = display_info.as_ref() else {
+        return;
+    };
+    let last_generation = last_generation.replace(display_info.generation);
+    if last_generation.is_none_or(|last| last == display_info.generation) {
+        return;
+    }
+
+    let dmabuf_supported = display_info
+        .dmabuf_formats
+        .contains(&(render::DMABUF_FORMAT, render::DMABUF_MODIFIER));
+    if dmabuf_supported {
+        info!(
+            "Display changed, now has {} monitors - recreating render targets",
+            display_info.monitor_count
+        );
+    } else {
+        warn!(
+            "Display changed, and can no longer import dmabuf format {:#x} with modifier {:#x} - \
+             frames may fail to display until the app is restarted",
+            render::DMABUF_FORMAT,
+            render::DMABUF_MODIFIER,
+        );
+    }
+
+    for mut window in &mut windows {
+        window.recreate_render_targets();
+    }
+    commands.remove_resource::<AdwaitaDmabufModifiers>();
+    commands.remove_resource::<AdwaitaRenderTargetFormats>();
+    display_changed_events.send(AdwaitaDisplayChanged {
+        monitor_count: display_info.monitor_count,
+        dmabuf_supported,
+    });
+}
+
+fn negotiate_dmabuf_modifiers(
+    mut commands: Commands,
+    display: Res<SharedDisplay>,
+    adapter: Res<RenderAdapter>,
+) {
+    let display = display.0.lock().expect("poisoned");
+    let Some(display) = display.as_ref() else {
+        return;
+    };
+    let display_formats = &display.dmabuf_formats;
+
+    let fourcc = render::DMABUF_FORMAT;
+    let adapter_modifiers = unsafe {
+        adapter.as_hal::<vulkan::Api, _, _>(|adapter| {
+            adapter
+                .map(render::adapter_format_modifiers)
+                .unwrap_or_default()
+        })
+    };
+
+    let mut modifiers = display_formats
+        .iter()
+        .filter(|(display_fourcc, _)| *display_fourcc == fourcc)
+        .map(|(_, modifier)| *modifier)
+        .filter(|modifier| adapter_modifiers.contains(modifier))
+        .map(|modifier| AdwaitaDmabufModifier {
+            modifier,
+            selected: modifier == render::DMABUF_MODIFIER,
+        })
+        .collect::<Vec<_>>();
+    modifiers.sort_by_key(|modifier| modifier.modifier);
+    modifiers.dedup();
+
+    if !modifiers.is_empty() && !modifiers.iter().any(|modifier| modifier.selected) {
+        warn!(
+            "Dmabuf modifier {:#x} is not supported by both the adapter and the display, \
+             frames may fail to display if the adapter can't create tiled render targets",
+            render::DMABUF_MODIFIER
+        );
+    }
+    debug!("Negotiated dmabuf modifiers for {fourcc:#x}: {modifiers:?}");
+    commands.insert_resource(AdwaitaDmabufModifiers { fourcc, modifiers });
+}
+
+fn query_render_target_formats(
+    mut commands: Commands,
+    display: Res<SharedDisplay>,
+    adapter: Res<RenderAdapter>,
+) {
+    let display = display.0.lock().expect("poisoned");
+    let Some(display) = display.as_ref() else {
+        return;
+    };
+    let formats = AdwaitaRenderTargetFormat::ALL
+        .into_iter()
+        .filter(|format| {
+            display
+                .dmabuf_formats
+                .contains(&(format.fourcc(false), render::DMABUF_MODIFIER))
+        })
+        .filter(|&format| unsafe {
+            adapter.as_hal::<vulkan::Api, _, _>(|adapter| {
+                adapter.is_some_and(|adapter| render::adapter_supports_export(adapter, format))
+            })
+        })
+        .map(|format| AdwaitaSupportedFormat {
+            format,
+            default: format == AdwaitaRenderTargetFormat::default(),
+        })
+        .collect::<Vec<_>>();
+
+    if formats.is_empty() {
+        warn!("No render target format can be shared with the display, frames may fail to display");
+    }
+    debug!("Supported render target formats: {formats:?}");
+    commands.insert_resource(AdwaitaRenderTargetFormats { formats });
+}
+
+fn sync_render_stats(shared: Res<SharedRenderStats>, mut stats: ResMut<AdwaitaRenderStats>) {
+    let shared = shared.lock().expect("poisoned");
+    *stats = shared.clone();
+}
+
+#[derive(Debug, Resource)]
+struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    read_buffer: wgpu::Buffer,
+    timestamp_period: f32,
+    state: GpuTimerState,
+    samples: VecDeque<Duration>,
+}
+
+#[derive(Debug)]
+enum GpuTimerState {
+    Idle,
+    Recording,
+    Mapping(Arc<OnceLock<Result<(), wgpu::BufferAsyncError>>>),
+}
+
+const TIMESTAMP_SIZE: u64 = std::mem::size_of::<u64>() as u64;
+
+impl GpuTimer {
+    fn new(render_device: &RenderDevice, render_queue: &RenderQueue) -> Option<Self> {
+        if !render_device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+        {
+            return None;
+        }
+
+        let device = render_device.wgpu_device();
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("adwaita_gpu_timer_query_set"),
+            ty: wgpu::QueryType::Timestamp,
+            count: 2,
+        });
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("adwaita_gpu_timer_resolve_buffer"),
+            size: 2 * TIMESTAMP_SIZE,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let read_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("adwaita_gpu_timer_read_buffer"),
+            size: 2 * TIMESTAMP_SIZE,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Some(Self {
+            query_set,
+            resolve_buffer,
+            read_buffer,
+            timestamp_period: render_queue.get_timestamp_period(),
+            state: GpuTimerState::Idle,
+            samples: VecDeque::with_capacity(GPU_FRAME_TIME_SAMPLES),
+        })
+    }
+
+    fn write_timestamp(&self, encoder: &mut wgpu::CommandEncoder, index: u32) {
+        // an empty compute pass is the only portable way to write a timestamp
+        // without `TIMESTAMP_QUERY_INSIDE_ENCODERS`
+        encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("adwaita_gpu_timer_pass"),
+            timestamp_writes: Some(wgpu::ComputePassTimestampWrites {
+                query_set: &self.query_set,
+                beginning_of_pass_write_index: Some(index),
+                end_of_pass_write_index: None,
+            }),
+        });
+    }
+
+    fn read_sample(&mut self) -> Duration {
+        let (start, end) = {
+            let view = self.read_buffer.slice(..).get_mapped_range();
+            let timestamps: Vec<u64> = view
+                .chunks_exact(TIMESTAMP_SIZE as usize)
+                .map(|bytes| u64::from_le_bytes(bytes.try_into().expect("chunk is 8 bytes")))
+                .collect();
+            (timestamps[0], timestamps[1])
+        };
+        self.read_buffer.unmap();
+
+        let ticks = end.saturating_sub(start);
+        let nanos = (ticks as f64 * f64::from(self.timestamp_period)) as u64;
+        Duration::from_nanos(nanos)
+    }
+
+    fn push_sample(&mut self, sample: Duration, stats: &mut AdwaitaRenderStats) {
+        if self.samples.len() >= GPU_FRAME_TIME_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+
+        let total = self.samples.iter().sum::<Duration>();
+        let count = u32::try_from(self.samples.len()).expect("sample count is small");
+        stats.last_gpu_frame_time = Some(sample);
+        stats.avg_gpu_frame_time = Some(total / count);
+    }
+}
+
+fn begin_gpu_timer(
+    timer: Option<ResMut<GpuTimer>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    shared_stats: Res<SharedRenderStats>,
+) {
+    let Some(mut timer) = timer else {
+        return;
+    };
+
+    if let GpuTimerState::Mapping(map_result) = &timer.state {
+        match map_result.get().cloned() {
+            // the previous measurement is still in flight, skip measuring this frame
+            None => return,
+            Some(Ok(())) => {
+                let sample = timer.read_sample();
+                timer.push_sample(sample, &mut shared_stats.lock().expect("poisoned"));
+            }
+            Some(Err(err)) => {
+                warn!("Failed to map GPU timer buffer: {err}");
+            }
+        }
+        timer.state = GpuTimerState::Idle;
+    }
+
+    let mut encoder = render_device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("adwaita_gpu_timer_begin"),
+    });
+    timer.write_timestamp(&mut encoder, 0);
+    render_queue.submit([encoder.finish()]);
+    timer.state = GpuTimerState::Recording;
+}
+
+fn end_gpu_timer(
+    timer: Option<ResMut<GpuTimer>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    let Some(mut timer) = timer else {
+        return;
+    };
+    if !matches!(timer.state, GpuTimerState::Recording) {
+        return;
+    }
+
+    let mut encoder = render_device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("adwaita_gpu_timer_end"),
+    });
+    timer.write_timestamp(&mut encoder, 1);
+    encoder.resolve_query_set(&timer.query_set, 0..2, &timer.resolve_buffer, 0);
+    encoder.copy_buffer_to_buffer(
+        &timer.resolve_buffer,
+        0,
+        &timer.read_buffer,
+        0,
+        2 * TIMESTAMP_SIZE,
+    );
+    render_queue.submit([encoder.finish()]);
+
+    let map_result = Arc::new(OnceLock::new());
+    timer.read_buffer.slice(..).map_async(wgpu::MapMode::Read, {
+        let map_result = map_result.clone();
+        move |result| {
+            _ = map_result.set(result);
+        }
+    });
+    timer.state = GpuTimerState::Mapping(map_result);
+}
@@ -0,0 +1,124 @@
+use adw::prelude::*;
+use bevy::prelude::*;
+
+use crate::adwaita_app::WindowEvent;
+
+/// Modal message dialog shown with [`AdwaitaWindow::confirm`].
+///
+/// [`AdwaitaWindow::confirm`]: crate::AdwaitaWindow::confirm
+#[derive(Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Default)]
+pub struct AdwaitaDialog {
+    pub heading: String,
+    pub body: String,
+    /// Buttons of the dialog, from start to end.
+    pub responses: Vec<AdwaitaDialogButton>,
+    /// ID of the response activated by pressing Enter.
+    pub default_response: Option<String>,
+}
+
+/// Button in an [`AdwaitaDialog`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Reflect)]
+#[reflect(Default)]
+pub struct AdwaitaDialogButton {
+    /// Identifies this button in [`AdwaitaDialogResponse::response`].
+    pub id: String,
+    pub label: String,
+    pub appearance: AdwaitaButtonAppearance,
+}
+
+impl AdwaitaDialogButton {
+    #[must_use]
+    pub fn new(id: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            label: label.into(),
+            appearance: AdwaitaButtonAppearance::default(),
+        }
+    }
+
+    /// Highlights this button as the action the user is expected to take.
+    #[must_use]
+    pub fn suggested(mut self) -> Self {
+        self.appearance = AdwaitaButtonAppearance::Suggested;
+        self
+    }
+
+    /// Highlights this button as a dangerous action, such as losing data.
+    #[must_use]
+    pub fn destructive(mut self) -> Self {
+        self.appearance = AdwaitaButtonAppearance::Destructive;
+        self
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Default)]
+pub enum AdwaitaButtonAppearance {
+    #[default]
+    Default,
+    Suggested,
+    Destructive,
+}
+
+/// Identifies a dialog opened with [`AdwaitaWindow::confirm`].
+///
+/// [`AdwaitaWindow::confirm`]: crate::AdwaitaWindow::confirm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub struct AdwaitaDialogId(pub(crate) u64);
+
+/// Sent when the user closes a dialog opened with [`AdwaitaWindow::confirm`].
+///
+/// [`AdwaitaWindow::confirm`]: crate::AdwaitaWindow::confirm
+#[derive(Debug, Clone, PartialEq, Eq, Event, Reflect)]
+pub struct AdwaitaDialogResponse {
+    /// Entity of the [`AdwaitaWindow`] the dialog was shown on.
+    ///
+    /// [`AdwaitaWindow`]: crate::AdwaitaWindow
+    pub window: Entity,
+    pub dialog: AdwaitaDialogId,
+    /// [`AdwaitaDialogButton::id`] of the button that was pressed, or [`None`] if the
+    /// dialog was dismissed, for example by pressing Escape.
+    pub response: Option<String>,
+}
+
+// activated when the dialog is dismissed, can't clash with the user's responses
+// since we only report responses which are actually in the dialog
+const CLOSE_RESPONSE: &str = "bevy-mod-adwaita-close";
+
+/// Presents `dialog` as a modal dialog of `window`, sending a
+/// [`WindowEvent::DialogResponse`] once it is closed.
+pub(crate) fn show_dialog(
+    window: &adw::Window,
+    id: AdwaitaDialogId,
+    dialog: AdwaitaDialog,
+    send_event: flume::Sender<WindowEvent>,
+) -> adw::MessageDialog {
+    let message_dialog =
+        adw::MessageDialog::new(Some(window), Some(&dialog.heading), Some(&dialog.body));
+    message_dialog.set_destroy_with_parent(true);
+    for button in &dialog.responses {
+        message_dialog.add_response(&button.id, &button.label);
+        message_dialog.set_response_appearance(
+            &button.id,
+            match button.appearance {
+                AdwaitaButtonAppearance::Default => adw::ResponseAppearance::Default,
+                AdwaitaButtonAppearance::Suggested => adw::ResponseAppearance::Suggested,
+                AdwaitaButtonAppearance::Destructive => adw::ResponseAppearance::Destructive,
+            },
+        );
+    }
+    message_dialog.set_default_response(dialog.default_response.as_deref());
+    message_dialog.set_close_response(CLOSE_RESPONSE);
+
+    message_dialog.connect_response(None, move |_, response| {
+        let response = dialog
+            .responses
+            .iter()
+            .any(|button| button.id == response)
+            .then(|| response.to_owned());
+        _ = send_event.send(WindowEvent::DialogResponse { id, response });
+    });
+    message_dialog.present();
+    message_dialog
+}
@@ -0,0 +1,109 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{AdwaitaRenderStats, AdwaitaWindow};
+
+/// Automatically lowers the internal render resolution of a window when frames
+/// take longer than [`target_frame_time`], and raises it again when there is
+/// headroom.
+///
+/// The window contents are always scaled up by GTK to fill the window, so only
+/// the sharpness of the rendered image changes.
+///
+/// If GPU frame times are available from [`AdwaitaRenderStats`], those are used,
+/// otherwise the CPU frame time from [`Time`] is used.
+///
+/// [`target_frame_time`]: AdwaitaDynamicResolution::target_frame_time
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[reflect(Default)]
+pub struct AdwaitaDynamicResolution {
+    pub target_frame_time: Duration,
+    pub min_scale: f32,
+    pub max_scale: f32,
+}
+
+impl Default for AdwaitaDynamicResolution {
+    fn default() -> Self {
+        Self {
+            target_frame_time: Duration::from_secs_f64(1.0 / 60.0),
+            min_scale: 0.5,
+            max_scale: 1.0,
+        }
+    }
+}
+
+// scale changes are quantized to this step, so that we only ever
+// cycle through a small set of render target sizes
+const SCALE_STEP: f32 = 0.05;
+// frame time must be below this fraction of the target before we scale up again
+const HEADROOM: f32 = 0.8;
+// wait this long between adjustments, so that frame times can settle
+const COOLDOWN: Duration = Duration::from_millis(500);
+
+#[derive(Debug)]
+pub(crate) struct DynamicResolutionState {
+    config: AdwaitaDynamicResolution,
+    cooldown: Timer,
+}
+
+impl DynamicResolutionState {
+    pub fn new(config: AdwaitaDynamicResolution) -> Self {
+        Self {
+            config,
+            cooldown: Timer::new(COOLDOWN, TimerMode::Once),
+        }
+    }
+
+    pub fn initial_scale(&self) -> f32 {
+        self.config.max_scale
+    }
+}
+
+pub(crate) fn update_dynamic_resolution(
+    time: Res<Time<Real>>,
+    stats: Res<AdwaitaRenderStats>,
+    mut windows: Query<&mut AdwaitaWindow>,
+) {
+    let frame_time = stats.avg_gpu_frame_time.unwrap_or_else(|| time.delta());
+
+    for mut window in &mut windows {
+        let window = &mut *window;
+        let Some(state) = window.dynamic_resolution.as_mut() else {
+            continue;
+        };
+
+        state.cooldown.tick(time.delta());
+        if !state.cooldown.finished() {
+            continue;
+        }
+
+        let AdwaitaDynamicResolution {
+            target_frame_time,
+            min_scale,
+            max_scale,
+        } = state.config;
+        let target = target_frame_time.as_secs_f32();
+        let actual = frame_time.as_secs_f32();
+
+        let new_scale = if actual > target {
+            window.render_scale - SCALE_STEP
+        } else if actual < target * HEADROOM {
+            window.render_scale + SCALE_STEP
+        } else {
+            continue;
+        };
+        let new_scale = ((new_scale / SCALE_STEP).round() * SCALE_STEP).clamp(min_scale, max_scale);
+        if (new_scale - window.render_scale).abs() < f32::EPSILON {
+            continue;
+        }
+
+        debug!(
+            "Frame time {actual:.4}s vs target {target:.4}s, changing render scale from {:.2} to {new_scale:.2}",
+            window.render_scale
+        );
+        window.render_scale = new_scale;
+        state.cooldown.reset();
+    }
+}
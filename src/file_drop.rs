@@ -0,0 +1,148 @@
+use std::cell::Cell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use adw::prelude::*;
+use adw::{gdk, gio, glib, gtk};
+use bevy::prelude::*;
+
+use crate::adwaita_app::WindowEvent;
+
+/// Sent when files are dropped onto the render target of an [`AdwaitaWindow`].
+///
+/// Each path is also sent as a [`FileDragAndDrop::DroppedFile`].
+///
+/// [`AdwaitaWindow`]: crate::AdwaitaWindow
+/// [`FileDragAndDrop::DroppedFile`]: bevy::window::FileDragAndDrop::DroppedFile
+#[derive(Debug, Clone, PartialEq, Event, Reflect)]
+pub struct AdwaitaFileDropped {
+    /// Entity of the [`AdwaitaWindow`] the files were dropped onto.
+    ///
+    /// [`AdwaitaWindow`]: crate::AdwaitaWindow
+    pub window: Entity,
+    /// Paths of the dropped files, leaving out files which aren't on the local file
+    /// system.
+    pub paths: Vec<PathBuf>,
+    /// Position of the drop in logical pixels from the top left of the rendered
+    /// frame, in the same coordinates as [`CursorMoved`].
+    pub position: Vec2,
+}
+
+/// Sent while files are dragged over the render target of an [`AdwaitaWindow`],
+/// for showing where they'd be dropped.
+///
+/// When the drag enters the window, its files are sent as
+/// [`FileDragAndDrop::HoveredFile`]s, and when it leaves without dropping, a
+/// [`FileDragAndDrop::HoveredFileCanceled`] is sent.
+///
+/// [`AdwaitaWindow`]: crate::AdwaitaWindow
+/// [`FileDragAndDrop::HoveredFile`]: bevy::window::FileDragAndDrop::HoveredFile
+/// [`FileDragAndDrop::HoveredFileCanceled`]: bevy::window::FileDragAndDrop::HoveredFileCanceled
+#[derive(Debug, Clone, Copy, PartialEq, Event, Reflect)]
+pub struct AdwaitaFileHovered {
+    /// Entity of the [`AdwaitaWindow`] the files are dragged over.
+    ///
+    /// [`AdwaitaWindow`]: crate::AdwaitaWindow
+    pub window: Entity,
+    /// Position of the drag, in the same coordinates as
+    /// [`AdwaitaFileDropped::position`].
+    pub position: Vec2,
+}
+
+/// Adds a drop target to `widget` accepting files, which sends the
+/// `WindowEvent::File*` events.
+///
+/// Drop targets only see drag-and-drop events, so pointer input is unaffected.
+/// While a drag is over the widget though, the pointer belongs to the drag, so no
+/// [`WindowEvent::CursorMoved`]s are sent.
+pub(crate) fn attach_file_drop(
+    widget: &impl IsA<gtk::Widget>,
+    send_event: &flume::Sender<WindowEvent>,
+) {
+    let target = gtk::DropTarget::new(glib::Type::INVALID, gdk::DragAction::COPY);
+    target.set_types(&[gdk::FileList::static_type(), gio::File::static_type()]);
+    // loads the files while hovering, so that they can be sent before the drop
+    target.set_preload(true);
+    // GTK also leaves the target after a drop, which shouldn't be seen as a cancel
+    let dropped = Rc::new(Cell::new(false));
+
+    target.connect_value_notify({
+        let send_event = send_event.clone();
+        move |target| {
+            if let Some(value) = target.value() {
+                _ = send_event.send(WindowEvent::FileHovered(paths(&value)));
+            }
+        }
+    });
+    target.connect_enter({
+        let send_event = send_event.clone();
+        let dropped = dropped.clone();
+        move |target, x, y| {
+            dropped.set(false);
+            send_drag_position(target, &send_event, x, y);
+            gdk::DragAction::COPY
+        }
+    });
+    target.connect_motion({
+        let send_event = send_event.clone();
+        move |target, x, y| {
+            send_drag_position(target, &send_event, x, y);
+            gdk::DragAction::COPY
+        }
+    });
+    target.connect_leave({
+        let send_event = send_event.clone();
+        let dropped = dropped.clone();
+        move |_| {
+            if !dropped.replace(false) {
+                _ = send_event.send(WindowEvent::FileHoverCanceled);
+            }
+        }
+    });
+    target.connect_drop({
+        let send_event = send_event.clone();
+        move |target, value, x, y| {
+            let Some(widget) = target.widget() else {
+                return false;
+            };
+            let paths = paths(value);
+            if paths.is_empty() {
+                return false;
+            }
+            dropped.set(true);
+            _ = send_event.send(WindowEvent::FileDropped {
+                paths,
+                position: Vec2::new(x as f32, y as f32),
+                widget_size: Vec2::new(widget.width() as f32, widget.height() as f32),
+            });
+            true
+        }
+    });
+    widget.add_controller(target);
+}
+
+fn send_drag_position(
+    target: &gtk::DropTarget,
+    send_event: &flume::Sender<WindowEvent>,
+    x: f64,
+    y: f64,
+) {
+    let Some(widget) = target.widget() else {
+        return;
+    };
+    _ = send_event.send(WindowEvent::FileDragMoved {
+        position: Vec2::new(x as f32, y as f32),
+        widget_size: Vec2::new(widget.width() as f32, widget.height() as f32),
+    });
+}
+
+fn paths(value: &glib::Value) -> Vec<PathBuf> {
+    let files = if let Ok(files) = value.get::<gdk::FileList>() {
+        files.files()
+    } else if let Ok(file) = value.get::<gio::File>() {
+        vec![file]
+    } else {
+        Vec::new()
+    };
+    files.iter().filter_map(|file| file.path()).collect()
+}
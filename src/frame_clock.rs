@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use adw::gdk;
+use bevy::prelude::*;
+
+use crate::adwaita_app::WindowEvent;
+
+/// Sent every time the GTK frame clock of an [`AdwaitaWindow`] ticks, which is
+/// when GTK starts drawing a new frame for the compositor.
+///
+/// All times are on GLib's monotonic clock (`g_get_monotonic_time`), so they can
+/// be compared with each other, but not with [`Time`] or [`Instant`]s. Use these to
+/// drive presentation-synced animation, for example by animating towards
+/// [`predicted_presentation_time`] instead of the current time.
+///
+/// [`AdwaitaWindow`]: crate::AdwaitaWindow
+/// [`Instant`]: std::time::Instant
+/// [`predicted_presentation_time`]: AdwaitaFrameTick::predicted_presentation_time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event, Reflect)]
+pub struct AdwaitaFrameTick {
+    /// Entity of the [`AdwaitaWindow`] whose frame clock ticked.
+    ///
+    /// [`AdwaitaWindow`]: crate::AdwaitaWindow
+    pub window: Entity,
+    /// Counter of the frame clock, increasing by one every frame.
+    pub frame_counter: i64,
+    /// Time at which GTK started this frame.
+    pub frame_time: Duration,
+    /// Time at which the compositor is expected to show this frame, or [`None`] if
+    /// GTK can't predict it, for example before the first frame has been shown.
+    pub predicted_presentation_time: Option<Duration>,
+    /// Time between two refreshes of the monitor, or [`None`] if unknown.
+    pub refresh_interval: Option<Duration>,
+}
+
+/// Builds a [`WindowEvent::FrameTick`] from the current state of `frame_clock`.
+pub(crate) fn frame_tick_event(frame_clock: &gdk::FrameClock) -> WindowEvent {
+    let frame_time = frame_clock.frame_time();
+    // predicts from the presentation times of previous frames
+    let (refresh_interval, presentation_time) = frame_clock.refresh_info(frame_time);
+    WindowEvent::FrameTick {
+        frame_counter: frame_clock.frame_counter(),
+        frame_time: micros(frame_time).unwrap_or_default(),
+        predicted_presentation_time: micros(presentation_time),
+        refresh_interval: micros(refresh_interval),
+    }
+}
+
+// GDK uses 0 for unknown times
+pub(crate) fn micros(time: i64) -> Option<Duration> {
+    u64::try_from(time)
+        .ok()
+        .filter(|&time| time > 0)
+        .map(Duration::from_micros)
+}
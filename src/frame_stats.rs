@@ -0,0 +1,163 @@
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use adw::prelude::*;
+use adw::{glib, gtk};
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{frame_clock, AdwaitaWindow};
+
+/// How often the overlay of [`AdwaitaWindowConfig::show_frame_stats`] is updated.
+///
+/// [`AdwaitaWindowConfig::show_frame_stats`]: crate::AdwaitaWindowConfig::show_frame_stats
+const OVERLAY_UPDATE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Frame timing of every [`AdwaitaWindow`] opened with
+/// [`AdwaitaWindowConfig::show_frame_stats`], for debugging how often GTK presents
+/// the frames that Bevy sends it.
+///
+/// [`AdwaitaWindowConfig::show_frame_stats`]: crate::AdwaitaWindowConfig::show_frame_stats
+#[derive(Debug, Clone, Default, Resource, Reflect)]
+#[reflect(Default, Resource)]
+pub struct AdwaitaFrameStats {
+    pub windows: HashMap<Entity, AdwaitaWindowFrameStats>,
+}
+
+/// Frame timing of a single window in [`AdwaitaFrameStats`].
+///
+/// All counts are totals since the window was opened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Reflect)]
+#[reflect(Default)]
+pub struct AdwaitaWindowFrameStats {
+    /// Frames per second of the GTK frame clock, averaged over its last frames.
+    pub gtk_fps: f64,
+    /// Time between two refreshes of the monitor, or [`None`] if unknown.
+    pub refresh_interval: Option<Duration>,
+    /// Number of frames which Bevy has rendered and sent to GTK.
+    pub sent_frames: u64,
+    /// Number of frames from Bevy which GTK has painted.
+    ///
+    /// When GTK paints less often than Bevy sends frames, only the newest frame is
+    /// painted, and the others are dropped.
+    pub presented_frames: u64,
+    /// Number of times that the render target image prepared for a frame wasn't
+    /// sent, and was put back to be rendered to in the next frame.
+    ///
+    /// This happens when a frame is skipped, such as while rendering is paused or
+    /// due to [`AdwaitaFrameBackpressure::DropNewest`].
+    ///
+    /// [`AdwaitaFrameBackpressure::DropNewest`]: crate::AdwaitaFrameBackpressure::DropNewest
+    pub put_back_frames: u64,
+}
+
+/// Counters shared between the render world, which sends frames, and GTK, which
+/// paints them.
+#[derive(Debug, Default)]
+pub(crate) struct FrameCounters {
+    pub sent: AtomicU64,
+    pub presented: AtomicU64,
+    pub put_back: AtomicU64,
+    // bits of the `f64`
+    gtk_fps: AtomicU64,
+    // in microseconds, or 0 if unknown
+    refresh_interval: AtomicU64,
+}
+
+pub(crate) type SharedFrameCounters = Arc<FrameCounters>;
+
+impl FrameCounters {
+    fn stats(&self) -> AdwaitaWindowFrameStats {
+        AdwaitaWindowFrameStats {
+            gtk_fps: f64::from_bits(self.gtk_fps.load(Ordering::Relaxed)),
+            refresh_interval: match self.refresh_interval.load(Ordering::Relaxed) {
+                0 => None,
+                micros => Some(Duration::from_micros(micros)),
+            },
+            sent_frames: self.sent.load(Ordering::Relaxed),
+            presented_frames: self.presented.load(Ordering::Relaxed),
+            put_back_frames: self.put_back.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Creates the label shown over the render target, which also records the timing
+/// of the window's frame clock into `counters`.
+pub(crate) fn overlay(counters: SharedFrameCounters) -> gtk::Label {
+    const MARGIN: i32 = 6;
+
+    let label = gtk::Label::builder()
+        .css_classes(["osd", "monospace", "caption"])
+        .halign(gtk::Align::Start)
+        .valign(gtk::Align::Start)
+        .margin_start(MARGIN)
+        .margin_end(MARGIN)
+        .margin_top(MARGIN)
+        .margin_bottom(MARGIN)
+        .xalign(0.0)
+        .can_target(false)
+        .build();
+
+    // stats at the last update of the label, for showing rates since then
+    let last_update = Cell::new(None::<(i64, AdwaitaWindowFrameStats)>);
+    label.add_tick_callback(move |label, frame_clock| {
+        let frame_time = frame_clock.frame_time();
+        let (refresh_interval, _) = frame_clock.refresh_info(frame_time);
+        counters
+            .gtk_fps
+            .store(frame_clock.fps().to_bits(), Ordering::Relaxed);
+        counters.refresh_interval.store(
+            frame_clock::micros(refresh_interval).map_or(0, |interval| interval.as_micros() as u64),
+            Ordering::Relaxed,
+        );
+
+        let stats = counters.stats();
+        let Some((last_time, last_stats)) = last_update.get() else {
+            last_update.set(Some((frame_time, stats)));
+            return glib::ControlFlow::Continue;
+        };
+        let elapsed = Duration::from_micros(frame_time.saturating_sub(last_time).unsigned_abs());
+        if elapsed < OVERLAY_UPDATE_INTERVAL {
+            return glib::ControlFlow::Continue;
+        }
+        last_update.set(Some((frame_time, stats)));
+
+        let rate = |count: u64, last_count: u64| {
+            count.saturating_sub(last_count) as f64 / elapsed.as_secs_f64()
+        };
+        let refresh = stats.refresh_interval.map_or_else(
+            || "unknown".to_owned(),
+            |interval| format!("{:.1} ms", interval.as_secs_f64() * 1000.0),
+        );
+        label.set_label(&format!(
+            "GTK       {:6.1} fps, refresh {refresh}\n\
+             Sent      {:6.1}/s  {}\n\
+             Presented {:6.1}/s  {}\n\
+             Put back  {:6.1}/s  {}",
+            stats.gtk_fps,
+            rate(stats.sent_frames, last_stats.sent_frames),
+            stats.sent_frames,
+            rate(stats.presented_frames, last_stats.presented_frames),
+            stats.presented_frames,
+            rate(stats.put_back_frames, last_stats.put_back_frames),
+            stats.put_back_frames,
+        ));
+        glib::ControlFlow::Continue
+    });
+    label
+}
+
+pub(crate) fn sync_frame_stats(
+    windows: Query<(Entity, &AdwaitaWindow)>,
+    mut frame_stats: ResMut<AdwaitaFrameStats>,
+) {
+    frame_stats
+        .windows
+        .retain(|&entity, _| windows.contains(entity));
+    for (entity, window) in &windows {
+        if let Some(counters) = &window.frame_counters {
+            frame_stats.windows.insert(entity, counters.stats());
+        }
+    }
+}
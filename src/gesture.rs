@@ -0,0 +1,137 @@
+use std::{cell::Cell, rc::Rc};
+
+use adw::prelude::*;
+use bevy::prelude::*;
+
+use crate::adwaita_app::WindowEvent;
+
+/// Touchpad or touchscreen gesture recognized by GTK over the content of an
+/// [`AdwaitaWindow`].
+///
+/// Every gesture starts with an [`AdwaitaGesturePhase::Begin`], followed by any
+/// number of updates, and finishes with either an [`AdwaitaGesturePhase::End`] or
+/// an [`AdwaitaGesturePhase::Cancel`].
+///
+/// [`AdwaitaWindow`]: crate::AdwaitaWindow
+#[derive(Debug, Clone, Copy, PartialEq, Event, Reflect)]
+pub struct AdwaitaGesture {
+    /// Entity of the [`AdwaitaWindow`] the gesture was done over.
+    ///
+    /// [`AdwaitaWindow`]: crate::AdwaitaWindow
+    pub window: Entity,
+    pub phase: AdwaitaGesturePhase,
+    pub kind: AdwaitaGestureKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum AdwaitaGesturePhase {
+    Begin,
+    Update,
+    End,
+    /// GTK stopped recognizing the gesture, for example because another gesture
+    /// took over. Any effects of the gesture should be reverted.
+    Cancel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub enum AdwaitaGestureKind {
+    /// Two-finger pinch.
+    Pinch {
+        /// Distance between the fingers relative to the start of the gesture, where
+        /// `1.0` is unchanged and `2.0` means the fingers are twice as far apart.
+        scale: f32,
+    },
+    /// Two-finger rotation.
+    Rotate {
+        /// Angle in radians relative to the start of the gesture, clockwise.
+        angle: f32,
+    },
+    /// Quick drag in any direction.
+    Swipe {
+        /// Velocity of the swipe in logical pixels per second.
+        velocity: Vec2,
+    },
+}
+
+/// Adds gesture controllers to `widget`, which send [`WindowEvent::Gesture`]s.
+pub(crate) fn attach_gestures(
+    widget: &impl IsA<gtk::Widget>,
+    send_event: &flume::Sender<WindowEvent>,
+) {
+    let zoom = gtk::GestureZoom::new();
+    connect_phases(&zoom, send_event, |zoom| AdwaitaGestureKind::Pinch {
+        scale: zoom.scale_delta() as f32,
+    });
+    widget.add_controller(zoom);
+
+    let rotate = gtk::GestureRotate::new();
+    connect_phases(&rotate, send_event, |rotate| AdwaitaGestureKind::Rotate {
+        angle: rotate.angle_delta() as f32,
+    });
+    widget.add_controller(rotate);
+
+    let swipe = gtk::GestureSwipe::new();
+    // the velocity is no longer available once the gesture has ended,
+    // so remember the last velocity we saw
+    let last_velocity = Rc::new(Cell::new(Vec2::ZERO));
+    swipe.connect_swipe({
+        let last_velocity = last_velocity.clone();
+        move |_, x, y| last_velocity.set(Vec2::new(x as f32, y as f32))
+    });
+    connect_phases(&swipe, send_event, move |swipe| {
+        if let Some((x, y)) = swipe.velocity() {
+            last_velocity.set(Vec2::new(x as f32, y as f32));
+        }
+        AdwaitaGestureKind::Swipe {
+            velocity: last_velocity.get(),
+        }
+    });
+    widget.add_controller(swipe);
+}
+
+fn connect_phases<G: IsA<gtk::Gesture>>(
+    gesture: &G,
+    send_event: &flume::Sender<WindowEvent>,
+    kind: impl Fn(&G) -> AdwaitaGestureKind + 'static,
+) {
+    let kind = Rc::new(kind);
+    // GTK also ends gestures after cancelling them, but we only report one of the two
+    let cancelled = Rc::new(Cell::new(false));
+
+    let send = {
+        let send_event = send_event.clone();
+        move |gesture: &G, phase| {
+            _ = send_event.send(WindowEvent::Gesture {
+                phase,
+                kind: kind(gesture),
+            });
+        }
+    };
+    let send = Rc::new(send);
+
+    gesture.connect_begin({
+        let send = send.clone();
+        let cancelled = cancelled.clone();
+        move |gesture, _| {
+            cancelled.set(false);
+            send(gesture, AdwaitaGesturePhase::Begin);
+        }
+    });
+    gesture.connect_update({
+        let send = send.clone();
+        move |gesture, _| send(gesture, AdwaitaGesturePhase::Update)
+    });
+    gesture.connect_cancel({
+        let send = send.clone();
+        let cancelled = cancelled.clone();
+        move |gesture, _| {
+            cancelled.set(true);
+            send(gesture, AdwaitaGesturePhase::Cancel);
+        }
+    });
+    gesture.connect_end(move |gesture, _| {
+        if !cancelled.get() {
+            send(gesture, AdwaitaGesturePhase::End);
+        }
+    });
+}
@@ -0,0 +1,140 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use adw::prelude::*;
+use adw::{gdk, gtk};
+use bevy::window::CursorGrabMode;
+
+use crate::adwaita_app::WindowEvent;
+use crate::cursor;
+
+/// How [`attach_pointer`](crate::pointer::attach_pointer) reports pointer motion
+/// over the render target.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum PointerMotion {
+    /// Only [`WindowEvent::CursorMoved`]s are sent.
+    #[default]
+    Absolute,
+    /// [`WindowEvent::MouseMotion`]s are also sent, with the distance between
+    /// consecutive positions.
+    Relative,
+    /// Like [`PointerMotion::Relative`], but the pointer is warped back into the
+    /// widget whenever it leaves.
+    Confined,
+    /// The pointer is warped back to the center of the widget after every move, and
+    /// only [`WindowEvent::MouseMotion`]s are sent.
+    Recentered,
+}
+
+pub(crate) type SharedPointerMotion = Rc<Cell<PointerMotion>>;
+
+/// Keeps the pointer inside of a window's render target, for
+/// [`WindowCommand::SetCursorGrab`](crate::adwaita_app::WindowCommand::SetCursorGrab).
+///
+/// On Wayland with the `wayland` feature, the pointer is locked or confined by the
+/// compositor. Otherwise, grabs are emulated by warping the pointer, which only
+/// works on X11 with the `x11` feature. Without either, the pointer moves freely,
+/// but [`WindowEvent::MouseMotion`]s are still sent.
+#[derive(Debug)]
+pub(crate) struct CursorGrab {
+    mode: CursorGrabMode,
+    motion: SharedPointerMotion,
+    #[cfg(feature = "wayland")]
+    constraints: Option<crate::pointer_constraints::PointerConstraints>,
+}
+
+impl CursorGrab {
+    pub fn new(motion: SharedPointerMotion) -> Self {
+        Self {
+            mode: CursorGrabMode::None,
+            motion,
+            #[cfg(feature = "wayland")]
+            constraints: None,
+        }
+    }
+
+    pub fn set(
+        &mut self,
+        window: &adw::Window,
+        mode: CursorGrabMode,
+        send_event: &flume::Sender<WindowEvent>,
+    ) {
+        if self.mode == mode {
+            return;
+        }
+        self.mode = mode;
+        self.motion.set(PointerMotion::Absolute);
+        #[cfg(feature = "wayland")]
+        if let Some(constraints) = &mut self.constraints {
+            constraints.release();
+        }
+        if mode == CursorGrabMode::None {
+            return;
+        }
+
+        let Some(surface) = window.surface() else {
+            tracing::warn!("Cannot grab cursor, window is not realized yet");
+            return;
+        };
+
+        #[cfg(feature = "wayland")]
+        {
+            if self.constraints.is_none() {
+                match crate::pointer_constraints::PointerConstraints::new(window, send_event) {
+                    Ok(constraints) => self.constraints = constraints,
+                    // the fallback below says why it isn't supported
+                    Err(err) => tracing::debug!("Failed to set up pointer constraints: {err}"),
+                }
+            }
+            if let Some(constraints) = &mut self.constraints {
+                constraints.constrain(mode);
+                return;
+            }
+        }
+        #[cfg(not(feature = "wayland"))]
+        let _ = send_event;
+
+        if cursor::can_warp(&surface) {
+            tracing::info!("Emulating cursor grab mode {mode:?} by warping the pointer");
+            self.motion.set(match mode {
+                CursorGrabMode::Locked => PointerMotion::Recentered,
+                _ => PointerMotion::Confined,
+            });
+        } else {
+            tracing::warn!(
+                "Cannot grab cursor, {}. Only relative motion is sent",
+                unsupported_reason(&surface.display())
+            );
+            self.motion.set(PointerMotion::Relative);
+        }
+    }
+}
+
+fn unsupported_reason(display: &gdk::Display) -> &'static str {
+    let backend = display.type_().name();
+    if backend.starts_with("GdkWayland") {
+        if cfg!(feature = "wayland") {
+            "the compositor doesn't support pointer constraints"
+        } else {
+            "this requires the `wayland` feature on Wayland"
+        }
+    } else if backend.starts_with("GdkX11") {
+        "this requires the `x11` feature on X11"
+    } else {
+        "this is only supported on Wayland and X11"
+    }
+}
+
+/// Sends a [`WindowEvent::MouseMotion`] for a `delta` in logical pixels of
+/// `widget`.
+pub(crate) fn send_mouse_motion(
+    widget: &impl IsA<gtk::Widget>,
+    send_event: &flume::Sender<WindowEvent>,
+    delta: bevy::math::Vec2,
+) {
+    let scale = widget
+        .native()
+        .and_then(|native| native.surface())
+        .map_or(1.0, |surface| surface.scale());
+    _ = send_event.send(WindowEvent::MouseMotion(delta * scale as f32));
+}
@@ -57,16 +57,19 @@ pub unsafe fn open_adapter(
     }
 }
 
+/// Merges the features and limits requested by `settings` with those of the
+/// adapter, in the same way as Bevy's `initialize_renderer`.
 pub fn make_device_descriptor<'a>(
     settings: &'a WgpuSettings,
-    adapter: &wgpu::Adapter,
-    adapter_info: &wgpu::AdapterInfo,
+    device_type: wgpu::DeviceType,
+    adapter_features: wgpu::Features,
+    adapter_limits: &wgpu::Limits,
 ) -> wgpu::DeviceDescriptor<'a> {
     let mut features = wgpu::Features::empty();
     let mut limits = settings.limits.clone();
     if matches!(settings.priority, WgpuSettingsPriority::Functionality) {
-        features = adapter.features();
-        if adapter_info.device_type == wgpu::DeviceType::DiscreteGpu {
+        features = adapter_features;
+        if device_type == wgpu::DeviceType::DiscreteGpu {
             // `MAPPABLE_PRIMARY_BUFFERS` can have a significant, negative performance impact for
             // discrete GPUs due to having to transfer data across the PCI-E bus and so it
             // should not be automatically enabled in this case. It is however beneficial for
@@ -82,7 +85,7 @@ pub fn make_device_descriptor<'a>(
         features -= wgpu::Features::RAY_QUERY;
         features -= wgpu::Features::RAY_TRACING_ACCELERATION_STRUCTURE;
 
-        limits = adapter.limits();
+        limits = adapter_limits.clone();
     }
 
     // Enforce the disabled features
@@ -211,3 +214,111 @@ pub fn make_device_descriptor<'a>(
         required_limits: limits,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADAPTER_FEATURES: wgpu::Features = wgpu::Features::TEXTURE_COMPRESSION_BC
+        .union(wgpu::Features::MAPPABLE_PRIMARY_BUFFERS)
+        .union(wgpu::Features::PUSH_CONSTANTS);
+
+    fn adapter_limits() -> wgpu::Limits {
+        wgpu::Limits {
+            max_bind_groups: 8,
+            max_push_constant_size: 256,
+            ..wgpu::Limits::default()
+        }
+    }
+
+    fn merge(settings: &WgpuSettings, device_type: wgpu::DeviceType) -> wgpu::DeviceDescriptor<'_> {
+        make_device_descriptor(settings, device_type, ADAPTER_FEATURES, &adapter_limits())
+    }
+
+    #[test]
+    fn functionality_uses_adapter_features_and_limits() {
+        let settings = WgpuSettings {
+            priority: WgpuSettingsPriority::Functionality,
+            ..WgpuSettings::default()
+        };
+        let descriptor = merge(&settings, wgpu::DeviceType::IntegratedGpu);
+        assert_eq!(descriptor.required_features, ADAPTER_FEATURES);
+        assert_eq!(descriptor.required_limits, adapter_limits());
+    }
+
+    #[test]
+    fn discrete_gpu_drops_mappable_primary_buffers() {
+        let settings = WgpuSettings {
+            priority: WgpuSettingsPriority::Functionality,
+            ..WgpuSettings::default()
+        };
+        let descriptor = merge(&settings, wgpu::DeviceType::DiscreteGpu);
+        assert_eq!(
+            descriptor.required_features,
+            ADAPTER_FEATURES - wgpu::Features::MAPPABLE_PRIMARY_BUFFERS
+        );
+    }
+
+    #[test]
+    fn requested_limits_survive() {
+        let limits = wgpu::Limits {
+            max_bind_groups: 6,
+            ..wgpu::Limits::downlevel_defaults()
+        };
+        let settings = WgpuSettings {
+            priority: WgpuSettingsPriority::Compatibility,
+            limits: limits.clone(),
+            ..WgpuSettings::default()
+        };
+        let descriptor = merge(&settings, wgpu::DeviceType::IntegratedGpu);
+        assert_eq!(descriptor.required_limits, limits);
+    }
+
+    #[test]
+    fn constrained_limits_survive() {
+        let settings = WgpuSettings {
+            priority: WgpuSettingsPriority::Functionality,
+            constrained_limits: Some(wgpu::Limits {
+                max_bind_groups: 5,
+                min_uniform_buffer_offset_alignment: 512,
+                ..adapter_limits()
+            }),
+            ..WgpuSettings::default()
+        };
+        let descriptor = merge(&settings, wgpu::DeviceType::IntegratedGpu);
+        assert_eq!(descriptor.required_limits.max_bind_groups, 5);
+        assert_eq!(
+            descriptor
+                .required_limits
+                .min_uniform_buffer_offset_alignment,
+            512
+        );
+        assert_eq!(descriptor.required_limits.max_push_constant_size, 256);
+    }
+
+    #[test]
+    fn requested_features_survive() {
+        let settings = WgpuSettings {
+            priority: WgpuSettingsPriority::Functionality,
+            features: wgpu::Features::POLYGON_MODE_LINE,
+            disabled_features: Some(wgpu::Features::PUSH_CONSTANTS),
+            ..WgpuSettings::default()
+        };
+        let descriptor = merge(&settings, wgpu::DeviceType::IntegratedGpu);
+        assert_eq!(
+            descriptor.required_features,
+            (ADAPTER_FEATURES - wgpu::Features::PUSH_CONSTANTS) | wgpu::Features::POLYGON_MODE_LINE
+        );
+
+        let settings = WgpuSettings {
+            priority: WgpuSettingsPriority::Compatibility,
+            features: wgpu::Features::POLYGON_MODE_LINE,
+            ..WgpuSettings::default()
+        };
+        let descriptor = merge(&settings, wgpu::DeviceType::IntegratedGpu);
+        assert_eq!(
+            descriptor.required_features,
+            wgpu::Features::POLYGON_MODE_LINE
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use adw::prelude::*;
+use adw::{gio, glib, gtk};
+use bevy::prelude::*;
+
+use crate::adwaita_app::WindowEvent;
+
+/// Widget added to the header bar of a window opened with
+/// [`AdwaitaHeaderBar::Custom`].
+///
+/// [`AdwaitaHeaderBar::Custom`]: crate::AdwaitaHeaderBar::Custom
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum AdwaitaHeaderItem {
+    /// Icon button, which sends an [`AdwaitaHeaderAction`] with `id` when clicked.
+    Button {
+        id: String,
+        /// Name of the icon from the icon theme, such as `document-save-symbolic`.
+        icon_name: String,
+        tooltip: Option<String>,
+        pack: AdwaitaHeaderPack,
+    },
+    /// Button opening a menu, where each item sends an [`AdwaitaHeaderAction`] when
+    /// activated.
+    MenuButton {
+        icon_name: String,
+        tooltip: Option<String>,
+        items: Vec<AdwaitaMenuItem>,
+        pack: AdwaitaHeaderPack,
+    },
+    /// Replaces the window title shown in the middle of the header bar.
+    Title {
+        title: String,
+        subtitle: Option<String>,
+    },
+}
+
+impl AdwaitaHeaderItem {
+    #[must_use]
+    pub fn button(id: impl Into<String>, icon_name: impl Into<String>) -> Self {
+        Self::Button {
+            id: id.into(),
+            icon_name: icon_name.into(),
+            tooltip: None,
+            pack: AdwaitaHeaderPack::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn menu_button(icon_name: impl Into<String>, items: Vec<AdwaitaMenuItem>) -> Self {
+        Self::MenuButton {
+            icon_name: icon_name.into(),
+            tooltip: None,
+            items,
+            pack: AdwaitaHeaderPack::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn title(title: impl Into<String>) -> Self {
+        Self::Title {
+            title: title.into(),
+            subtitle: None,
+        }
+    }
+
+    /// Sets the tooltip of a button, doing nothing for a title.
+    #[must_use]
+    pub fn with_tooltip(mut self, text: impl Into<String>) -> Self {
+        if let Self::Button { tooltip, .. } | Self::MenuButton { tooltip, .. } = &mut self {
+            *tooltip = Some(text.into());
+        }
+        self
+    }
+
+    /// Packs a button at the end of the header bar, doing nothing for a title.
+    #[must_use]
+    pub fn at_end(mut self) -> Self {
+        if let Self::Button { pack, .. } | Self::MenuButton { pack, .. } = &mut self {
+            *pack = AdwaitaHeaderPack::End;
+        }
+        self
+    }
+}
+
+/// Side of the header bar that an [`AdwaitaHeaderItem`] is placed on.
+///
+/// Items on the same side are placed in order, starting from the edge of the window.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[reflect(Default)]
+pub enum AdwaitaHeaderPack {
+    #[default]
+    Start,
+    End,
+}
+
+/// Item in the menu of an [`AdwaitaHeaderItem::MenuButton`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[reflect(Default)]
+pub struct AdwaitaMenuItem {
+    /// Identifies this item in [`AdwaitaHeaderAction::id`].
+    pub id: String,
+    pub label: String,
+}
+
+impl AdwaitaMenuItem {
+    #[must_use]
+    pub fn new(id: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            label: label.into(),
+        }
+    }
+}
+
+/// Sent when a button or menu item in the header bar of an [`AdwaitaWindow`] is
+/// activated.
+///
+/// [`AdwaitaWindow`]: crate::AdwaitaWindow
+#[derive(Debug, Clone, PartialEq, Eq, Event, Reflect)]
+pub struct AdwaitaHeaderAction {
+    /// Entity of the [`AdwaitaWindow`].
+    ///
+    /// [`AdwaitaWindow`]: crate::AdwaitaWindow
+    pub window: Entity,
+    /// ID of the [`AdwaitaHeaderItem::Button`] or [`AdwaitaMenuItem`].
+    pub id: String,
+}
+
+const ACTION_GROUP: &str = "adwaita-header";
+const ACTION: &str = "activate";
+
+/// Builds a header bar containing `items`, which sends a
+/// [`WindowEvent::HeaderAction`] when one of them is activated.
+pub(crate) fn custom_header_bar(
+    items: &[AdwaitaHeaderItem],
+    send_event: &flume::Sender<WindowEvent>,
+) -> adw::HeaderBar {
+    let header_bar = adw::HeaderBar::new();
+
+    // buttons and menu items all activate the same action, with their ID as the
+    // target, so that IDs don't have to be valid action names
+    let action = gio::SimpleAction::new(ACTION, Some(glib::VariantTy::STRING));
+    action.connect_activate({
+        let send_event = send_event.clone();
+        move |_, target| {
+            let Some(id) = target.and_then(|target| target.get::<String>()) else {
+                return;
+            };
+            _ = send_event.send(WindowEvent::HeaderAction(id));
+        }
+    });
+    let action_group = gio::SimpleActionGroup::new();
+    action_group.add_action(&action);
+    header_bar.insert_action_group(ACTION_GROUP, Some(&action_group));
+    let detailed_action = format!("{ACTION_GROUP}.{ACTION}");
+
+    for item in items {
+        let (widget, pack): (gtk::Widget, _) = match item {
+            AdwaitaHeaderItem::Button {
+                id,
+                icon_name,
+                tooltip,
+                pack,
+            } => {
+                let button = gtk::Button::from_icon_name(icon_name);
+                button.set_tooltip_text(tooltip.as_deref());
+                button.set_action_name(Some(&detailed_action));
+                button.set_action_target_value(Some(&id.to_variant()));
+                (button.upcast(), *pack)
+            }
+            AdwaitaHeaderItem::MenuButton {
+                icon_name,
+                tooltip,
+                items,
+                pack,
+            } => {
+                let menu = gio::Menu::new();
+                for item in items {
+                    let menu_item = gio::MenuItem::new(Some(&item.label), None);
+                    menu_item.set_action_and_target_value(
+                        Some(&detailed_action),
+                        Some(&item.id.to_variant()),
+                    );
+                    menu.append_item(&menu_item);
+                }
+                let button = gtk::MenuButton::builder()
+                    .icon_name(icon_name.as_str())
+                    .menu_model(&menu)
+                    .build();
+                button.set_tooltip_text(tooltip.as_deref());
+                (button.upcast(), *pack)
+            }
+            AdwaitaHeaderItem::Title { title, subtitle } => {
+                let title = adw::WindowTitle::new(title, subtitle.as_deref().unwrap_or_default());
+                header_bar.set_title_widget(Some(&title));
+                continue;
+            }
+        };
+        match pack {
+            AdwaitaHeaderPack::Start => header_bar.pack_start(&widget),
+            AdwaitaHeaderPack::End => header_bar.pack_end(&widget),
+        }
+    }
+    header_bar
+}
@@ -0,0 +1,180 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::PathBuf;
+use std::{fs, io};
+
+use adw::prelude::*;
+use adw::{gdk, glib, gtk};
+use bevy::{prelude::*, render::render_resource::TextureFormat};
+
+use crate::AdwaitaWindow;
+
+/// Icon of an [`AdwaitaWindow`], shown by the desktop in places such as the
+/// taskbar and window switcher.
+///
+/// | Backend | Support |
+/// |---------|---------|
+/// | X11     | Supported |
+/// | Wayland | Unsupported, the compositor shows the icon of the `.desktop` file matching the app ID |
+///
+/// On Wayland, install a `.desktop` file with an `Icon` entry, named after the
+/// [`AdwaitaWindowPlugin::application_id`], instead.
+///
+/// [`AdwaitaWindowPlugin::application_id`]: crate::AdwaitaWindowPlugin::application_id
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum AdwaitaWindowIcon {
+    /// Name of an icon in the user's icon theme, such as `applications-games`.
+    Named(String),
+    /// Pixels of the icon, in non-premultiplied RGBA with 8 bits per channel and
+    /// rows of `width * 4` bytes.
+    ///
+    /// The icon should be square, and at least as large as the largest size the
+    /// desktop shows it at, such as 256x256 pixels. GTK scales it down to the other
+    /// sizes.
+    Rgba {
+        width: u32,
+        height: u32,
+        data: Vec<u8>,
+    },
+}
+
+impl AdwaitaWindowIcon {
+    /// Converts the pixels of `image` into an [`AdwaitaWindowIcon::Rgba`], or returns
+    /// [`None`] if its format can't be converted, or its pixels have been unloaded
+    /// from the main world.
+    #[must_use]
+    pub fn from_image(image: &Image) -> Option<Self> {
+        let image = image.convert(TextureFormat::Rgba8UnormSrgb)?;
+        let size = image.size();
+        let expected_len = size.x as usize * size.y as usize * 4;
+        (size.x > 0 && size.y > 0 && image.data.len() == expected_len).then_some(Self::Rgba {
+            width: size.x,
+            height: size.y,
+            data: image.data,
+        })
+    }
+}
+
+/// Sets the [`AdwaitaWindowIcon`] of the [`AdwaitaWindow`] on this entity from an
+/// [`Image`], once it's loaded and whenever it changes.
+///
+/// The image must be kept in the main world, so its
+/// [`RenderAssetUsages`](bevy::render::render_asset::RenderAssetUsages) must include
+/// `MAIN_WORLD`.
+#[derive(Debug, Clone, PartialEq, Eq, Component, Reflect)]
+#[reflect(Component)]
+pub struct AdwaitaWindowIconImage(pub Handle<Image>);
+
+pub(crate) fn sync_window_icon_images(
+    windows: Query<(&AdwaitaWindow, Ref<AdwaitaWindowIconImage>)>,
+    images: Res<Assets<Image>>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+) {
+    let changed_images = image_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    for (window, icon_image) in &windows {
+        if !icon_image.is_changed() && !changed_images.contains(&icon_image.0.id()) {
+            continue;
+        }
+        // sent again once it's loaded
+        let Some(image) = images.get(&icon_image.0) else {
+            continue;
+        };
+        match AdwaitaWindowIcon::from_image(image) {
+            Some(icon) => window.set_icon(Some(icon)),
+            None => warn!(
+                "Cannot use image {:?} as a window icon, it has an unsupported format \
+                 {:?}, or isn't kept in the main world",
+                icon_image.0, image.texture_descriptor.format,
+            ),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+enum IconError {
+    #[error("{len} bytes of pixels don't make up a {width}x{height} RGBA image")]
+    InvalidSize { len: usize, width: u32, height: u32 },
+    #[error("failed to create icon directory")]
+    CreateDir(#[source] io::Error),
+    #[error("failed to save icon")]
+    Save(#[source] glib::BoolError),
+}
+
+/// Sets the icon of `window`, or resets it to the app's default icon.
+pub(crate) fn set_icon(window: &adw::Window, icon: Option<&AdwaitaWindowIcon>) {
+    let name = match icon {
+        None => None,
+        Some(AdwaitaWindowIcon::Named(name)) => Some(name.clone()),
+        Some(AdwaitaWindowIcon::Rgba {
+            width,
+            height,
+            data,
+        }) => match install_icon(&WidgetExt::display(window), *width, *height, data) {
+            Ok(name) => Some(name),
+            Err(err) => {
+                tracing::warn!("Failed to set window icon: {err}");
+                return;
+            }
+        },
+    };
+    window.set_icon_name(name.as_deref());
+}
+
+/// Saves the pixels of an icon into a directory of the icon theme, since GTK 4 only
+/// sets window icons by name, and returns the name to look it up by.
+fn install_icon(
+    display: &gdk::Display,
+    width: u32,
+    height: u32,
+    data: &[u8],
+) -> Result<String, IconError> {
+    let invalid_size = || IconError::InvalidSize {
+        len: data.len(),
+        width,
+        height,
+    };
+    let (Ok(texture_width), Ok(texture_height)) = (i32::try_from(width), i32::try_from(height))
+    else {
+        return Err(invalid_size());
+    };
+    if width == 0 || height == 0 || data.len() != width as usize * height as usize * 4 {
+        return Err(invalid_size());
+    }
+
+    // the icon theme caches icons by name, so changed pixels need a new name
+    let mut hasher = DefaultHasher::new();
+    (width, height, data).hash(&mut hasher);
+    let name = format!("bevy-mod-adwaita-icon-{:016x}", hasher.finish());
+
+    let dir = icon_dir();
+    let path = dir.join(format!("{name}.png"));
+    if !path.exists() {
+        fs::create_dir_all(&dir).map_err(IconError::CreateDir)?;
+        let texture = gdk::MemoryTexture::new(
+            texture_width,
+            texture_height,
+            gdk::MemoryFormat::R8g8b8a8,
+            &glib::Bytes::from(data),
+            width as usize * 4,
+        );
+        texture.save_to_png(&path).map_err(IconError::Save)?;
+    }
+
+    // icons directly in a search path are found without belonging to a theme
+    let theme = gtk::IconTheme::for_display(display);
+    if !theme.search_path().contains(&dir) {
+        theme.add_search_path(&dir);
+    }
+    Ok(name)
+}
+
+// the runtime directory is cleared when the user logs out
+fn icon_dir() -> PathBuf {
+    glib::user_runtime_dir().join(format!("bevy_mod_adwaita-{}/icons", std::process::id()))
+}
@@ -0,0 +1,102 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use adw::prelude::*;
+use adw::{gio, glib};
+use bevy::prelude::*;
+
+/// How long the user has been idle for, as reported by GNOME's idle monitor.
+///
+/// This is updated about once a second, so that apps can auto-pause or dim the
+/// screen while the user is away. Idle time counts from the last keyboard or
+/// pointer input anywhere in the session, not only in this app's windows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Resource, Reflect)]
+#[reflect(Resource, Default)]
+pub struct AdwaitaIdleState {
+    /// Time since the user last gave any input, or [`None`] if the idle monitor
+    /// isn't available, for example outside of GNOME.
+    pub idle_time: Option<Duration>,
+}
+
+impl AdwaitaIdleState {
+    /// Whether the user has been idle for at least `threshold`.
+    ///
+    /// Always `false` if the idle monitor isn't available.
+    #[must_use]
+    pub fn is_idle_for(&self, threshold: Duration) -> bool {
+        self.idle_time
+            .is_some_and(|idle_time| idle_time >= threshold)
+    }
+}
+
+/// Idle state read on the GTK thread, if it changed since Bevy last read it.
+#[derive(Debug, Clone, Default, Resource, Deref)]
+pub(crate) struct SharedIdleState(Arc<Mutex<Option<AdwaitaIdleState>>>);
+
+const IDLE_MONITOR_NAME: &str = "org.gnome.Mutter.IdleMonitor";
+const IDLE_MONITOR_PATH: &str = "/org/gnome/Mutter/IdleMonitor/Core";
+const POLL_INTERVAL_SECS: u32 = 1;
+
+/// Must be called on the GTK thread.
+pub(crate) fn watch_idle_state(shared: SharedIdleState) {
+    gio::DBusProxy::for_bus(
+        gio::BusType::Session,
+        gio::DBusProxyFlags::DO_NOT_LOAD_PROPERTIES | gio::DBusProxyFlags::DO_NOT_CONNECT_SIGNALS,
+        None,
+        IDLE_MONITOR_NAME,
+        IDLE_MONITOR_PATH,
+        IDLE_MONITOR_NAME,
+        None::<&gio::Cancellable>,
+        move |result| {
+            let proxy = match result {
+                Ok(proxy) => proxy,
+                Err(err) => {
+                    tracing::debug!(
+                        "Failed to connect to the idle monitor, idle time won't be reported: {err}"
+                    );
+                    return;
+                }
+            };
+            // creating the proxy succeeds even if nothing owns the name
+            if proxy.name_owner().is_none() {
+                tracing::debug!("No idle monitor is running, idle time won't be reported");
+                return;
+            }
+
+            // the monitor only notifies about fixed thresholds, so poll instead to
+            // report the actual idle time
+            glib::timeout_add_seconds_local(POLL_INTERVAL_SECS, move || {
+                let shared = shared.clone();
+                proxy.call(
+                    "GetIdletime",
+                    None,
+                    gio::DBusCallFlags::NONE,
+                    -1,
+                    None::<&gio::Cancellable>,
+                    move |result| {
+                        let idle_time = match result {
+                            Ok(reply) => reply.get::<(u64,)>().map(|(millis,)| millis),
+                            Err(err) => {
+                                tracing::debug!("Failed to get idle time: {err}");
+                                None
+                            }
+                        };
+                        *shared.lock().expect("poisoned") = Some(AdwaitaIdleState {
+                            idle_time: idle_time.map(Duration::from_millis),
+                        });
+                    },
+                );
+                glib::ControlFlow::Continue
+            });
+        },
+    );
+}
+
+pub(crate) fn sync_idle_state(
+    shared: Res<SharedIdleState>,
+    mut idle_state: ResMut<AdwaitaIdleState>,
+) {
+    if let Some(state) = shared.lock().expect("poisoned").take() {
+        idle_state.set_if_neq(state);
+    }
+}
@@ -0,0 +1,103 @@
+use adw::prelude::*;
+use bevy::math::{Rect, UVec2, Vec2};
+use gtk::gdk;
+
+use crate::adwaita_app::WindowEvent;
+use crate::cursor;
+
+/// Creates the input method context of a window, which sends
+/// [`WindowEvent::ImeCommit`]s and [`WindowEvent::ImePreedit`]s while it's enabled
+/// with [`set_ime_enabled`].
+///
+/// The candidate window is positioned relative to `render_target`.
+pub(crate) fn create_im_context(
+    render_target: &gtk::Picture,
+    send_event: &flume::Sender<WindowEvent>,
+) -> gtk::IMMulticontext {
+    let im_context = gtk::IMMulticontext::new();
+    im_context.set_client_widget(Some(render_target));
+    im_context.connect_commit({
+        let send_event = send_event.clone();
+        move |_, text| _ = send_event.send(WindowEvent::ImeCommit(text.to_owned()))
+    });
+    im_context.connect_preedit_changed({
+        let send_event = send_event.clone();
+        move |im_context| {
+            let (value, _, cursor) = im_context.preedit_string();
+            // GTK counts characters, but Bevy wants a byte index
+            let cursor = value
+                .char_indices()
+                .nth(usize::try_from(cursor).unwrap_or_default())
+                .map_or(value.len(), |(index, _)| index);
+            _ = send_event.send(WindowEvent::ImePreedit {
+                value: value.into(),
+                cursor: Some(cursor),
+            });
+        }
+    });
+    im_context.connect_preedit_end({
+        let send_event = send_event.clone();
+        move |_| {
+            // Bevy clears the preedit with an empty one, the same as winit
+            _ = send_event.send(WindowEvent::ImePreedit {
+                value: String::new(),
+                cursor: None,
+            });
+        }
+    });
+    im_context
+}
+
+/// Routes key presses through `im_context` while `enabled`, sending a
+/// [`WindowEvent::ImeEnabled`] once done.
+///
+/// Input methods consume keys which are part of a composition, and even the simple
+/// built-in one turns printable keys into commits, so the context is only attached
+/// while text input is actually wanted.
+pub(crate) fn set_ime_enabled(
+    window: &adw::Window,
+    key_controller: &gtk::EventControllerKey,
+    im_context: &gtk::IMMulticontext,
+    enabled: bool,
+    send_event: &flume::Sender<WindowEvent>,
+) {
+    if enabled {
+        key_controller.set_im_context(Some(im_context));
+        // the key controller only tells the context about focus changes from now on
+        if window.is_active() {
+            im_context.focus_in();
+        }
+    } else {
+        im_context.reset();
+        im_context.focus_out();
+        key_controller.set_im_context(None::<&gtk::IMContext>);
+    }
+    _ = send_event.send(WindowEvent::ImeEnabled(enabled));
+}
+
+/// Tells the input method where the text being edited is, so that it can place its
+/// candidate window next to it.
+///
+/// `area` is in physical pixels of a frame of `frame_size`, which is mapped to the
+/// area of `render_target` that the frame is drawn in.
+pub(crate) fn set_cursor_area(
+    im_context: &gtk::IMMulticontext,
+    render_target: &gtk::Picture,
+    frame_size: UVec2,
+    area: Rect,
+) {
+    let widget_size = Vec2::new(render_target.width() as f32, render_target.height() as f32);
+    let (Some(min), Some(max)) = (
+        cursor::widget_position(widget_size, frame_size, area.min),
+        cursor::widget_position(widget_size, frame_size, area.max),
+    ) else {
+        return;
+    };
+    let size = (max - min).max(Vec2::ONE);
+    im_context.set_cursor_location(&gdk::Rectangle::new(
+        min.x as i32,
+        min.y as i32,
+        size.x.ceil() as i32,
+        size.y.ceil() as i32,
+    ));
+}
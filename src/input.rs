@@ -0,0 +1,217 @@
+use std::{cell::RefCell, fmt, rc::Rc, sync::Arc};
+
+use adw::prelude::*;
+use bevy::{
+    input::ButtonState,
+    prelude::{Reflect, ReflectDefault},
+};
+use gtk::{gdk, glib};
+
+use crate::{adwaita_app::WindowEvent, keyboard};
+
+/// Whether key events reach the rendered content or the GTK widgets of a window
+/// first.
+///
+/// Keys that reach the content are sent as [`KeyboardInput`] events, and update
+/// [`ButtonInput<KeyCode>`].
+///
+/// [`KeyboardInput`]: bevy::input::keyboard::KeyboardInput
+/// [`ButtonInput<KeyCode>`]: bevy::input::ButtonInput
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[reflect(Default)]
+pub enum AdwaitaKeyPropagation {
+    /// Focused GTK widgets, such as header bar buttons, and the window's keyboard
+    /// shortcuts get key events first. The rendered content only gets the keys they
+    /// don't handle.
+    #[default]
+    Bubble,
+    /// The rendered content gets every key event before any GTK widget, so gameplay
+    /// keys never activate focused buttons, mnemonics or shortcuts.
+    ///
+    /// This includes keyboard navigation, such as Tab to move focus between widgets.
+    /// Dialogs and menus open in their own surfaces, so they still get keys.
+    Capture,
+}
+
+impl AdwaitaKeyPropagation {
+    pub(crate) const fn phase(self) -> gtk::PropagationPhase {
+        match self {
+            Self::Bubble => gtk::PropagationPhase::Bubble,
+            Self::Capture => gtk::PropagationPhase::Capture,
+        }
+    }
+}
+
+/// Forwards the key events sent to `window` to the rendered content, in the phase
+/// of `propagation`.
+///
+/// Change the phase of the returned controller to switch propagation later.
+pub(crate) fn attach_key_controller(
+    window: &adw::Window,
+    propagation: AdwaitaKeyPropagation,
+    filter: &SharedInputFilter,
+    send_event: &flume::Sender<WindowEvent>,
+) -> gtk::EventControllerKey {
+    let controller = gtk::EventControllerKey::new();
+    controller.set_propagation_phase(propagation.phase());
+    // GTK sends key repeats as more presses, the same as winit
+    controller.connect_key_pressed({
+        let filter = filter.clone();
+        let send_event = send_event.clone();
+        move |controller, keyval, keycode, _| {
+            if !send_key(
+                controller,
+                &filter,
+                &send_event,
+                keyval,
+                keycode,
+                ButtonState::Pressed,
+            ) {
+                return glib::Propagation::Proceed;
+            }
+            // whatever reaches the content is handled by it, so GTK doesn't act on
+            // the same key further along
+            glib::Propagation::Stop
+        }
+    });
+    controller.connect_key_released({
+        let filter = filter.clone();
+        let send_event = send_event.clone();
+        move |controller, keyval, keycode, _| {
+            send_key(
+                controller,
+                &filter,
+                &send_event,
+                keyval,
+                keycode,
+                ButtonState::Released,
+            );
+        }
+    });
+    window.add_controller(controller.clone());
+    controller
+}
+
+fn send_key(
+    controller: &gtk::EventControllerKey,
+    filter: &SharedInputFilter,
+    send_event: &flume::Sender<WindowEvent>,
+    keyval: gdk::Key,
+    keycode: u32,
+    state: ButtonState,
+) -> bool {
+    if let (Some(filter), Some(event)) = (&*filter.borrow(), controller.current_event()) {
+        if !filter.allows(&event) {
+            return false;
+        }
+    }
+    _ = send_event.send(WindowEvent::Key {
+        key_code: keyboard::key_code(keycode),
+        logical_key: keyboard::logical_key(keyval),
+        state,
+    });
+    true
+}
+
+/// Decides which raw input events over the rendered content of a window, and key
+/// events sent to it, are forwarded to Bevy.
+///
+/// The filter runs on the GTK thread for every event before it reaches the render
+/// target, so it should be quick, and can't access the Bevy world directly. Share any
+/// state it needs through atomics or a mutex. Events are filtered before any of the
+/// window's events are sent to Bevy, such as [`AdwaitaGesture`]s and
+/// [`KeyboardInput`]s.
+///
+/// Return `true` to forward the event, or `false` to suppress it. Suppressed events
+/// still reach the GTK widgets of the window, such as the header bar.
+///
+/// [`AdwaitaGesture`]: crate::AdwaitaGesture
+/// [`KeyboardInput`]: bevy::input::keyboard::KeyboardInput
+#[derive(Clone)]
+pub struct AdwaitaInputFilter(Arc<dyn Fn(&gdk::Event) -> bool + Send + Sync>);
+
+impl AdwaitaInputFilter {
+    pub fn new(f: impl Fn(&gdk::Event) -> bool + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    pub(crate) fn allows(&self, event: &gdk::Event) -> bool {
+        (self.0)(event)
+    }
+}
+
+impl fmt::Debug for AdwaitaInputFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AdwaitaInputFilter").finish_non_exhaustive()
+    }
+}
+
+/// Filter currently used by a window, only accessed on the GTK thread.
+pub(crate) type SharedInputFilter = Rc<RefCell<Option<AdwaitaInputFilter>>>;
+
+/// Runs `filter` on all events sent to `widget`, before any other controllers of
+/// `widget` see them.
+pub(crate) fn attach_input_filter(widget: &impl IsA<gtk::Widget>, filter: &SharedInputFilter) {
+    let controller = gtk::EventControllerLegacy::new();
+    controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+    controller.connect_event({
+        let filter = filter.clone();
+        move |_, event| match &*filter.borrow() {
+            Some(filter) if !filter.allows(event) => glib::Propagation::Stop,
+            _ => glib::Propagation::Proceed,
+        }
+    });
+    widget.add_controller(controller);
+}
+
+/// Logs every event sent to `widget` at trace level, before it's filtered or
+/// translated.
+pub(crate) fn attach_input_debug(widget: &impl IsA<gtk::Widget>) {
+    let controller = gtk::EventControllerLegacy::new();
+    controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+    controller.connect_event(|_, event| {
+        log_event(event);
+        glib::Propagation::Proceed
+    });
+    widget.add_controller(controller);
+}
+
+fn log_event(event: &gdk::Event) {
+    let event_type = event.event_type();
+    let device = event.device().map(|device| device.name());
+    let position = event.position();
+    let modifiers = event.modifier_state();
+
+    if let Some(event) = event.downcast_ref::<gdk::KeyEvent>() {
+        tracing::trace!(
+            ?event_type,
+            ?device,
+            keyval = ?event.keyval(),
+            keycode = event.keycode(),
+            ?modifiers,
+            "GDK event"
+        );
+    } else if let Some(event) = event.downcast_ref::<gdk::ButtonEvent>() {
+        tracing::trace!(
+            ?event_type,
+            ?device,
+            button = event.button(),
+            ?position,
+            ?modifiers,
+            "GDK event"
+        );
+    } else if let Some(event) = event.downcast_ref::<gdk::ScrollEvent>() {
+        tracing::trace!(
+            ?event_type,
+            ?device,
+            direction = ?event.direction(),
+            deltas = ?event.deltas(),
+            ?position,
+            ?modifiers,
+            "GDK event"
+        );
+    } else {
+        tracing::trace!(?event_type, ?device, ?position, ?modifiers, "GDK event");
+    }
+}
@@ -0,0 +1,335 @@
+//! Translates GDK key events into Bevy's [`KeyCode`]s and [`Key`]s.
+
+use adw::gdk;
+use adw::glib::translate::IntoGlib;
+use bevy::input::keyboard::{Key, KeyCode, NativeKey, NativeKeyCode};
+
+/// Physical key of a GDK hardware keycode.
+///
+/// On both Wayland and X11, GDK keycodes are XKB keycodes, which are the Linux evdev
+/// scancodes offset by 8.
+pub(crate) fn key_code(keycode: u32) -> KeyCode {
+    let Some(scancode) = keycode.checked_sub(8) else {
+        return KeyCode::Unidentified(NativeKeyCode::Xkb(keycode));
+    };
+    // from `linux/input-event-codes.h`
+    match scancode {
+        1 => KeyCode::Escape,
+        2 => KeyCode::Digit1,
+        3 => KeyCode::Digit2,
+        4 => KeyCode::Digit3,
+        5 => KeyCode::Digit4,
+        6 => KeyCode::Digit5,
+        7 => KeyCode::Digit6,
+        8 => KeyCode::Digit7,
+        9 => KeyCode::Digit8,
+        10 => KeyCode::Digit9,
+        11 => KeyCode::Digit0,
+        12 => KeyCode::Minus,
+        13 => KeyCode::Equal,
+        14 => KeyCode::Backspace,
+        15 => KeyCode::Tab,
+        16 => KeyCode::KeyQ,
+        17 => KeyCode::KeyW,
+        18 => KeyCode::KeyE,
+        19 => KeyCode::KeyR,
+        20 => KeyCode::KeyT,
+        21 => KeyCode::KeyY,
+        22 => KeyCode::KeyU,
+        23 => KeyCode::KeyI,
+        24 => KeyCode::KeyO,
+        25 => KeyCode::KeyP,
+        26 => KeyCode::BracketLeft,
+        27 => KeyCode::BracketRight,
+        28 => KeyCode::Enter,
+        29 => KeyCode::ControlLeft,
+        30 => KeyCode::KeyA,
+        31 => KeyCode::KeyS,
+        32 => KeyCode::KeyD,
+        33 => KeyCode::KeyF,
+        34 => KeyCode::KeyG,
+        35 => KeyCode::KeyH,
+        36 => KeyCode::KeyJ,
+        37 => KeyCode::KeyK,
+        38 => KeyCode::KeyL,
+        39 => KeyCode::Semicolon,
+        40 => KeyCode::Quote,
+        41 => KeyCode::Backquote,
+        42 => KeyCode::ShiftLeft,
+        43 => KeyCode::Backslash,
+        44 => KeyCode::KeyZ,
+        45 => KeyCode::KeyX,
+        46 => KeyCode::KeyC,
+        47 => KeyCode::KeyV,
+        48 => KeyCode::KeyB,
+        49 => KeyCode::KeyN,
+        50 => KeyCode::KeyM,
+        51 => KeyCode::Comma,
+        52 => KeyCode::Period,
+        53 => KeyCode::Slash,
+        54 => KeyCode::ShiftRight,
+        55 => KeyCode::NumpadMultiply,
+        56 => KeyCode::AltLeft,
+        57 => KeyCode::Space,
+        58 => KeyCode::CapsLock,
+        59 => KeyCode::F1,
+        60 => KeyCode::F2,
+        61 => KeyCode::F3,
+        62 => KeyCode::F4,
+        63 => KeyCode::F5,
+        64 => KeyCode::F6,
+        65 => KeyCode::F7,
+        66 => KeyCode::F8,
+        67 => KeyCode::F9,
+        68 => KeyCode::F10,
+        69 => KeyCode::NumLock,
+        70 => KeyCode::ScrollLock,
+        71 => KeyCode::Numpad7,
+        72 => KeyCode::Numpad8,
+        73 => KeyCode::Numpad9,
+        74 => KeyCode::NumpadSubtract,
+        75 => KeyCode::Numpad4,
+        76 => KeyCode::Numpad5,
+        77 => KeyCode::Numpad6,
+        78 => KeyCode::NumpadAdd,
+        79 => KeyCode::Numpad1,
+        80 => KeyCode::Numpad2,
+        81 => KeyCode::Numpad3,
+        82 => KeyCode::Numpad0,
+        83 => KeyCode::NumpadDecimal,
+        85 => KeyCode::Lang5,
+        86 => KeyCode::IntlBackslash,
+        87 => KeyCode::F11,
+        88 => KeyCode::F12,
+        89 => KeyCode::IntlRo,
+        90 => KeyCode::Lang3,
+        91 => KeyCode::Lang4,
+        92 => KeyCode::Convert,
+        93 => KeyCode::KanaMode,
+        94 => KeyCode::NonConvert,
+        96 => KeyCode::NumpadEnter,
+        97 => KeyCode::ControlRight,
+        98 => KeyCode::NumpadDivide,
+        99 => KeyCode::PrintScreen,
+        100 => KeyCode::AltRight,
+        102 => KeyCode::Home,
+        103 => KeyCode::ArrowUp,
+        104 => KeyCode::PageUp,
+        105 => KeyCode::ArrowLeft,
+        106 => KeyCode::ArrowRight,
+        107 => KeyCode::End,
+        108 => KeyCode::ArrowDown,
+        109 => KeyCode::PageDown,
+        110 => KeyCode::Insert,
+        111 => KeyCode::Delete,
+        113 => KeyCode::AudioVolumeMute,
+        114 => KeyCode::AudioVolumeDown,
+        115 => KeyCode::AudioVolumeUp,
+        116 => KeyCode::Power,
+        117 => KeyCode::NumpadEqual,
+        119 => KeyCode::Pause,
+        121 => KeyCode::NumpadComma,
+        122 => KeyCode::Lang1,
+        123 => KeyCode::Lang2,
+        124 => KeyCode::IntlYen,
+        125 => KeyCode::SuperLeft,
+        126 => KeyCode::SuperRight,
+        127 => KeyCode::ContextMenu,
+        128 => KeyCode::BrowserStop,
+        129 => KeyCode::Again,
+        130 => KeyCode::Props,
+        131 => KeyCode::Undo,
+        132 => KeyCode::Select,
+        133 => KeyCode::Copy,
+        134 => KeyCode::Open,
+        135 => KeyCode::Paste,
+        136 => KeyCode::Find,
+        137 => KeyCode::Cut,
+        138 => KeyCode::Help,
+        140 => KeyCode::LaunchApp2,
+        142 => KeyCode::Sleep,
+        143 => KeyCode::WakeUp,
+        144 => KeyCode::LaunchApp1,
+        155 => KeyCode::LaunchMail,
+        156 => KeyCode::BrowserFavorites,
+        158 => KeyCode::BrowserBack,
+        159 => KeyCode::BrowserForward,
+        161 => KeyCode::Eject,
+        163 => KeyCode::MediaTrackNext,
+        164 => KeyCode::MediaPlayPause,
+        165 => KeyCode::MediaTrackPrevious,
+        166 => KeyCode::MediaStop,
+        172 => KeyCode::BrowserHome,
+        173 => KeyCode::BrowserRefresh,
+        183 => KeyCode::F13,
+        184 => KeyCode::F14,
+        185 => KeyCode::F15,
+        186 => KeyCode::F16,
+        187 => KeyCode::F17,
+        188 => KeyCode::F18,
+        189 => KeyCode::F19,
+        190 => KeyCode::F20,
+        191 => KeyCode::F21,
+        192 => KeyCode::F22,
+        193 => KeyCode::F23,
+        194 => KeyCode::F24,
+        217 => KeyCode::BrowserSearch,
+        226 => KeyCode::MediaSelect,
+        _ => KeyCode::Unidentified(NativeKeyCode::Xkb(keycode)),
+    }
+}
+
+/// Logical key of a GDK keyval, after the keyboard layout and modifiers have been
+/// applied.
+pub(crate) fn logical_key(keyval: gdk::Key) -> Key {
+    if let Some(key) = named_key(keyval) {
+        return key;
+    }
+    if let Some(key) = dead_key(keyval) {
+        return key;
+    }
+    match keyval.to_unicode() {
+        Some(c) if !c.is_control() => Key::Character(c.to_string().into()),
+        _ => Key::Unidentified(NativeKey::Xkb(keyval.into_glib())),
+    }
+}
+
+fn named_key(keyval: gdk::Key) -> Option<Key> {
+    Some(match keyval {
+        gdk::Key::Escape => Key::Escape,
+        gdk::Key::Return | gdk::Key::KP_Enter | gdk::Key::ISO_Enter => Key::Enter,
+        gdk::Key::Tab | gdk::Key::KP_Tab | gdk::Key::ISO_Left_Tab => Key::Tab,
+        gdk::Key::space | gdk::Key::KP_Space => Key::Space,
+        gdk::Key::BackSpace => Key::Backspace,
+        gdk::Key::Delete | gdk::Key::KP_Delete => Key::Delete,
+        gdk::Key::Insert | gdk::Key::KP_Insert => Key::Insert,
+        gdk::Key::Home | gdk::Key::KP_Home => Key::Home,
+        gdk::Key::End | gdk::Key::KP_End => Key::End,
+        gdk::Key::Page_Up | gdk::Key::KP_Page_Up => Key::PageUp,
+        gdk::Key::Page_Down | gdk::Key::KP_Page_Down => Key::PageDown,
+        gdk::Key::Left | gdk::Key::KP_Left => Key::ArrowLeft,
+        gdk::Key::Right | gdk::Key::KP_Right => Key::ArrowRight,
+        gdk::Key::Up | gdk::Key::KP_Up => Key::ArrowUp,
+        gdk::Key::Down | gdk::Key::KP_Down => Key::ArrowDown,
+        gdk::Key::Clear | gdk::Key::KP_Begin => Key::Clear,
+
+        gdk::Key::Shift_L | gdk::Key::Shift_R => Key::Shift,
+        gdk::Key::Control_L | gdk::Key::Control_R => Key::Control,
+        gdk::Key::Alt_L | gdk::Key::Alt_R => Key::Alt,
+        gdk::Key::ISO_Level3_Shift => Key::AltGraph,
+        gdk::Key::Meta_L | gdk::Key::Meta_R => Key::Meta,
+        gdk::Key::Super_L | gdk::Key::Super_R => Key::Super,
+        gdk::Key::Hyper_L | gdk::Key::Hyper_R => Key::Hyper,
+        gdk::Key::Caps_Lock => Key::CapsLock,
+        gdk::Key::Num_Lock => Key::NumLock,
+        gdk::Key::Scroll_Lock => Key::ScrollLock,
+        gdk::Key::Multi_key => Key::Compose,
+        gdk::Key::Mode_switch => Key::ModeChange,
+
+        gdk::Key::Menu => Key::ContextMenu,
+        gdk::Key::Print | gdk::Key::Sys_Req => Key::PrintScreen,
+        gdk::Key::Pause | gdk::Key::Break => Key::Pause,
+        gdk::Key::Cancel => Key::Cancel,
+        gdk::Key::Execute => Key::Execute,
+        gdk::Key::Select => Key::Select,
+        gdk::Key::Help => Key::Help,
+        gdk::Key::Find => Key::Find,
+        gdk::Key::Undo => Key::Undo,
+        gdk::Key::Redo => Key::Redo,
+        gdk::Key::Copy => Key::Copy,
+        gdk::Key::Cut => Key::Cut,
+        gdk::Key::Paste => Key::Paste,
+        gdk::Key::Open => Key::Open,
+        gdk::Key::Close => Key::Close,
+        gdk::Key::New => Key::New,
+        gdk::Key::Save => Key::Save,
+        gdk::Key::ZoomIn => Key::ZoomIn,
+        gdk::Key::ZoomOut => Key::ZoomOut,
+
+        gdk::Key::Henkan => Key::Convert,
+        gdk::Key::Muhenkan => Key::NonConvert,
+        gdk::Key::Kanji => Key::KanjiMode,
+        gdk::Key::Hiragana => Key::Hiragana,
+        gdk::Key::Katakana => Key::Katakana,
+        gdk::Key::Hiragana_Katakana => Key::HiraganaKatakana,
+        gdk::Key::Zenkaku_Hankaku => Key::ZenkakuHankaku,
+        gdk::Key::Romaji => Key::Romaji,
+        gdk::Key::Eisu_toggle => Key::Eisu,
+        gdk::Key::Hangul => Key::HangulMode,
+        gdk::Key::Hangul_Hanja => Key::HanjaMode,
+
+        gdk::Key::AudioPlay => Key::MediaPlayPause,
+        gdk::Key::AudioPause => Key::MediaPause,
+        gdk::Key::AudioStop => Key::MediaStop,
+        gdk::Key::AudioNext => Key::MediaTrackNext,
+        gdk::Key::AudioPrev => Key::MediaTrackPrevious,
+        gdk::Key::AudioRecord => Key::MediaRecord,
+        gdk::Key::AudioRewind => Key::MediaRewind,
+        gdk::Key::AudioForward => Key::MediaFastForward,
+        gdk::Key::AudioMute => Key::AudioVolumeMute,
+        gdk::Key::AudioLowerVolume => Key::AudioVolumeDown,
+        gdk::Key::AudioRaiseVolume => Key::AudioVolumeUp,
+        gdk::Key::AudioMicMute => Key::MicrophoneVolumeMute,
+        gdk::Key::MonBrightnessUp => Key::BrightnessUp,
+        gdk::Key::MonBrightnessDown => Key::BrightnessDown,
+        gdk::Key::Eject => Key::Eject,
+        gdk::Key::PowerOff => Key::PowerOff,
+        gdk::Key::Sleep => Key::Standby,
+        gdk::Key::WakeUp => Key::WakeUp,
+        gdk::Key::LogOff => Key::LogOff,
+        gdk::Key::ScreenSaver => Key::LaunchScreenSaver,
+        gdk::Key::Back => Key::BrowserBack,
+        gdk::Key::Forward => Key::BrowserForward,
+        gdk::Key::Refresh => Key::BrowserRefresh,
+        gdk::Key::Stop => Key::BrowserStop,
+        gdk::Key::Search => Key::BrowserSearch,
+        gdk::Key::Favorites => Key::BrowserFavorites,
+        gdk::Key::HomePage => Key::BrowserHome,
+        gdk::Key::WWW => Key::LaunchWebBrowser,
+        gdk::Key::Mail => Key::LaunchMail,
+        gdk::Key::Calculator => Key::LaunchApplication2,
+        gdk::Key::MyComputer => Key::LaunchApplication1,
+
+        gdk::Key::F1 => Key::F1,
+        gdk::Key::F2 => Key::F2,
+        gdk::Key::F3 => Key::F3,
+        gdk::Key::F4 => Key::F4,
+        gdk::Key::F5 => Key::F5,
+        gdk::Key::F6 => Key::F6,
+        gdk::Key::F7 => Key::F7,
+        gdk::Key::F8 => Key::F8,
+        gdk::Key::F9 => Key::F9,
+        gdk::Key::F10 => Key::F10,
+        gdk::Key::F11 => Key::F11,
+        gdk::Key::F12 => Key::F12,
+        gdk::Key::F13 => Key::F13,
+        gdk::Key::F14 => Key::F14,
+        gdk::Key::F15 => Key::F15,
+        gdk::Key::F16 => Key::F16,
+        gdk::Key::F17 => Key::F17,
+        gdk::Key::F18 => Key::F18,
+        gdk::Key::F19 => Key::F19,
+        gdk::Key::F20 => Key::F20,
+        gdk::Key::F21 => Key::F21,
+        gdk::Key::F22 => Key::F22,
+        gdk::Key::F23 => Key::F23,
+        gdk::Key::F24 => Key::F24,
+        _ => return None,
+    })
+}
+
+fn dead_key(keyval: gdk::Key) -> Option<Key> {
+    // the spacing versions of the accents, matching what winit reports
+    let accent = match keyval {
+        gdk::Key::dead_grave => Some('`'),
+        gdk::Key::dead_acute => Some('´'),
+        gdk::Key::dead_circumflex => Some('^'),
+        gdk::Key::dead_tilde => Some('~'),
+        gdk::Key::dead_diaeresis => Some('¨'),
+        gdk::Key::dead_cedilla => Some('¸'),
+        _ => None,
+    };
+    let is_dead = (gdk::Key::dead_grave.into_glib()..=gdk::Key::dead_greek.into_glib())
+        .contains(&keyval.into_glib());
+    is_dead.then_some(Key::Dead(accent))
+}
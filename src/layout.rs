@@ -0,0 +1,102 @@
+use std::{cell::RefCell, fmt, sync::Arc};
+
+use gtk::prelude::*;
+
+/// Custom layout for the content of an Adwaita window.
+///
+/// The function runs on the GTK thread when the window is opened, and returns the
+/// widget that takes the place of the render target in the window. The header bar
+/// configured in [`AdwaitaHeaderBar`] is still added around this widget.
+///
+/// Use [`AdwaitaLayoutContext::render_target`] to place the main render target
+/// somewhere in your layout.
+///
+/// [`AdwaitaHeaderBar`]: crate::AdwaitaHeaderBar
+#[derive(Clone)]
+pub struct AdwaitaLayout(Arc<dyn Fn(&AdwaitaLayoutContext) -> gtk::Widget + Send + Sync>);
+
+impl AdwaitaLayout {
+    pub fn new(f: impl Fn(&AdwaitaLayoutContext) -> gtk::Widget + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    pub(crate) fn build(&self, cx: &AdwaitaLayoutContext) -> gtk::Widget {
+        (self.0)(cx)
+    }
+}
+
+impl fmt::Debug for AdwaitaLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AdwaitaLayout").finish_non_exhaustive()
+    }
+}
+
+/// Widgets available to an [`AdwaitaLayout`] while building the window content.
+#[derive(Debug)]
+pub struct AdwaitaLayoutContext {
+    render_target: gtk::Widget,
+    previews: RefCell<Vec<gtk::Picture>>,
+}
+
+impl AdwaitaLayoutContext {
+    pub(crate) fn new(render_target: gtk::Widget) -> Self {
+        Self {
+            render_target,
+            previews: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn into_previews(self) -> Vec<gtk::Picture> {
+        self.previews.into_inner()
+    }
+
+    /// Widget displaying the frames rendered by Bevy.
+    ///
+    /// This widget is also used to detect the size of the render target, so it
+    /// must be added to the layout exactly once.
+    #[must_use]
+    pub fn render_target(&self) -> &gtk::Widget {
+        &self.render_target
+    }
+
+    /// Wraps the render target in an overlay, with `widget` drawn over it.
+    ///
+    /// This is the supported way to mix native GTK drawing with the Bevy-rendered
+    /// content, for example a `gtk::GLArea` or `gtk::DrawingArea` drawing a HUD. Use
+    /// the returned overlay in place of [`AdwaitaLayoutContext::render_target`], and
+    /// add any further overlays to it.
+    ///
+    /// GTK draws the overlay on the GTK thread whenever the widget is redrawn, which
+    /// isn't synchronized with the frames rendered by Bevy, so its contents may be a
+    /// frame ahead of or behind the scene. A `gtk::GLArea` uses its own GL context, and
+    /// can't share resources with Bevy's renderer. The frame is still offloaded to the
+    /// compositor if it can be placed below the window contents, which requires the
+    /// default black letterbox color, otherwise GTK composites the two itself.
+    ///
+    /// The overlay receives input over the area it covers. Set
+    /// [`can_target`](gtk::prelude::WidgetExt::set_can_target) to `false` on `widget`
+    /// to let input reach the render target instead.
+    #[must_use]
+    pub fn render_target_with_overlay(&self, widget: &impl IsA<gtk::Widget>) -> gtk::Overlay {
+        let overlay = gtk::Overlay::new();
+        overlay.set_child(Some(&self.render_target));
+        overlay.add_overlay(widget);
+        overlay
+    }
+
+    /// Creates a picture which displays the same frames as the main render target.
+    ///
+    /// The same dmabuf-backed texture is shared between all pictures, so this does
+    /// not copy the frame. GTK scales the texture to the picture's size, making this
+    /// suitable for thumbnails or minimaps. The size of a preview doesn't affect the
+    /// render target size.
+    #[must_use]
+    pub fn create_preview(&self) -> gtk::Picture {
+        let preview = gtk::Picture::builder()
+            .content_fit(gtk::ContentFit::Contain)
+            .can_shrink(true)
+            .build();
+        self.previews.borrow_mut().push(preview.clone());
+        preview
+    }
+}
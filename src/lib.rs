@@ -1,73 +1,479 @@
+mod about;
+mod accent_color;
 mod adwaita_app;
+mod attention;
+mod capture;
+mod clipboard;
+mod color_scheme;
+mod cursor;
+mod depth;
+mod diagnostics;
+mod dialog;
+mod dynamic_resolution;
+mod file_drop;
+mod frame_clock;
+mod frame_stats;
+mod gesture;
+mod grab;
 mod hal_custom;
+mod header_bar;
+mod icon;
+#[cfg(feature = "idle-monitor")]
+mod idle;
+mod ime;
+mod input;
+mod keyboard;
+mod layout;
+mod lifecycle;
+mod monitor;
+mod pointer;
+#[cfg(feature = "wayland")]
+mod pointer_constraints;
+mod position;
+#[cfg(feature = "upower")]
+mod power;
+#[cfg(feature = "gstreamer")]
+mod recording;
 mod render;
+mod scroll;
+mod toast;
+mod touch;
+#[cfg(feature = "wayland")]
+mod wayland;
+
+pub use {adw, gtk};
+
+pub use about::AdwaitaAboutInfo;
+pub use accent_color::AdwaitaAccentColor;
+pub use capture::AdwaitaFrameCaptured;
+pub use clipboard::AdwaitaClipboardText;
+pub use color_scheme::{AdwaitaColorScheme, AdwaitaColorSchemePreference};
+pub use depth::AdwaitaDepthReadback;
+pub use diagnostics::{
+    AdwaitaAdapterDiagnostics, AdwaitaAdapterType, AdwaitaDisplayChanged, AdwaitaDmabufModifier,
+    AdwaitaDmabufModifiers, AdwaitaRenderStats, AdwaitaRenderTargetFormats, AdwaitaSupportedFormat,
+    GPU_FRAME_TIME_SAMPLES,
+};
+pub use dialog::{
+    AdwaitaButtonAppearance, AdwaitaDialog, AdwaitaDialogButton, AdwaitaDialogId,
+    AdwaitaDialogResponse,
+};
+pub use dynamic_resolution::AdwaitaDynamicResolution;
+pub use file_drop::{AdwaitaFileDropped, AdwaitaFileHovered};
+pub use frame_clock::AdwaitaFrameTick;
+pub use frame_stats::{AdwaitaFrameStats, AdwaitaWindowFrameStats};
+pub use gesture::{AdwaitaGesture, AdwaitaGestureKind, AdwaitaGesturePhase};
+pub use header_bar::{AdwaitaHeaderAction, AdwaitaHeaderItem, AdwaitaHeaderPack, AdwaitaMenuItem};
+pub use icon::{AdwaitaWindowIcon, AdwaitaWindowIconImage};
+#[cfg(feature = "idle-monitor")]
+pub use idle::AdwaitaIdleState;
+pub use input::{AdwaitaInputFilter, AdwaitaKeyPropagation};
+pub use layout::{AdwaitaLayout, AdwaitaLayoutContext};
+pub use monitor::{AdwaitaMonitorChanged, AdwaitaMonitorInfo};
+#[cfg(feature = "upower")]
+pub use power::AdwaitaPowerState;
+pub use render::{
+    AdwaitaAdapterSelection, AdwaitaDrmNode, AdwaitaFrameBackpressure, AdwaitaPresentMode,
+    AdwaitaPresentedFrame, AdwaitaRenderError, AdwaitaRenderTargetFormat,
+};
+pub use scroll::AdwaitaScrollFilter;
+pub use toast::{AdwaitaToast, AdwaitaToastDismissed, AdwaitaToastId};
 
 use std::{
     any::type_name,
+    future::Future,
     sync::{
-        atomic::{AtomicBool, AtomicI32, Ordering},
-        Arc,
+        atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
     },
-    thread,
+    time::{Duration, Instant},
 };
 
-use adwaita_app::{WindowCommand, WindowOpen};
+use accent_color::SharedAccentColor;
+use adwaita_app::{
+    AppRegistration, MainThread, MainThreadRequest, SharedCommandQueue, SharedDisplayInfo,
+    WindowCommand, WindowEvent, WindowOpen,
+};
 use atomicbox::AtomicOptionBox;
 use bevy::{
-    ecs::system::EntityCommand,
+    core::FrameCount,
+    ecs::{
+        schedule::{InternedScheduleLabel, ScheduleLabel},
+        system::{EntityCommand, SystemParam},
+    },
+    input::{
+        keyboard::{KeyboardFocusLost, KeyboardInput},
+        mouse::{MouseButtonInput, MouseMotion, MouseWheel},
+        InputSystem,
+    },
     prelude::*,
     render::{
-        camera::{ManualTextureViewHandle, ManualTextureViews, RenderTarget},
-        renderer::RenderDevice,
+        camera::{
+            CameraUpdateSystem, ManualTextureViewHandle, ManualTextureViews, RenderTarget, Viewport,
+        },
+        renderer::{render_system, RenderDevice},
         settings::WgpuSettings,
         Extract, Render, RenderApp, RenderPlugin, RenderSet,
     },
-    window::{ExitCondition, WindowRef},
+    window::{
+        AppLifecycle, CursorEntered, CursorGrabMode, CursorIcon, CursorLeft, CursorMoved,
+        ExitCondition, FileDragAndDrop, Ime, WindowCloseRequested, WindowFocused, WindowMoved,
+        WindowRef, WindowResized,
+    },
+};
+use capture::{PendingFrameCaptures, SharedFrameCaptures};
+use color_scheme::SharedColorScheme;
+use depth::{PendingDepthReadbacks, SharedDepthReadbacks};
+use diagnostics::DiagnosticsPlugin;
+use dynamic_resolution::DynamicResolutionState;
+use frame_stats::SharedFrameCounters;
+use monitor::SharedMonitorInfo;
+use render::{
+    FrameDamage, FrameInfo, PresentWaiter, RenderTargetRing, RingImage, SharedConsumedFrame,
+    SharedPresentWaiters,
 };
-use render::{DmabufInfo, FrameInfo};
 
+/// Opens Adwaita windows for [`AdwaitaWindow`] entities, and shows the frames that
+/// Bevy renders to them.
+///
+/// GTK runs on its own thread, which is started the first time this plugin is built
+/// and shared by every app in the process. This means that multiple independent
+/// [`App`]s, each with their own renderer, can use this plugin at the same time to
+/// render to separate windows (see the `multi_app` example). Each app's windows and
+/// render targets are only ever accessed by that app, and are freed once the app
+/// drops them.
+///
+/// Once every app using the thread has sent an [`AppExit`] or been dropped, GTK
+/// destroys the remaining windows and the thread stops. GTK can't be started again
+/// after that.
+///
+/// # Panics
+///
+/// Building this plugin panics if the GTK thread has already stopped, because every
+/// app which used it before has exited.
 #[derive(Clone)]
 pub struct AdwaitaWindowPlugin {
     pub primary_window_config: Option<AdwaitaWindowConfig>,
     pub exit_condition: ExitCondition,
+    /// Reverse-DNS ID of the app, such as `com.example.Game`, which the desktop uses
+    /// to match windows to the app's `.desktop` file for their name and icon.
+    ///
+    /// This becomes the Wayland app ID and the X11 `WM_CLASS` of every window. If
+    /// [`None`] or malformed, the name of the executable is used instead. GTK runs
+    /// once per process, so only the ID of the first app to build this plugin is
+    /// used.
+    pub application_id: Option<String>,
+    /// Whether cameras targeting [`WindowRef::Primary`] are automatically changed to
+    /// render to the [`PrimaryAdwaitaWindow`] instead.
+    ///
+    /// Disable this if you manage camera render targets yourself, or with
+    /// [`RenderToAdwaitaWindow`].
+    pub auto_retarget_cameras: bool,
+    /// Whether to send [`AppLifecycle`] events when GTK suspends or resumes the
+    /// Adwaita windows, for example when they are minimized.
+    ///
+    /// The app is suspended when all of its windows are suspended. This is disabled
+    /// by default, since desktop apps usually don't expect to be suspended.
+    ///
+    /// [`AppLifecycle`]: bevy::window::AppLifecycle
+    pub app_lifecycle_events: bool,
+    /// Whether to wait for an [`OpenPrimaryWindow`] event before opening the primary
+    /// window, instead of opening it when the plugin is built.
+    ///
+    /// Use this to finish any setup before showing a window. The app doesn't exit
+    /// because of [`exit_condition`] until the primary window has been opened.
+    ///
+    /// [`exit_condition`]: AdwaitaWindowPlugin::exit_condition
+    pub defer_primary_window: bool,
+    /// What happens to the entity of an [`AdwaitaWindow`] when the user closes it.
+    pub close_behavior: AdwaitaCloseBehavior,
+    /// Whether windows close as soon as the user asks to close them.
+    ///
+    /// Disable this to handle [`AdwaitaCloseRequested`] yourself, for example to ask
+    /// the user to save first, and call [`AdwaitaWindow::close`] once you're done.
+    pub close_when_requested: bool,
+    /// Schedule that [`AdwaitaSet::PollWindows`] runs in, along with the systems
+    /// ordered around it, such as the ones updating [`AdwaitaAccentColor`] and the
+    /// render diagnostics.
+    ///
+    /// By default, this is [`PreUpdate`], so that window state and input from GTK are
+    /// up to date for all of your game logic. If you move it to a later schedule:
+    /// - input events are forwarded after Bevy's [`InputSystem`] has already run, so
+    ///   [`ButtonInput`] only sees them on the next frame
+    /// - systems running before it see the window size, and events such as
+    ///   [`AdwaitaWindowClosed`], from the previous frame
+    /// - cameras keep rendering at the old size for one more frame after a resize
+    ///
+    /// Cameras are retargeted by observers (see [`auto_retarget_cameras`]), which
+    /// don't depend on this schedule.
+    ///
+    /// [`auto_retarget_cameras`]: AdwaitaWindowPlugin::auto_retarget_cameras
+    pub poll_schedule: InternedScheduleLabel,
 }
 
 impl Default for AdwaitaWindowPlugin {
     fn default() -> Self {
         Self {
             primary_window_config: Some(AdwaitaWindowConfig::default()),
+            application_id: None,
             exit_condition: ExitCondition::OnAllClosed,
+            auto_retarget_cameras: true,
+            app_lifecycle_events: false,
+            defer_primary_window: false,
+            close_behavior: AdwaitaCloseBehavior::default(),
+            close_when_requested: true,
+            poll_schedule: PreUpdate.intern(),
         }
     }
 }
 
 impl Plugin for AdwaitaWindowPlugin {
     fn build(&self, app: &mut App) {
-        let (send_window_open, recv_window_open) = flume::bounded::<WindowOpen>(1);
-        thread::spawn(|| adwaita_app::main_thread_loop(recv_window_open));
+        let application_id = self.application_id.as_deref().filter(|id| {
+            let valid = adw::gio::Application::id_is_valid(id);
+            if !valid {
+                warn!(
+                    "Application ID \"{id}\" isn't a valid reverse-DNS ID such as \
+                     `com.example.Game`, using the executable name instead"
+                );
+            }
+            valid
+        });
+        let main_thread = MainThread::get(application_id);
+        let registration = main_thread
+            .register_app()
+            .unwrap_or_else(|err| panic!("Failed to build `AdwaitaWindowPlugin`: {err}"));
+        app.insert_resource(registration).add_systems(
+            Last,
+            release_main_thread_on_exit.run_if(resource_exists::<AppRegistration>),
+        );
+        let accent_color = SharedAccentColor::default();
+        _ = main_thread
+            .send_request
+            .send(MainThreadRequest::WatchAccentColor(accent_color.clone()));
+        let color_scheme = SharedColorScheme::default();
+        _ = main_thread
+            .send_request
+            .send(MainThreadRequest::WatchColorScheme(color_scheme.clone()));
+
+        app.insert_resource(SharedDisplay(main_thread.display_info.clone()))
+            .register_type::<AdwaitaAccentColor>()
+            .register_type::<AdwaitaColorScheme>()
+            .register_type::<AdwaitaWindowSize>()
+            .register_type::<AdwaitaWindowFocused>()
+            .register_type::<AdwaitaGesture>()
+            .add_event::<AdwaitaGesture>()
+            .add_event::<MouseWheel>()
+            .add_event::<KeyboardInput>()
+            .add_event::<KeyboardFocusLost>()
+            .add_event::<WindowFocused>()
+            .add_event::<WindowResized>()
+            .add_event::<WindowMoved>()
+            .add_event::<MouseButtonInput>()
+            .add_event::<MouseMotion>()
+            .add_event::<CursorMoved>()
+            .add_event::<CursorEntered>()
+            .add_event::<CursorLeft>()
+            .register_type::<AdwaitaDepthReadback>()
+            .add_event::<AdwaitaDepthReadback>()
+            .register_type::<AdwaitaFrameCaptured>()
+            .add_event::<AdwaitaFrameCaptured>()
+            .register_type::<AdwaitaFrameTick>()
+            .add_event::<AdwaitaFrameTick>()
+            .register_type::<AdwaitaFrameStats>()
+            .init_resource::<AdwaitaFrameStats>()
+            .register_type::<AdwaitaDialogResponse>()
+            .add_event::<AdwaitaDialogResponse>()
+            .register_type::<AdwaitaToastDismissed>()
+            .add_event::<AdwaitaToastDismissed>()
+            .register_type::<AdwaitaClipboardText>()
+            .add_event::<AdwaitaClipboardText>()
+            .register_type::<AdwaitaFileDropped>()
+            .add_event::<AdwaitaFileDropped>()
+            .register_type::<AdwaitaFileHovered>()
+            .add_event::<AdwaitaFileHovered>()
+            .add_event::<FileDragAndDrop>()
+            .register_type::<AdwaitaMonitorChanged>()
+            .add_event::<AdwaitaMonitorChanged>()
+            .register_type::<AdwaitaHeaderAction>()
+            .add_event::<AdwaitaHeaderAction>()
+            .init_resource::<AdwaitaAccentColor>()
+            .insert_resource(accent_color)
+            .init_resource::<AdwaitaColorScheme>()
+            .insert_resource(color_scheme)
+            .add_systems(
+                self.poll_schedule,
+                (
+                    accent_color::sync_accent_color,
+                    color_scheme::sync_color_scheme,
+                )
+                    .before(AdwaitaSet::PollWindows),
+            )
+            .add_systems(
+                self.poll_schedule,
+                frame_stats::sync_frame_stats.after(AdwaitaSet::PollWindows),
+            )
+            .add_plugins(DiagnosticsPlugin {
+                schedule: self.poll_schedule,
+            })
+            .insert_resource(SendWindowOpen(main_thread.send_request.clone()))
+            // `ButtonInput`s are updated from the forwarded events
+            .configure_sets(
+                self.poll_schedule,
+                AdwaitaSet::PollWindows.before(InputSystem),
+            )
+            .add_systems(
+                self.poll_schedule,
+                (
+                    forward_window_events,
+                    depth::send_depth_readbacks,
+                    capture::send_frame_captures,
+                    sync_window_cursors,
+                    sync_window_ime,
+                    dynamic_resolution::update_dynamic_resolution,
+                    poll_windows,
+                    sync_window_sizes,
+                    update_preallocated_viewports,
+                    pause_cameras,
+                    expire_present_waiters,
+                )
+                    .chain()
+                    .in_set(AdwaitaSet::PollWindows),
+            );
+
+        #[cfg(feature = "upower")]
+        {
+            let power_state = power::SharedPowerState::default();
+            _ = main_thread
+                .send_request
+                .send(MainThreadRequest::WatchPowerState(power_state.clone()));
+            app.register_type::<AdwaitaPowerState>()
+                .init_resource::<AdwaitaPowerState>()
+                .insert_resource(power_state)
+                .add_systems(
+                    self.poll_schedule,
+                    power::sync_power_state.before(AdwaitaSet::PollWindows),
+                );
+        }
+
+        #[cfg(feature = "idle-monitor")]
+        {
+            let idle_state = idle::SharedIdleState::default();
+            _ = main_thread
+                .send_request
+                .send(MainThreadRequest::WatchIdleState(idle_state.clone()));
+            app.register_type::<AdwaitaIdleState>()
+                .init_resource::<AdwaitaIdleState>()
+                .insert_resource(idle_state)
+                .add_systems(
+                    self.poll_schedule,
+                    idle::sync_idle_state.before(AdwaitaSet::PollWindows),
+                );
+        }
+
+        app.observe(release_render_targets);
+
+        if self.auto_retarget_cameras {
+            app.observe(update_default_camera_render_target)
+                .observe(update_existing_cameras_render_target);
+        }
+
+        if self.app_lifecycle_events {
+            app.add_event::<AppLifecycle>().add_systems(
+                self.poll_schedule,
+                lifecycle::emit_app_lifecycle.after(AdwaitaSet::PollWindows),
+            );
+        }
+
+        app.insert_resource(self.close_behavior)
+            .register_type::<AdwaitaCloseBehavior>()
+            .register_type::<AdwaitaWindowClosed>()
+            .add_event::<AdwaitaWindowClosed>()
+            .register_type::<AdwaitaCloseRequested>()
+            .add_event::<AdwaitaCloseRequested>();
+        if self.close_when_requested {
+            app.add_systems(
+                self.poll_schedule,
+                close_when_requested.after(AdwaitaSet::PollWindows),
+            );
+        }
+
+        app.register_type::<AdwaitaWindowIcon>()
+            .register_type::<AdwaitaWindowIconImage>()
+            .add_systems(
+                self.poll_schedule,
+                icon::sync_window_icon_images
+                    .run_if(resource_exists::<Assets<Image>>)
+                    .before(AdwaitaSet::PollWindows),
+            );
+
+        app.register_type::<RenderToAdwaitaWindow>().add_systems(
+            PostUpdate,
+            retarget_bound_cameras.before(CameraUpdateSystem),
+        );
 
-        app.insert_resource(SendWindowOpen(send_window_open))
-            .add_systems(PreUpdate, poll_windows)
-            .observe(update_default_camera_render_target)
-            .observe(update_existing_cameras_render_target);
+        app.add_event::<OpenPrimaryWindow>()
+            .configure_sets(
+                PostUpdate,
+                AdwaitaSet::ExitOnClose.run_if(not(resource_exists::<PendingPrimaryWindow>)),
+            )
+            .add_systems(
+                self.poll_schedule,
+                open_pending_primary_window
+                    .run_if(resource_exists::<PendingPrimaryWindow>)
+                    .before(AdwaitaSet::PollWindows),
+            );
 
         match self.exit_condition {
             ExitCondition::OnPrimaryClosed => {
-                app.add_systems(PostUpdate, exit_on_primary_closed);
+                app.add_systems(
+                    PostUpdate,
+                    exit_on_primary_closed.in_set(AdwaitaSet::ExitOnClose),
+                );
             }
             ExitCondition::OnAllClosed => {
-                app.add_systems(PostUpdate, exit_on_all_closed);
+                app.add_systems(
+                    PostUpdate,
+                    exit_on_all_closed.in_set(AdwaitaSet::ExitOnClose),
+                );
             }
             ExitCondition::DontExit => {}
         }
 
         let render_app = app.sub_app_mut(RenderApp);
         render_app
-            .add_systems(ExtractSchedule, extract_windows)
-            .add_systems(Render, send_frame_to_windows.after(RenderSet::Render))
+            .init_resource::<PendingDepthReadbacks>()
+            .init_resource::<PendingFrameCaptures>()
+            .configure_sets(Render, AdwaitaSet::SendFrames.after(RenderSet::Render))
+            .add_systems(
+                ExtractSchedule,
+                extract_windows.in_set(AdwaitaSet::ExtractWindows),
+            )
+            .add_systems(
+                Render,
+                (
+                    send_frame_to_windows,
+                    depth::finish_depth_readbacks,
+                    depth::copy_depth_readbacks,
+                    capture::finish_frame_captures,
+                    capture::copy_frame_captures.before(send_frame_to_windows),
+                )
+                    .in_set(AdwaitaSet::SendFrames),
+            )
+            .add_systems(
+                Render,
+                throttle_frames
+                    .in_set(RenderSet::Render)
+                    .before(render_system),
+            )
             .add_systems(Last, put_back_next_frame_if_not_sent);
 
         if let Some(config) = self.primary_window_config.clone() {
+            if self.defer_primary_window {
+                app.insert_resource(PendingPrimaryWindow(config));
+                return;
+            }
+
             let world = app.world_mut();
             let entity = world.spawn_empty().id();
             AdwaitaWindow::open(config).apply(entity, world);
@@ -76,7 +482,107 @@ impl Plugin for AdwaitaWindowPlugin {
     }
 }
 
+/// Opens the primary window when [`AdwaitaWindowPlugin::defer_primary_window`] is
+/// enabled.
+///
+/// The window is opened in the next run of [`AdwaitaWindowPlugin::poll_schedule`]
+/// after sending this event.
+/// Sending this more than once, or if the primary window is not deferred, does nothing.
+#[derive(Debug, Default, Clone, Copy, Event)]
+pub struct OpenPrimaryWindow;
+
+#[derive(Debug, Resource)]
+struct PendingPrimaryWindow(AdwaitaWindowConfig);
+
+/// How an [`AdwaitaWindow`] entity is cleaned up once its window is closed.
+///
+/// An [`AdwaitaWindowClosed`] event is sent in every case.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Resource, Reflect)]
+#[reflect(Resource, Default)]
+pub enum AdwaitaCloseBehavior {
+    /// Despawns the window entity and all of its descendants.
+    #[default]
+    DespawnRecursive,
+    /// Despawns only the window entity, leaving its children without a parent.
+    Despawn,
+    /// Only removes the [`AdwaitaWindow`] component, leaving the entity and its
+    /// children for you to clean up.
+    RemoveComponent,
+}
+
+/// Sent in [`AdwaitaSet::PollWindows`] when an [`AdwaitaWindow`] is closed, either
+/// by the user or by [`AdwaitaWindow::close`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Event, Reflect)]
+pub struct AdwaitaWindowClosed {
+    pub window: Entity,
+}
+
+/// Sent in [`AdwaitaSet::PollWindows`] when the user asks to close an
+/// [`AdwaitaWindow`], such as by clicking its close button.
+///
+/// The window stays open until [`AdwaitaWindow::close`] is called, which happens
+/// right away unless [`AdwaitaWindowPlugin::close_when_requested`] is disabled. With
+/// it disabled, a close is vetoed by not calling [`AdwaitaWindow::close`], and the
+/// app keeps running, since the exit conditions only look at closed windows.
+///
+/// Bevy's [`WindowCloseRequested`] is sent for the same entity alongside this, so
+/// code written for winit windows sees the request too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Event, Reflect)]
+pub struct AdwaitaCloseRequested {
+    pub window: Entity,
+}
+
+fn close_when_requested(
+    mut requested_events: EventReader<AdwaitaCloseRequested>,
+    windows: Query<&AdwaitaWindow>,
+) {
+    for event in requested_events.read() {
+        if let Ok(window) = windows.get(event.window) {
+            window.close();
+        }
+    }
+}
+
+fn open_pending_primary_window(
+    mut commands: Commands,
+    mut open_events: EventReader<OpenPrimaryWindow>,
+    pending: Res<PendingPrimaryWindow>,
+) {
+    if open_events.read().last().is_none() {
+        return;
+    }
+
+    commands
+        .spawn_empty()
+        .add(AdwaitaWindow::open(pending.0.clone()))
+        .insert(PrimaryAdwaitaWindow);
+    commands.remove_resource::<PendingPrimaryWindow>();
+}
+
+/// System sets for the systems added by [`AdwaitaWindowPlugin`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemSet)]
+pub enum AdwaitaSet {
+    /// In [`AdwaitaWindowPlugin::poll_schedule`], which is [`PreUpdate`] by default:
+    /// reads the window state and events sent by GTK, and creates
+    /// new render targets for resized windows. Closed windows are cleaned up here,
+    /// according to the [`AdwaitaCloseBehavior`].
+    PollWindows,
+    /// In [`PostUpdate`]: exits the app if the [`ExitCondition`] is met.
+    ExitOnClose,
+    /// In the render app's [`ExtractSchedule`]: extracts newly created render targets.
+    ExtractWindows,
+    /// In the render app's [`Render`] schedule, after [`RenderSet::Render`]: hands
+    /// rendered frames over to GTK.
+    SendFrames,
+}
+
 impl AdwaitaWindowPlugin {
+    /// [`WindowPlugin`] for use with this plugin, which leaves opening and closing
+    /// windows to [`AdwaitaWindowPlugin`].
+    ///
+    /// Bevy's own `close_when_requested` must stay disabled, since it would despawn
+    /// the window's entity on a [`WindowCloseRequested`] without closing the GTK
+    /// window. Use [`AdwaitaWindowPlugin::close_when_requested`] instead.
     #[must_use]
     pub fn window_plugin() -> WindowPlugin {
         WindowPlugin {
@@ -86,43 +592,401 @@ impl AdwaitaWindowPlugin {
         }
     }
 
+    /// Whether this system can render into Adwaita windows at all.
+    ///
+    /// This only inspects the available Vulkan adapters without creating a device, so
+    /// it's cheap enough to call before deciding which windowing plugin to use.
     #[must_use]
-    pub fn render_plugin(settings: WgpuSettings) -> RenderPlugin {
-        let render_creation = render::create_renderer(settings);
-        RenderPlugin {
+    pub fn is_supported() -> bool {
+        render::is_supported()
+    }
+
+    pub fn render_plugin(settings: WgpuSettings) -> Result<RenderPlugin, AdwaitaRenderError> {
+        Self::render_plugin_with_config(settings, AdwaitaRenderConfig::default())
+    }
+
+    pub fn render_plugin_with_config(
+        settings: WgpuSettings,
+        config: AdwaitaRenderConfig,
+    ) -> Result<RenderPlugin, AdwaitaRenderError> {
+        let render_creation = render::create_renderer(settings, config)?;
+        Ok(RenderPlugin {
             render_creation,
             synchronous_pipeline_compilation: false,
-        }
+        })
     }
 }
 
+/// Options for creating the renderer in [`AdwaitaWindowPlugin::render_plugin_with_config`].
+#[derive(Debug, Clone, Default)]
+pub struct AdwaitaRenderConfig {
+    /// Allows rendering on a software rasterizer such as llvmpipe, if no hardware
+    /// adapter is available or it's selected with [`AdwaitaRenderConfig::adapter`].
+    ///
+    /// By default, creating the renderer fails with
+    /// [`AdwaitaRenderError::SoftwareAdapter`] in this case, instead of running at a
+    /// few frames per second.
+    pub allow_software_adapter: bool,
+    /// Which adapter to render on, such as to pick the discrete GPU on a laptop.
+    ///
+    /// Frames can only be shown if the compositor can import buffers from the
+    /// adapter, so the default of rendering on the display's adapter is almost
+    /// always right.
+    pub adapter: AdwaitaAdapterSelection,
+}
+
 #[derive(Debug, Component)]
 pub struct AdwaitaWindow {
-    send_command: flume::Sender<WindowCommand>,
+    commands: SharedCommandQueue,
+    recv_event: flume::Receiver<WindowEvent>,
+    next_dialog_id: AtomicU64,
+    next_toast_id: AtomicU64,
+    batch: Mutex<Option<Vec<WindowCommand>>>,
     render_target_width: Arc<AtomicI32>,
     render_target_height: Arc<AtomicI32>,
-    scale_factor: Arc<AtomicI32>,
+    scale_factor: Arc<AtomicU32>,
+    max_render_target_size: Arc<OnceLock<UVec2>>,
+    max_import_size: Arc<Mutex<Option<UVec2>>>,
     shared_next_frame: Arc<AtomicOptionBox<FrameInfo>>,
+    frame_damage: Mutex<Option<Vec<URect>>>,
+    depth_requests: Mutex<Vec<URect>>,
+    cursor_icon: Mutex<CursorIcon>,
+    cursor_visible: AtomicBool,
+    cursor_grab_mode: Mutex<CursorGrabMode>,
+    ime_enabled: AtomicBool,
+    ime_cursor_area: Mutex<Option<Rect>>,
+    cursor_position: Option<Vec2>,
+    position: Option<IVec2>,
+    // config the window was opened with, with the state that the user can change
+    // kept up to date
+    current_config: AdwaitaWindowConfig,
+    // limits of the content size in logical pixels, unset limits are 0 or `u32::MAX`
+    min_size: Mutex<UVec2>,
+    max_size: Mutex<UVec2>,
+    ready: AtomicBool,
+    depth_readbacks: SharedDepthReadbacks,
+    capture_requested: Arc<AtomicBool>,
+    frame_captures: SharedFrameCaptures,
+    rendering_paused: AtomicBool,
+    shared_damage: Arc<Mutex<FrameDamage>>,
+    rendered_frame_count: Arc<AtomicU32>,
+    consumed_frame: SharedConsumedFrame,
+    frame_counters: Option<SharedFrameCounters>,
+    frame_backpressure: AdwaitaFrameBackpressure,
+    // set by `pause_cameras` when this frame is skipped due to backpressure
+    dropping_frame: bool,
+    presented_frame: Arc<Mutex<Option<AdwaitaPresentedFrame>>>,
+    present_waiters: SharedPresentWaiters,
+    monitor: SharedMonitorInfo,
     closed: Arc<AtomicBool>,
+    close_requested: Arc<AtomicBool>,
+    suspended: Arc<AtomicBool>,
+    title_bar_visible: Arc<AtomicBool>,
+    right_to_left: Arc<AtomicBool>,
     render_target_handle: ManualTextureViewHandle,
+    alternate_view_handle: Option<ManualTextureViewHandle>,
     last_render_target_size: UVec2,
+    last_base_size: UVec2,
+    // logical size of the content, as last sent in `WindowResized`
+    last_content_size: Vec2,
+    last_scale_factor: f32,
+    render_scale_override: Option<f32>,
+    render_scale: f32,
+    preallocate_render_target: bool,
+    aspect_ratio: Option<f32>,
+    render_target_format: AdwaitaRenderTargetFormat,
+    transparent: bool,
+    render_target: Option<RenderTargetRing>,
+    render_target_pool: Vec<RenderTargetRing>,
+    next_buffer_id: u64,
+    next_frame_sequence: u64,
+    dynamic_resolution: Option<DynamicResolutionState>,
     next_frame_to_render: Arc<AtomicOptionBox<FrameInfo>>,
 }
 
+// render targets of recently used sizes are kept around, so that switching between
+// render scales doesn't have to reallocate a target every time
+const RENDER_TARGET_POOL_SIZE: usize = 4;
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Component, Reflect)]
 #[reflect(Default, Component)]
 pub struct PrimaryAdwaitaWindow;
 
+/// Renders the camera on this entity to the [`AdwaitaWindow`] on another entity,
+/// by keeping the camera's [`Camera::target`] set to the window's render target.
+///
+/// Unlike [`AdwaitaWindowPlugin::auto_retarget_cameras`], this works for any
+/// window, including ones opened after the camera is spawned. Until the window is
+/// opened, the camera's target is left as it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component, Reflect)]
+#[reflect(Component)]
+pub struct RenderToAdwaitaWindow(pub Entity);
+
+/// Mirrors the size of an [`AdwaitaWindow`]'s render target, so that systems can
+/// query it directly.
+///
+/// This is inserted alongside the [`AdwaitaWindow`], and only changes when the
+/// render target is resized or the scale factor changes, so it can be used with
+/// [`Changed`] filters. If the entity also has a Bevy [`Window`], its
+/// [`resolution`](Window::resolution) is kept in sync with this.
+#[derive(Debug, Clone, Copy, PartialEq, Component, Reflect)]
+#[reflect(Default, Component)]
+pub struct AdwaitaWindowSize {
+    /// See [`AdwaitaWindow::physical_size`].
+    pub physical_size: UVec2,
+    /// See [`AdwaitaWindow::scale_factor`].
+    pub scale_factor: f32,
+}
+
+impl Default for AdwaitaWindowSize {
+    fn default() -> Self {
+        Self {
+            physical_size: UVec2::ZERO,
+            scale_factor: 1.0,
+        }
+    }
+}
+
+/// Whether an [`AdwaitaWindow`] is the active window, which gets keyboard input.
+///
+/// This is inserted alongside the [`AdwaitaWindow`], starting as `false` until GTK
+/// activates the window. A [`WindowFocused`] event is sent whenever it changes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Component, Reflect)]
+#[reflect(Default, Component)]
+pub struct AdwaitaWindowFocused(pub bool);
+
 #[derive(Debug, Clone, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serialize", serde(default))]
 #[reflect(Default)]
 pub struct AdwaitaWindowConfig {
     pub width: u32,
     pub height: u32,
+    /// Smallest width of the rendered content in logical pixels, which the user
+    /// can't resize the window below.
+    pub min_width: Option<u32>,
+    /// See [`min_width`](AdwaitaWindowConfig::min_width).
+    pub min_height: Option<u32>,
+    /// Largest width of the rendered content in logical pixels.
+    ///
+    /// GTK 4 can't stop the user from resizing a window, so the window can still
+    /// grow past this. Instead, the render target stops growing, and GTK scales it
+    /// up to fill the rest of the window.
+    pub max_width: Option<u32>,
+    /// See [`max_width`](AdwaitaWindowConfig::max_width).
+    pub max_height: Option<u32>,
     pub title: String,
+    /// Icon of the window, see [`AdwaitaWindowIcon`] for where it's shown.
+    ///
+    /// To set the icon from an image asset, add an [`AdwaitaWindowIconImage`] to
+    /// the window's entity instead.
+    pub icon: Option<AdwaitaWindowIcon>,
     pub resizable: bool,
+    /// Whether GTK draws the window's frame: its shadow, rounded corners and the
+    /// borders used to resize it.
+    ///
+    /// This is independent of [`header_bar`], which is part of the window's content
+    /// rather than its frame, so a window without decorations still shows its header
+    /// bar. For a fully borderless window, also use [`AdwaitaHeaderBar::None`] or
+    /// hide the title bar with [`AdwaitaWindow::set_title_bar_visible`]. Maximized
+    /// and fullscreen windows don't draw a frame either way.
+    ///
+    /// [`header_bar`]: AdwaitaWindowConfig::header_bar
+    pub decorated: bool,
     pub maximized: bool,
     pub fullscreen: bool,
+    /// Whether the window asks to be focused when it opens.
+    ///
+    /// Set this to `false` for companion or tool windows which shouldn't interrupt
+    /// what the user is doing. Whether the window is actually focused is up to the
+    /// compositor:
+    ///
+    /// | Backend | Support |
+    /// |---------|---------|
+    /// | X11     | Requires the `x11` feature, and a window manager respecting `_NET_WM_USER_TIME` |
+    /// | Wayland | The window doesn't request activation, but many compositors focus new windows anyway |
+    pub focus_on_open: bool,
+    /// Startup notification ID or activation token to present the window with, so
+    /// that the compositor focuses it and ends the launcher's loading indicator.
+    ///
+    /// When the app is launched from a desktop file or launcher, GTK already reads
+    /// `XDG_ACTIVATION_TOKEN` (Wayland) or `DESKTOP_STARTUP_ID` (X11) from the
+    /// environment, and uses it for the first window opened. Set this for windows
+    /// opened later in response to an activation, such as with a token passed over
+    /// D-Bus in the `activation-token` or `desktop-startup-id` platform data.
+    ///
+    /// This is ignored if [`focus_on_open`] is disabled.
+    ///
+    /// [`focus_on_open`]: AdwaitaWindowConfig::focus_on_open
+    pub startup_id: Option<String>,
     pub header_bar: AdwaitaHeaderBar,
+    pub maximize_action: AdwaitaMaximizeAction,
+    pub title_bar_visible: bool,
+    /// Whether to clip the window content to the rounded corners of the window.
+    ///
+    /// The window always keeps its client-side shadow and rounded frame, but the
+    /// render target is drawn as a rectangle over the corners. This is most visible
+    /// with [`AdwaitaHeaderBar::None`], where nothing else covers the top corners.
+    /// The corners are square again while the window is maximized, fullscreen or
+    /// tiled.
+    ///
+    /// Clipping prevents GTK from offloading the frame to the compositor, so each
+    /// frame is composited by GTK instead, which costs some GPU time.
+    pub clip_rounded_corners: bool,
+    /// Margin around the window content in logical pixels, which shows the window
+    /// background instead of the render target.
+    ///
+    /// Unlike a header bar, the margin can't be used to drag the window. Use an
+    /// [`AdwaitaLayout`] with a `gtk::WindowHandle` for custom drag regions.
+    pub content_margin: u32,
+    /// Scale factor used to size the render target, instead of the display's scale
+    /// factor.
+    ///
+    /// On HiDPI displays, setting this to `Some(1.0)` renders at the window's logical
+    /// size and lets GTK upscale the result, trading sharpness for performance.
+    pub render_scale_override: Option<f32>,
+    pub dynamic_resolution: Option<AdwaitaDynamicResolution>,
+    /// Allocates the render target at the size of the largest monitor up front, so
+    /// that resizing the window doesn't reallocate it.
+    ///
+    /// Only the top-left region matching the window size is rendered and shown, by
+    /// setting the [`Viewport`] of all cameras rendering to this window. This
+    /// overrides any viewport you set on those cameras.
+    ///
+    /// This makes resizing smooth, at the cost of always using as much VRAM as a
+    /// fullscreen window, e.g. around 32 MiB per render target image for a 4K
+    /// monitor. The render target is still reallocated if the window grows beyond
+    /// the size of the largest monitor.
+    ///
+    /// [`Viewport`]: bevy::render::camera::Viewport
+    pub preallocate_render_target: bool,
+    /// Fixed aspect ratio (width / height) of the rendered content.
+    ///
+    /// The render target is sized to the largest size with this aspect ratio which
+    /// fits in the window, and the remaining space is filled with pillarbox or
+    /// letterbox bars of [`letterbox_color`].
+    ///
+    /// [`letterbox_color`]: AdwaitaWindowConfig::letterbox_color
+    pub aspect_ratio: Option<f32>,
+    /// Format of the texture that Bevy renders to, which decides whether its output
+    /// is sRGB-encoded by the GPU or written linearly.
+    ///
+    /// The default, [`AdwaitaRenderTargetFormat::Srgb`], looks the same as Bevy
+    /// rendering to a winit window. Check [`AdwaitaRenderTargetFormats`] before using
+    /// any other format; if it's known to be unsupported, the window falls back to
+    /// the default and logs an error.
+    pub render_target_format: AdwaitaRenderTargetFormat,
+    /// Also creates a view of the render target in the other 8-bit RGBA
+    /// [`AdwaitaRenderTargetFormat`], for tools which sample what Bevy rendered, such
+    /// as color scopes and histograms.
+    ///
+    /// Both views point at the same image. Cameras keep rendering through the view
+    /// in [`render_target_format`], which is what's shown in the window. With the
+    /// default [`AdwaitaRenderTargetFormat::Srgb`], sampling the alternate
+    /// `Rgba8Unorm` view returns the stored sRGB-encoded values as-is, instead of
+    /// decoding them to linear. Other formats have no alternate, so this is ignored
+    /// for them, and an error is logged.
+    ///
+    /// Look up the alternate view in [`ManualTextureViews`] by its
+    /// [`AdwaitaWindow::alternate_format_view`] handle, e.g. to bind it in a render
+    /// graph node which runs after the cameras. It's replaced whenever the render
+    /// target is recreated, so don't hold on to it across frames.
+    ///
+    /// [`render_target_format`]: AdwaitaWindowConfig::render_target_format
+    pub alternate_format_view: bool,
+    /// Whether the alpha channel of the rendered content is kept, so that whatever is
+    /// behind the window shows through where it's less than `1.0`.
+    ///
+    /// Bevy has to actually render transparency for this to show, so give the
+    /// window's cameras a [`ClearColor`] or [`ClearColorConfig`] with an alpha below
+    /// `1.0`. The header bar and letterbox bars stay opaque unless they're styled
+    /// otherwise. Alpha is treated as straight, not premultiplied.
+    ///
+    /// [`ClearColorConfig`]: bevy::render::camera::ClearColorConfig
+    pub transparent: bool,
+    /// What to do when the compositor picks up frames slower than Bevy renders them.
+    ///
+    /// Ignored if [`present_mode`] is set.
+    ///
+    /// [`present_mode`]: AdwaitaWindowConfig::present_mode
+    pub frame_backpressure: AdwaitaFrameBackpressure,
+    /// Present mode that frames are handed to GTK with, which replaces
+    /// [`frame_backpressure`] with the backpressure of that mode.
+    ///
+    /// If [`None`], frames are picked up on each tick of the window's frame clock,
+    /// with [`frame_backpressure`].
+    ///
+    /// [`frame_backpressure`]: AdwaitaWindowConfig::frame_backpressure
+    pub present_mode: Option<AdwaitaPresentMode>,
+    /// Color of the bars around the content when the window doesn't match the
+    /// content's [`aspect_ratio`]. If [`None`], the bars are black.
+    ///
+    /// [`aspect_ratio`]: AdwaitaWindowConfig::aspect_ratio
+    pub letterbox_color: Option<Color>,
+    /// Index of the workspace (virtual desktop) to open the window on.
+    ///
+    /// Support depends on the windowing system:
+    ///
+    /// | Backend | Support |
+    /// |---------|---------|
+    /// | X11     | Requires the `x11` feature, and a window manager supporting `_NET_WM_DESKTOP` |
+    /// | Wayland | Unsupported, the compositor decides where the window opens |
+    ///
+    /// When unsupported, a warning is logged and the window opens wherever the
+    /// compositor puts it.
+    pub workspace: Option<u32>,
+    /// Scale of the text in the GTK-drawn chrome, such as the header bar, menus and
+    /// toasts, relative to the user's system setting.
+    ///
+    /// This doesn't affect the content rendered by Bevy, or the chrome of other
+    /// windows. If [`None`], the user's system setting is respected.
+    pub chrome_text_scale: Option<f32>,
+    /// Scale of the icons in the GTK-drawn chrome of this window, relative to their
+    /// default size.
+    ///
+    /// This doesn't affect the content rendered by Bevy. If [`None`], icons are
+    /// shown at their default size.
+    pub chrome_icon_scale: Option<f32>,
+    /// Reading direction of the GTK-drawn chrome of this window.
+    ///
+    /// This doesn't affect the content rendered by Bevy. Use
+    /// [`AdwaitaWindow::is_right_to_left`] to lay out your own UI to match.
+    pub text_direction: AdwaitaTextDirection,
+    /// Position to warp the pointer to once the first frame is shown, in physical
+    /// pixels of the render target.
+    ///
+    /// See [`AdwaitaWindow::warp_cursor`] for where this is supported.
+    pub initial_cursor_position: Option<Vec2>,
+    /// Covers the render target with a spinner from when the window opens, until
+    /// [`AdwaitaWindow::signal_ready`] is called.
+    ///
+    /// Use this to hide the scene while assets are still loading, even after the
+    /// first frames have been rendered. The overlay also blocks input to the render
+    /// target.
+    pub loading_overlay: bool,
+    /// Whether key events reach the rendered content or the GTK widgets of the
+    /// window first.
+    pub key_propagation: AdwaitaKeyPropagation,
+    /// Logs every raw GDK input event over the render target at trace level, before
+    /// it's filtered or forwarded to Bevy.
+    ///
+    /// This is useful for diagnosing why a key or button isn't reaching Bevy, and
+    /// has no overhead when disabled.
+    pub debug_input: bool,
+    /// Shows how often GTK's frame clock ticks, and how many frames Bevy sends and
+    /// GTK presents, over the render target.
+    ///
+    /// The same stats are kept in [`AdwaitaFrameStats`]. This is useful for
+    /// debugging why frames aren't reaching the compositor.
+    pub show_frame_stats: bool,
+    /// Filter for smooth scrolling over the render target, which is forwarded to
+    /// Bevy as [`MouseWheel`] events.
+    ///
+    /// If [`None`], scroll deltas are forwarded unchanged.
+    pub scroll_filter: Option<AdwaitaScrollFilter>,
+    #[reflect(ignore)]
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    pub layout: Option<AdwaitaLayout>,
 }
 
 impl Default for AdwaitaWindowConfig {
@@ -130,26 +994,95 @@ impl Default for AdwaitaWindowConfig {
         Self {
             width: 1280,
             height: 720,
+            min_width: None,
+            min_height: None,
+            max_width: None,
+            max_height: None,
             title: "App".into(),
+            icon: None,
             resizable: true,
+            decorated: true,
             maximized: false,
             fullscreen: false,
+            focus_on_open: true,
+            startup_id: None,
             header_bar: AdwaitaHeaderBar::default(),
+            maximize_action: AdwaitaMaximizeAction::default(),
+            title_bar_visible: true,
+            clip_rounded_corners: false,
+            content_margin: 0,
+            render_scale_override: None,
+            dynamic_resolution: None,
+            preallocate_render_target: false,
+            aspect_ratio: None,
+            render_target_format: AdwaitaRenderTargetFormat::default(),
+            alternate_format_view: false,
+            transparent: false,
+            frame_backpressure: AdwaitaFrameBackpressure::default(),
+            present_mode: None,
+            letterbox_color: None,
+            workspace: None,
+            chrome_text_scale: None,
+            chrome_icon_scale: None,
+            text_direction: AdwaitaTextDirection::default(),
+            initial_cursor_position: None,
+            loading_overlay: false,
+            key_propagation: AdwaitaKeyPropagation::default(),
+            debug_input: false,
+            show_frame_stats: false,
+            scroll_filter: None,
+            layout: None,
         }
     }
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 #[reflect(Default)]
 pub enum AdwaitaHeaderBar {
     #[default]
     Full,
     OverContent,
     None,
+    /// Like [`AdwaitaHeaderBar::Full`], with widgets added to the header bar.
+    ///
+    /// Activating them sends an [`AdwaitaHeaderAction`].
+    Custom(Vec<AdwaitaHeaderItem>),
+}
+
+/// Reading direction of the GTK-drawn chrome of a window.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[reflect(Default)]
+pub enum AdwaitaTextDirection {
+    /// Follows the system locale.
+    #[default]
+    System,
+    /// Left-to-right.
+    Ltr,
+    /// Right-to-left, which mirrors the layout of the header bar, menus and dialogs.
+    Rtl,
 }
 
+/// What the maximize button in the header bar does.
+///
+/// Double-clicking the title bar does the same thing as the button.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[reflect(Default)]
+pub enum AdwaitaMaximizeAction {
+    /// Toggles whether the window is maximized.
+    #[default]
+    Maximize,
+    /// Toggles whether the window is fullscreen instead.
+    Fullscreen,
+}
+
+#[derive(Debug, Resource)]
+struct SendWindowOpen(flume::Sender<MainThreadRequest>);
+
 #[derive(Debug, Resource)]
-struct SendWindowOpen(flume::Sender<WindowOpen>);
+struct SharedDisplay(SharedDisplayInfo);
 
 impl AdwaitaWindow {
     #[must_use]
@@ -160,45 +1093,173 @@ impl AdwaitaWindow {
                 config.title
             );
 
-            let (send_command, recv_command) = flume::bounded::<WindowCommand>(16);
+            let commands = SharedCommandQueue::default();
+            let (send_event, recv_event) = flume::unbounded::<WindowEvent>();
             let render_target_width = Arc::new(AtomicI32::new(-1));
             let render_target_height = Arc::new(AtomicI32::new(-1));
-            let scale_factor = Arc::new(AtomicI32::new(-1));
+            let scale_factor = Arc::new(AtomicU32::new(0));
+            let max_render_target_size = Arc::new(OnceLock::new());
+            let max_import_size = Arc::new(Mutex::new(None));
             let shared_next_frame = Arc::new(AtomicOptionBox::<FrameInfo>::none());
+            let shared_damage = Arc::new(Mutex::new(FrameDamage::Full));
+            let rendered_frame_count = Arc::new(AtomicU32::new(0));
+            let consumed_frame = SharedConsumedFrame::default();
+            let frame_counters = config.show_frame_stats.then(SharedFrameCounters::default);
+            let presented_frame = Arc::new(Mutex::new(None));
+            let present_waiters = SharedPresentWaiters::default();
+            let monitor = SharedMonitorInfo::default();
             let closed = Arc::new(AtomicBool::new(false));
+            let close_requested = Arc::new(AtomicBool::new(false));
+            let suspended = Arc::new(AtomicBool::new(false));
+            let title_bar_visible = Arc::new(AtomicBool::new(
+                config.title_bar_visible && config.header_bar != AdwaitaHeaderBar::None,
+            ));
+            let right_to_left = Arc::new(AtomicBool::new(false));
+            let render_scale_override = config.render_scale_override;
+            let preallocate_render_target = config.preallocate_render_target;
+            let aspect_ratio = config.aspect_ratio;
+            let render_target_format = match world.get_resource::<AdwaitaRenderTargetFormats>() {
+                Some(formats) if !formats.supports(config.render_target_format) => {
+                    error!(
+                        "Render target format {:?} can't be shared with the display on this \
+                         system, using {:?} instead",
+                        config.render_target_format,
+                        AdwaitaRenderTargetFormat::default(),
+                    );
+                    AdwaitaRenderTargetFormat::default()
+                }
+                _ => config.render_target_format,
+            };
+            let frame_backpressure = config
+                .present_mode
+                .map_or(config.frame_backpressure, AdwaitaPresentMode::backpressure);
+            let transparent = config.transparent;
+            let min_size = UVec2::new(
+                config.min_width.unwrap_or(0),
+                config.min_height.unwrap_or(0),
+            );
+            let max_size = UVec2::new(
+                config.max_width.unwrap_or(u32::MAX),
+                config.max_height.unwrap_or(u32::MAX),
+            );
+            let alternate_format_view = config.alternate_format_view;
+            if alternate_format_view && render_target_format.alternate().is_none() {
+                error!(
+                    "Render target format {render_target_format:?} has no alternate format, \
+                     not creating an alternate view"
+                );
+            }
+            let alternate_format_view =
+                alternate_format_view && render_target_format.alternate().is_some();
+            let dynamic_resolution = config.dynamic_resolution.map(DynamicResolutionState::new);
+            let render_scale = dynamic_resolution
+                .as_ref()
+                .map_or(1.0, DynamicResolutionState::initial_scale);
+            let current_config = config.clone();
             let request = WindowOpen {
                 config,
-                recv_command,
+                commands: commands.clone(),
+                send_event,
                 render_target_width: render_target_width.clone(),
                 render_target_height: render_target_height.clone(),
                 shared_next_frame: shared_next_frame.clone(),
+                shared_damage: shared_damage.clone(),
+                rendered_frame_count: rendered_frame_count.clone(),
+                consumed_frame: consumed_frame.clone(),
+                frame_counters: frame_counters.clone(),
+                presented_frame: presented_frame.clone(),
+                present_waiters: present_waiters.clone(),
+                monitor: monitor.clone(),
                 scale_factor: scale_factor.clone(),
+                max_render_target_size: max_render_target_size.clone(),
+                max_import_size: max_import_size.clone(),
                 closed: closed.clone(),
+                close_requested: close_requested.clone(),
+                suspended: suspended.clone(),
+                title_bar_visible: title_bar_visible.clone(),
+                right_to_left: right_to_left.clone(),
             };
 
             let manual_texture_views = world.resource::<ManualTextureViews>();
-            let render_target_handle = loop {
+            let new_handle = |taken: Option<ManualTextureViewHandle>| loop {
                 let handle = ManualTextureViewHandle(rand::random());
-                if !manual_texture_views.contains_key(&handle) {
+                if !manual_texture_views.contains_key(&handle) && Some(handle) != taken {
                     break handle;
                 }
             };
+            let render_target_handle = new_handle(None);
+            let alternate_view_handle =
+                alternate_format_view.then(|| new_handle(Some(render_target_handle)));
 
+            world
+                .entity_mut(entity)
+                .insert((AdwaitaWindowSize::default(), AdwaitaWindowFocused(false)));
             world.entity_mut(entity).insert(AdwaitaWindow {
-                send_command,
+                commands,
+                recv_event,
+                next_dialog_id: AtomicU64::new(0),
+                next_toast_id: AtomicU64::new(0),
+                batch: Mutex::new(None),
                 render_target_width,
                 render_target_height,
                 scale_factor,
+                max_render_target_size,
+                max_import_size,
                 shared_next_frame,
+                frame_damage: Mutex::new(None),
+                depth_requests: Mutex::new(Vec::new()),
+                cursor_icon: Mutex::new(CursorIcon::Default),
+                cursor_visible: AtomicBool::new(true),
+                cursor_grab_mode: Mutex::new(CursorGrabMode::None),
+                ime_enabled: AtomicBool::new(false),
+                ime_cursor_area: Mutex::new(None),
+                cursor_position: None,
+                position: None,
+                current_config,
+                min_size: Mutex::new(min_size),
+                max_size: Mutex::new(max_size),
+                ready: AtomicBool::new(false),
+                depth_readbacks: SharedDepthReadbacks::default(),
+                capture_requested: Arc::new(AtomicBool::new(false)),
+                frame_captures: SharedFrameCaptures::default(),
+                rendering_paused: AtomicBool::new(false),
+                shared_damage,
+                rendered_frame_count,
+                consumed_frame,
+                frame_counters,
+                frame_backpressure,
+                dropping_frame: false,
+                presented_frame,
+                present_waiters,
+                monitor,
                 closed,
+                close_requested,
+                suspended,
+                title_bar_visible,
+                right_to_left,
                 render_target_handle,
+                alternate_view_handle,
                 last_render_target_size: UVec2::new(0, 0),
+                last_base_size: UVec2::new(0, 0),
+                last_content_size: Vec2::ZERO,
+                last_scale_factor: 1.0,
+                render_scale_override,
+                render_scale,
+                preallocate_render_target,
+                aspect_ratio,
+                render_target_format,
+                transparent,
+                render_target: None,
+                render_target_pool: Vec::new(),
+                next_buffer_id: 0,
+                next_frame_sequence: 0,
+                dynamic_resolution,
                 next_frame_to_render: Arc::new(AtomicOptionBox::none()),
             });
             world
                 .resource::<SendWindowOpen>()
                 .0
-                .send(request)
+                .send(MainThreadRequest::OpenWindow(Box::new(request)))
                 .expect("Adwaita main thread dropped");
         }
     }
@@ -213,10 +1274,551 @@ impl AdwaitaWindow {
         RenderTarget::TextureView(self.render_target_handle)
     }
 
+    /// Handle of the alternate view of the render target in [`ManualTextureViews`],
+    /// if [`AdwaitaWindowConfig::alternate_format_view`] is enabled.
+    #[must_use]
+    pub const fn alternate_format_view(&self) -> Option<ManualTextureViewHandle> {
+        self.alternate_view_handle
+    }
+
+    /// Size of the render target that Bevy is currently rendering to, in physical
+    /// pixels.
+    ///
+    /// This is the region that's shown in the window, which may be smaller than the
+    /// allocated texture if [`AdwaitaWindowConfig::preallocate_render_target`] is
+    /// enabled. Returns [`UVec2::ZERO`] until the window has been laid out.
+    #[must_use]
+    pub const fn physical_size(&self) -> UVec2 {
+        self.last_render_target_size
+    }
+
+    /// Number of physical pixels per logical pixel of the window.
+    ///
+    /// This is the display's scale factor, or [`AdwaitaWindowConfig::render_scale_override`]
+    /// if set. It doesn't include [`AdwaitaWindow::render_scale`]. With fractional
+    /// scaling, this is the exact fraction, such as `1.25`.
+    #[must_use]
+    pub const fn scale_factor(&self) -> f32 {
+        self.last_scale_factor
+    }
+
+    /// Fraction of the window's physical size that the render target is allocated at.
+    ///
+    /// This is always `1.0` unless [`AdwaitaWindowConfig::dynamic_resolution`] is enabled.
+    #[must_use]
+    pub const fn render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+    /// Frame which GTK has most recently painted to the screen.
+    ///
+    /// This lags behind the frames rendered by Bevy, since GTK only picks up a new
+    /// frame on its next frame clock tick. Returns [`None`] until the first frame
+    /// has been displayed.
+    #[must_use]
+    pub fn presented_frame(&self) -> Option<AdwaitaPresentedFrame> {
+        *self.presented_frame.lock().expect("poisoned")
+    }
+
+    /// Largest render target size that the compositor has accepted, in physical
+    /// pixels.
+    ///
+    /// Some compositors refuse to import buffers above a certain size, even if Vulkan
+    /// can allocate them. Compositors don't report this limit, so it's only known
+    /// once a render target has been rejected, after which render targets are kept
+    /// below this size and upscaled to fill the window. Returns [`None`] if no render
+    /// target has been rejected yet.
+    #[must_use]
+    pub fn max_import_size(&self) -> Option<UVec2> {
+        *self.max_import_size.lock().expect("poisoned")
+    }
+
+    /// Monitor that this window is currently shown on.
+    ///
+    /// Returns [`None`] until the window has been mapped onto a monitor.
+    #[must_use]
+    pub fn monitor(&self) -> Option<AdwaitaMonitorInfo> {
+        self.monitor.lock().expect("poisoned").clone()
+    }
+
+    /// Waits until GTK presents the next frame of this window.
+    ///
+    /// Resolves to the presented frame, or [`None`] if the window is closed first.
+    /// Run this in a task, for example with [`AsyncComputeTaskPool`], to pace logic
+    /// to actual presentation instead of Bevy's update rate.
+    ///
+    /// GTK doesn't present anything while the window is hidden or minimized, so
+    /// this may never resolve. Use [`AdwaitaWindow::await_present_timeout`] to give
+    /// up after some time.
+    ///
+    /// [`AsyncComputeTaskPool`]: bevy::tasks::AsyncComputeTaskPool
+    pub fn await_present(
+        &self,
+    ) -> impl Future<Output = Option<AdwaitaPresentedFrame>> + Send + 'static {
+        self.wait_for_present(None)
+    }
+
+    /// Like [`AdwaitaWindow::await_present`], but resolves to [`None`] if no frame is
+    /// presented within `timeout`.
+    ///
+    /// The timeout is checked once per app update, so it may overshoot by up to a
+    /// frame.
+    pub fn await_present_timeout(
+        &self,
+        timeout: Duration,
+    ) -> impl Future<Output = Option<AdwaitaPresentedFrame>> + Send + 'static {
+        self.wait_for_present(Some(Instant::now() + timeout))
+    }
+
+    fn wait_for_present(
+        &self,
+        deadline: Option<Instant>,
+    ) -> impl Future<Output = Option<AdwaitaPresentedFrame>> + Send + 'static {
+        let (send, recv) = flume::bounded(1);
+        self.present_waiters
+            .lock()
+            .expect("poisoned")
+            .push(PresentWaiter { send, deadline });
+        async move { recv.recv_async().await.ok() }
+    }
+
+    /// Starts recording the frames shown in this window to an H.264 Matroska file
+    /// at `path`, replacing any recording already in progress.
+    ///
+    /// Requires the `gstreamer` feature, and GStreamer with the `x264enc` element
+    /// (from gst-plugins-ugly) installed at runtime. Frames are read directly from
+    /// the render target's dmabuf, so the GPU driver must support mapping linear
+    /// dmabufs. Errors are logged, and stop the recording.
+    ///
+    /// If the window is resized while recording, frames are scaled to the size of
+    /// the first recorded frame.
+    #[cfg(feature = "gstreamer")]
+    pub fn start_recording(&self, path: impl Into<std::path::PathBuf>) {
+        self.send(WindowCommand::StartRecording(path.into()));
+    }
+
+    /// Stops the recording started by [`AdwaitaWindow::start_recording`], and
+    /// finishes writing its file.
+    #[cfg(feature = "gstreamer")]
+    pub fn stop_recording(&self) {
+        self.send(WindowCommand::StopRecording);
+    }
+
+    /// Shows `dialog` as a modal dialog over this window.
+    ///
+    /// Once the user closes the dialog, an [`AdwaitaDialogResponse`] with the returned
+    /// ID is sent.
+    pub fn confirm(&self, dialog: AdwaitaDialog) -> AdwaitaDialogId {
+        let id = AdwaitaDialogId(self.next_dialog_id.fetch_add(1, Ordering::SeqCst));
+        self.send(WindowCommand::ShowDialog(id, dialog));
+        id
+    }
+
+    /// Shows `toast` over the content of this window.
+    ///
+    /// Toasts are queued, and shown one at a time. Once the toast goes away, an
+    /// [`AdwaitaToastDismissed`] with the returned ID is sent.
+    pub fn show_toast(&self, toast: AdwaitaToast) -> AdwaitaToastId {
+        let id = AdwaitaToastId(self.next_toast_id.fetch_add(1, Ordering::SeqCst));
+        self.send(WindowCommand::ShowToast(id, toast));
+        id
+    }
+
+    /// Shows an about window for the app over this window.
+    ///
+    /// To open it from the header bar, add an item for it to a
+    /// [`AdwaitaHeaderItem::MenuButton`], and call this when its
+    /// [`AdwaitaHeaderAction`] is sent.
+    pub fn show_about(&self, info: AdwaitaAboutInfo) {
+        self.send(WindowCommand::ShowAbout(info));
+    }
+
+    /// Replaces the content of the clipboard with `text`.
+    pub fn set_clipboard_text(&self, text: impl Into<String>) {
+        self.send(WindowCommand::SetClipboardText(text.into()));
+    }
+
+    /// Reads the text on the clipboard, which is sent as an [`AdwaitaClipboardText`]
+    /// once it's available.
+    ///
+    /// The clipboard may be owned by another app, so this usually takes a few frames.
+    pub fn request_clipboard_text(&self) {
+        self.send(WindowCommand::RequestClipboardText);
+    }
+
+    /// Sets the filter deciding which input events over this window's content are
+    /// forwarded to Bevy, replacing the previous filter.
+    ///
+    /// See [`AdwaitaInputFilter`] for when the filter runs.
+    pub fn set_input_filter(
+        &self,
+        filter: impl Fn(&gtk::gdk::Event) -> bool + Send + Sync + 'static,
+    ) {
+        self.send(WindowCommand::SetInputFilter(Some(
+            AdwaitaInputFilter::new(filter),
+        )));
+    }
+
+    /// Removes the filter set by [`AdwaitaWindow::set_input_filter`], forwarding all
+    /// input events again.
+    pub fn clear_input_filter(&self) {
+        self.send(WindowCommand::SetInputFilter(None));
+    }
+
+    /// Sets whether key events reach the rendered content or the GTK widgets of this
+    /// window first.
+    ///
+    /// See [`AdwaitaWindowConfig::key_propagation`].
+    pub fn set_key_propagation(&self, propagation: AdwaitaKeyPropagation) {
+        self.send(WindowCommand::SetKeyPropagation(propagation));
+    }
+
+    /// Moves the pointer to `position` in this window's render target, in the same
+    /// physical pixel coordinates as the rendered frame.
+    ///
+    /// This is useful for centering the cursor when entering mouselook. Support
+    /// depends on the windowing system:
+    ///
+    /// | Backend | Support |
+    /// |---------|---------|
+    /// | X11     | Requires the `x11` feature |
+    /// | Wayland | Unsupported, compositors don't let clients move the pointer |
+    ///
+    /// When unsupported, a warning is logged and the pointer stays where it is. If no
+    /// frame has been shown yet, the pointer is moved once the first frame is shown.
+    pub fn warp_cursor(&self, position: Vec2) {
+        self.send(WindowCommand::WarpCursor(position));
+    }
+
+    /// Shows `icon` while the pointer is over this window's render target.
+    ///
+    /// If this entity also has a Bevy [`Window`], its [`Cursor::icon`] is applied
+    /// automatically, so existing code that sets cursor icons keeps working. Icons
+    /// missing from the user's cursor theme fall back to the default cursor.
+    ///
+    /// [`Cursor::icon`]: bevy::window::Cursor::icon
+    pub fn set_cursor_icon(&self, icon: CursorIcon) {
+        let mut cursor_icon = self.cursor_icon.lock().expect("poisoned");
+        if *cursor_icon != icon {
+            *cursor_icon = icon;
+            self.send(WindowCommand::SetCursorIcon(icon));
+        }
+    }
+
+    /// Sets whether the pointer is shown while it's over this window's render
+    /// target, such as to hide it while aiming.
+    ///
+    /// If this entity also has a Bevy [`Window`], its [`Cursor::visible`] is
+    /// applied automatically. The header bar always shows the pointer.
+    ///
+    /// [`Cursor::visible`]: bevy::window::Cursor::visible
+    pub fn set_cursor_visible(&self, visible: bool) {
+        if self.cursor_visible.swap(visible, Ordering::SeqCst) != visible {
+            self.send(WindowCommand::SetCursorVisible(visible));
+        }
+    }
+
+    /// Keeps the pointer inside of this window's render target, for controls such
+    /// as first-person cameras.
+    ///
+    /// While the cursor is grabbed, how far the pointer moves is sent as
+    /// [`MouseMotion`] events. When [`CursorGrabMode::Locked`], the pointer stays in
+    /// place and no [`CursorMoved`] events are sent.
+    ///
+    /// | Backend | Support |
+    /// |---------|---------|
+    /// | Wayland | Requires the `wayland` feature, and a compositor supporting pointer constraints |
+    /// | X11     | Emulated by warping the pointer, requires the `x11` feature |
+    ///
+    /// When unsupported, a warning is logged and the pointer moves freely, but
+    /// [`MouseMotion`] events are still sent. If this entity also has a Bevy
+    /// [`Window`], its [`Cursor::grab_mode`] is applied automatically.
+    ///
+    /// [`Cursor::grab_mode`]: bevy::window::Cursor::grab_mode
+    pub fn set_cursor_grab(&self, mode: CursorGrabMode) {
+        let mut grab_mode = self.cursor_grab_mode.lock().expect("poisoned");
+        if *grab_mode != mode {
+            *grab_mode = mode;
+            self.send(WindowCommand::SetCursorGrab(mode));
+        }
+    }
+
+    /// Sets whether this window demands the user's attention, such as by flashing
+    /// its taskbar entry.
+    ///
+    /// | Backend | Support |
+    /// |---------|---------|
+    /// | X11     | Requires the `x11` feature |
+    /// | Wayland | Requires the `wayland` feature, and a compositor supporting `xdg_activation_v1` |
+    ///
+    /// Requests are ignored while the window is focused, and the window stops
+    /// demanding attention once it's focused. Wayland can't take back a request,
+    /// so unsetting it does nothing there. When unsupported, this does nothing.
+    pub fn set_urgent(&self, urgent: bool) {
+        self.send(WindowCommand::SetUrgent(urgent));
+    }
+
+    /// Asks the user to look at this window, such as when a long task finishes in
+    /// the background, see [`AdwaitaWindow::set_urgent`].
+    pub fn request_attention(&self) {
+        self.set_urgent(true);
+    }
+
+    /// Sets whether text is typed through the user's input method, which is needed
+    /// for composing text such as Chinese, Japanese or accented characters.
+    ///
+    /// While enabled, text is sent as [`Ime`] events, and keys which the input method
+    /// consumes, which may be any printable key, aren't sent as [`KeyboardInput`].
+    /// Only enable this while a text field has focus. Like with winit, an
+    /// [`Ime::Enabled`] or [`Ime::Disabled`] is sent once it takes effect.
+    ///
+    /// If this entity also has a Bevy [`Window`], its `ime_enabled` and
+    /// `ime_position` are applied automatically.
+    pub fn set_ime_enabled(&self, enabled: bool) {
+        if self.ime_enabled.swap(enabled, Ordering::SeqCst) != enabled {
+            self.send(WindowCommand::SetImeEnabled(enabled));
+        }
+    }
+
+    /// Whether text is typed through the user's input method, see
+    /// [`AdwaitaWindow::set_ime_enabled`].
+    #[must_use]
+    pub fn is_ime_enabled(&self) -> bool {
+        self.ime_enabled.load(Ordering::SeqCst)
+    }
+
+    /// Tells the input method where the text being typed is, in the same physical
+    /// pixel coordinates as the rendered frame, so that it can show its candidate
+    /// window next to it.
+    pub fn set_ime_cursor_area(&self, area: Rect) {
+        let mut cursor_area = self.ime_cursor_area.lock().expect("poisoned");
+        if *cursor_area != Some(area) {
+            *cursor_area = Some(area);
+            self.send(WindowCommand::SetImeCursorArea(area));
+        }
+    }
+
+    /// Icon shown while the pointer is over this window's render target.
+    #[must_use]
+    pub fn cursor_icon(&self) -> CursorIcon {
+        *self.cursor_icon.lock().expect("poisoned")
+    }
+
+    /// Whether the pointer is shown over this window's render target, see
+    /// [`AdwaitaWindow::set_cursor_visible`].
+    #[must_use]
+    pub fn is_cursor_visible(&self) -> bool {
+        self.cursor_visible.load(Ordering::SeqCst)
+    }
+
+    /// How the pointer is kept inside of this window's render target, see
+    /// [`AdwaitaWindow::set_cursor_grab`].
+    #[must_use]
+    pub fn cursor_grab_mode(&self) -> CursorGrabMode {
+        *self.cursor_grab_mode.lock().expect("poisoned")
+    }
+
+    /// Position of the pointer over this window's rendered frame, in logical pixels
+    /// from the top left, or [`None`] if the pointer is outside of the window.
+    ///
+    /// This is the same position as in the [`CursorMoved`] events for this window.
+    #[must_use]
+    pub const fn cursor_position(&self) -> Option<Vec2> {
+        self.cursor_position
+    }
+
+    // maps a position in logical coordinates of the render target widget to logical
+    // pixels of the rendered frame
+    fn logical_frame_position(&self, widget_size: Vec2, position: Vec2) -> Option<Vec2> {
+        // the frame may be letterboxed in the widget, and rendered at a different
+        // resolution than the widget's
+        cursor::frame_position(widget_size, self.last_render_target_size, position)
+            .map(|position| position / self.last_scale_factor)
+    }
+
+    /// Position of the top-left corner of this window on the screen, in physical
+    /// pixels, as last sent in a [`WindowMoved`] event.
+    ///
+    /// This is [`None`] on Wayland, which doesn't tell clients where their windows are.
+    #[must_use]
+    pub const fn position(&self) -> Option<IVec2> {
+        self.position
+    }
+
+    #[must_use]
+    pub const fn is_maximized(&self) -> bool {
+        self.current_config.maximized
+    }
+
+    #[must_use]
+    pub const fn is_fullscreen(&self) -> bool {
+        self.current_config.fullscreen
+    }
+
+    /// Config which reopens this window in its current state, for saving with the
+    /// `serialize` feature when the app exits.
+    ///
+    /// This is the config the window was opened with, but with the size, maximized
+    /// and fullscreen state that the window has now. The size is the one the window
+    /// is restored to when it's not maximized or fullscreen.
+    #[must_use]
+    pub fn current_config(&self) -> AdwaitaWindowConfig {
+        self.current_config.clone()
+    }
+
+    /// Moves the top-left corner of this window to `position` on the screen, in
+    /// physical pixels.
+    ///
+    /// This only works on X11 with the `x11` feature enabled. On Wayland, the
+    /// compositor always decides where windows go, so this logs a warning instead.
+    pub fn set_position(&self, position: IVec2) {
+        self.send(WindowCommand::SetPosition(position));
+    }
+
+    /// Signals that the app has finished loading, which fades out the
+    /// [`AdwaitaWindowConfig::loading_overlay`].
+    ///
+    /// Calling this more than once, or without a loading overlay, does nothing.
+    pub fn signal_ready(&self) {
+        if !self.ready.swap(true, Ordering::SeqCst) {
+            self.send(WindowCommand::DismissLoadingOverlay);
+        }
+    }
+
+    /// Whether [`AdwaitaWindow::signal_ready`] has been called.
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::SeqCst)
+    }
+
+    /// Whether GTK has suspended this window, for example because it is minimized.
+    ///
+    /// The window keeps rendering while suspended, but nothing is shown to the user.
+    #[must_use]
+    pub fn is_suspended(&self) -> bool {
+        self.suspended.load(Ordering::SeqCst)
+    }
+
+    /// Drops all render targets, so that new ones are created in the next
+    /// [`AdwaitaSet::PollWindows`].
+    pub(crate) fn recreate_render_targets(&mut self) {
+        // the new display may accept larger buffers
+        *self.max_import_size.lock().expect("poisoned") = None;
+        self.send(WindowCommand::ResetImportLimit);
+        self.render_target = None;
+        self.render_target_pool.clear();
+        self.last_render_target_size = UVec2::ZERO;
+    }
+
+    fn send(&self, command: WindowCommand) {
+        let mut batch = self.batch.lock().expect("poisoned");
+        if let Some(batch) = batch.as_mut() {
+            batch.push(command);
+        } else if Arc::strong_count(&self.commands) == 1 {
+            debug!("Window is closed, dropping {command:?}");
+        } else {
+            self.commands.push(command);
+        }
+    }
+
+    /// Applies all commands sent to this window inside `f` together, in a single
+    /// GTK main loop iteration.
+    ///
+    /// Use this when changing several window properties at once, to avoid visible
+    /// intermediate states.
+    ///
+    /// ```ignore
+    /// window.batch(|window| {
+    ///     window.unfullscreen();
+    ///     window.set_title("Paused");
+    /// });
+    /// ```
+    pub fn batch(&mut self, f: impl FnOnce(&Self)) {
+        *self.batch.get_mut().expect("poisoned") = Some(Vec::new());
+        f(self);
+        let commands = self
+            .batch
+            .get_mut()
+            .expect("poisoned")
+            .take()
+            .unwrap_or_default();
+        if !commands.is_empty() {
+            self.send(WindowCommand::Batch(commands));
+        }
+    }
+
+    /// Reports that a region of the render target, in physical pixels, changed
+    /// in this frame.
+    ///
+    /// GTK then only has to redraw the damaged regions, which reduces compositor
+    /// work for mostly static content. If no damage is reported in a frame, the
+    /// whole render target is assumed to have changed.
+    pub fn report_damage(&self, rect: URect) {
+        self.frame_damage
+            .lock()
+            .expect("poisoned")
+            .get_or_insert_with(Vec::new)
+            .push(rect);
+    }
+
+    /// Reports that nothing in the render target changed in this frame.
+    ///
+    /// See [`AdwaitaWindow::report_damage`].
+    pub fn report_no_damage(&self) {
+        self.frame_damage
+            .lock()
+            .expect("poisoned")
+            .get_or_insert_with(Vec::new);
+    }
+
+    /// Reads back the depth buffer in `rect` of this window's render target, in
+    /// physical pixels, for example for GPU picking.
+    ///
+    /// The depth of the next rendered frame is sent in an [`AdwaitaDepthReadback`] a
+    /// few frames later, once the GPU has copied it. The depth is read from the
+    /// camera rendering to this window with the highest [`Camera::order`], which must
+    /// not use MSAA, and must include [`TextureUsages::COPY_SRC`] in its
+    /// `Camera3d::depth_texture_usages`.
+    ///
+    /// [`TextureUsages::COPY_SRC`]: bevy::render::render_resource::TextureUsages::COPY_SRC
+    pub fn read_depth(&self, rect: URect) {
+        self.depth_requests.lock().expect("poisoned").push(rect);
+    }
+
+    /// Copies the next frame rendered to this window back to the CPU, for example for
+    /// screenshots.
+    ///
+    /// The frame is sent in an [`AdwaitaFrameCaptured`] a few frames later, once the
+    /// GPU has copied it, so this never waits for the GPU. Requests made before the
+    /// next frame is rendered are combined into one capture.
+    pub fn capture_frame(&self) {
+        self.capture_requested.store(true, Ordering::SeqCst);
+    }
+
+    /// Stops rendering new frames to this window, while GTK keeps showing the last
+    /// frame that was rendered.
+    ///
+    /// This deactivates all cameras rendering to the window, and saves GPU time in
+    /// pause menus or while a modal dialog is open. The render target isn't resized
+    /// while paused, so the held frame is scaled to fit the window instead.
+    pub fn pause_rendering(&self) {
+        self.rendering_paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Continues rendering to this window after [`AdwaitaWindow::pause_rendering`],
+    /// starting with a fresh frame.
+    pub fn resume_rendering(&self) {
+        self.rendering_paused.store(false, Ordering::SeqCst);
+    }
+
+    /// Whether rendering is paused by [`AdwaitaWindow::pause_rendering`].
+    #[must_use]
+    pub fn is_rendering_paused(&self) -> bool {
+        self.rendering_paused.load(Ordering::SeqCst)
+    }
+
     pub fn set_maximized(&self, maximized: bool) {
-        _ = self
-            .send_command
-            .send(WindowCommand::SetMaximized(maximized));
+        self.send(WindowCommand::SetMaximized(maximized));
     }
 
     pub fn maximize(&self) {
@@ -228,9 +1830,7 @@ impl AdwaitaWindow {
     }
 
     pub fn set_fullscreen(&self, fullscreen: bool) {
-        _ = self
-            .send_command
-            .send(WindowCommand::SetFullscreen(fullscreen));
+        self.send(WindowCommand::SetFullscreen(fullscreen));
     }
 
     pub fn fullscreen(&self) {
@@ -241,9 +1841,107 @@ impl AdwaitaWindow {
         self.set_fullscreen(false);
     }
 
+    /// Closes the window, without sending an [`AdwaitaCloseRequested`] first.
+    ///
+    /// An [`AdwaitaWindowClosed`] is sent once GTK has closed it, and the entity is
+    /// cleaned up according to the [`AdwaitaCloseBehavior`].
+    pub fn close(&self) {
+        self.send(WindowCommand::Close);
+    }
+
+    /// Sets [`AdwaitaWindowConfig::min_width`] and [`AdwaitaWindowConfig::min_height`].
+    pub fn set_min_size(&self, width: Option<u32>, height: Option<u32>) {
+        *self.min_size.lock().expect("poisoned") =
+            UVec2::new(width.unwrap_or(0), height.unwrap_or(0));
+        self.send(WindowCommand::SetMinSize { width, height });
+    }
+
+    /// Sets [`AdwaitaWindowConfig::max_width`] and [`AdwaitaWindowConfig::max_height`].
+    ///
+    /// This only limits the size of the render target, see
+    /// [`AdwaitaWindowConfig::max_width`].
+    pub fn set_max_size(&self, width: Option<u32>, height: Option<u32>) {
+        *self.max_size.lock().expect("poisoned") =
+            UVec2::new(width.unwrap_or(u32::MAX), height.unwrap_or(u32::MAX));
+    }
+
     pub fn set_title(&self, title: impl Into<String>) {
         let title = title.into();
-        _ = self.send_command.send(WindowCommand::SetTitle(title));
+        self.send(WindowCommand::SetTitle(title));
+    }
+
+    /// Sets [`AdwaitaWindowConfig::icon`], or resets the window to the app's default
+    /// icon if [`None`].
+    pub fn set_icon(&self, icon: Option<AdwaitaWindowIcon>) {
+        self.send(WindowCommand::SetIcon(icon));
+    }
+
+    /// Shows or hides the window's title bar, without changing its [`AdwaitaHeaderBar`]
+    /// style.
+    ///
+    /// When hidden, the render target takes up the space of the header bar.
+    /// This has no effect if the window was opened with [`AdwaitaHeaderBar::None`].
+    pub fn set_title_bar_visible(&self, visible: bool) {
+        self.send(WindowCommand::SetTitleBarVisible(visible));
+    }
+
+    pub fn show_title_bar(&self) {
+        self.set_title_bar_visible(true);
+    }
+
+    pub fn hide_title_bar(&self) {
+        self.set_title_bar_visible(false);
+    }
+
+    /// Sets [`AdwaitaWindowConfig::decorated`].
+    pub fn set_decorated(&self, decorated: bool) {
+        self.send(WindowCommand::SetDecorated(decorated));
+    }
+
+    /// Whether the title bar is currently shown, as last applied by GTK.
+    #[must_use]
+    pub fn is_title_bar_visible(&self) -> bool {
+        self.title_bar_visible.load(Ordering::SeqCst)
+    }
+
+    /// Sets the reading direction of the GTK-drawn chrome of this window.
+    ///
+    /// See [`AdwaitaWindowConfig::text_direction`].
+    pub fn set_text_direction(&self, direction: AdwaitaTextDirection) {
+        self.send(WindowCommand::SetTextDirection(direction));
+    }
+
+    /// Overrides the user's light/dark preference.
+    ///
+    /// GTK styles all windows of the process the same way, so this also applies to
+    /// every other window. [`AdwaitaColorScheme`] is updated once the change is applied.
+    pub fn set_color_scheme(&self, preference: AdwaitaColorSchemePreference) {
+        self.send(WindowCommand::SetColorScheme(preference));
+    }
+
+    /// Whether the GTK-drawn chrome is laid out right-to-left, as last applied by
+    /// GTK.
+    ///
+    /// With [`AdwaitaTextDirection::System`], this reflects the system locale.
+    #[must_use]
+    pub fn is_right_to_left(&self) -> bool {
+        self.right_to_left.load(Ordering::SeqCst)
+    }
+}
+
+fn release_render_targets(
+    trigger: Trigger<OnRemove, AdwaitaWindow>,
+    windows: Query<&AdwaitaWindow>,
+    mut manual_texture_views: ResMut<ManualTextureViews>,
+) {
+    let Ok(window) = windows.get(trigger.entity()) else {
+        return;
+    };
+    // GTK keeps its own reference to the frame it's showing, so the render target
+    // is only freed once the window has been destroyed too
+    manual_texture_views.remove(&window.render_target_handle);
+    if let Some(handle) = window.alternate_view_handle {
+        manual_texture_views.remove(&handle);
     }
 }
 
@@ -287,6 +1985,23 @@ fn update_existing_cameras_render_target(
     }
 }
 
+fn retarget_bound_cameras(
+    windows: Query<&AdwaitaWindow>,
+    mut cameras: Query<(&RenderToAdwaitaWindow, &mut Camera)>,
+) {
+    for (RenderToAdwaitaWindow(window), mut camera) in &mut cameras {
+        let Ok(window) = windows.get(*window) else {
+            continue;
+        };
+        // only touch the camera when retargeting, so it isn't marked as changed
+        // every frame
+        let handle = window.render_target_handle;
+        if !matches!(camera.target, RenderTarget::TextureView(current) if current == handle) {
+            camera.target = window.render_target();
+        }
+    }
+}
+
 fn exit_on_primary_closed(
     mut app_exit_events: EventWriter<AppExit>,
     windows: Query<(), (With<AdwaitaWindow>, With<PrimaryAdwaitaWindow>)>,
@@ -307,103 +2022,718 @@ fn exit_on_all_closed(
     }
 }
 
+// grouped since systems can only take up to 16 params
+#[derive(SystemParam)]
+struct WidgetEventWriters<'w> {
+    gesture: EventWriter<'w, AdwaitaGesture>,
+    dialog: EventWriter<'w, AdwaitaDialogResponse>,
+    toast: EventWriter<'w, AdwaitaToastDismissed>,
+    clipboard: EventWriter<'w, AdwaitaClipboardText>,
+    header: EventWriter<'w, AdwaitaHeaderAction>,
+}
+
+#[derive(SystemParam)]
+struct WindowEventWriters<'w> {
+    focused: EventWriter<'w, WindowFocused>,
+    moved: EventWriter<'w, WindowMoved>,
+    monitor: EventWriter<'w, AdwaitaMonitorChanged>,
+    frame_tick: EventWriter<'w, AdwaitaFrameTick>,
+}
+
+#[derive(SystemParam)]
+struct InputEventWriters<'w> {
+    wheel: EventWriter<'w, MouseWheel>,
+    keyboard: EventWriter<'w, KeyboardInput>,
+    focus_lost: EventWriter<'w, KeyboardFocusLost>,
+    ime: EventWriter<'w, Ime>,
+    mouse_button: EventWriter<'w, MouseButtonInput>,
+    mouse_motion: EventWriter<'w, MouseMotion>,
+    touch: EventWriter<'w, TouchInput>,
+}
+
+#[derive(SystemParam)]
+struct CursorEventWriters<'w> {
+    moved: EventWriter<'w, CursorMoved>,
+    entered: EventWriter<'w, CursorEntered>,
+    left: EventWriter<'w, CursorLeft>,
+}
+
+#[derive(SystemParam)]
+struct FileDropEventWriters<'w> {
+    drag: EventWriter<'w, FileDragAndDrop>,
+    hovered: EventWriter<'w, AdwaitaFileHovered>,
+    dropped: EventWriter<'w, AdwaitaFileDropped>,
+}
+
+fn forward_window_events(
+    mut windows: Query<(Entity, &mut AdwaitaWindow, &mut AdwaitaWindowFocused)>,
+    mut widget_events: WidgetEventWriters,
+    mut window_events: WindowEventWriters,
+    mut input_events: InputEventWriters,
+    mut cursor_events: CursorEventWriters,
+    mut file_drop_events: FileDropEventWriters,
+) {
+    for (entity, mut window, mut focused) in &mut windows {
+        while let Ok(event) = window.recv_event.try_recv() {
+            match event {
+                WindowEvent::Gesture { phase, kind } => {
+                    widget_events.gesture.send(AdwaitaGesture {
+                        window: entity,
+                        phase,
+                        kind,
+                    });
+                }
+                WindowEvent::DialogResponse { id, response } => {
+                    widget_events.dialog.send(AdwaitaDialogResponse {
+                        window: entity,
+                        dialog: id,
+                        response,
+                    });
+                }
+                WindowEvent::ToastDismissed { id, button_clicked } => {
+                    widget_events.toast.send(AdwaitaToastDismissed {
+                        window: entity,
+                        id,
+                        button_clicked,
+                    });
+                }
+                WindowEvent::ClipboardText(text) => {
+                    widget_events.clipboard.send(AdwaitaClipboardText {
+                        window: entity,
+                        text,
+                    });
+                }
+                WindowEvent::MonitorChanged(monitor) => {
+                    window_events.monitor.send(AdwaitaMonitorChanged {
+                        window: entity,
+                        monitor,
+                    });
+                }
+                WindowEvent::HeaderAction(id) => {
+                    widget_events
+                        .header
+                        .send(AdwaitaHeaderAction { window: entity, id });
+                }
+                WindowEvent::Scroll { unit, delta } => {
+                    input_events.wheel.send(MouseWheel {
+                        unit,
+                        x: delta.x,
+                        y: delta.y,
+                        window: entity,
+                    });
+                }
+                WindowEvent::Key {
+                    key_code,
+                    logical_key,
+                    state,
+                } => {
+                    input_events.keyboard.send(KeyboardInput {
+                        key_code,
+                        logical_key,
+                        state,
+                        window: entity,
+                    });
+                }
+                WindowEvent::Focused(is_focused) => {
+                    focused.set_if_neq(AdwaitaWindowFocused(is_focused));
+                    window_events.focused.send(WindowFocused {
+                        window: entity,
+                        focused: is_focused,
+                    });
+                    // we never see the releases of keys held while the window is in
+                    // the background, so they'd be stuck down otherwise
+                    if !is_focused {
+                        input_events.focus_lost.send(KeyboardFocusLost);
+                    }
+                }
+                WindowEvent::StateChanged {
+                    size,
+                    maximized,
+                    fullscreen,
+                } => {
+                    let config = &mut window.current_config;
+                    config.width = size.x;
+                    config.height = size.y;
+                    config.maximized = maximized;
+                    config.fullscreen = fullscreen;
+                }
+                WindowEvent::Moved(position) => {
+                    window.position = Some(position);
+                    window_events.moved.send(WindowMoved {
+                        window: entity,
+                        position,
+                    });
+                }
+                WindowEvent::CursorMoved {
+                    position,
+                    widget_size,
+                } => {
+                    let Some(position) = window.logical_frame_position(widget_size, position)
+                    else {
+                        continue;
+                    };
+                    let delta = window.cursor_position.map(|last| position - last);
+                    window.cursor_position = Some(position);
+                    cursor_events.moved.send(CursorMoved {
+                        window: entity,
+                        position,
+                        delta,
+                    });
+                }
+                WindowEvent::CursorEntered => {
+                    cursor_events.entered.send(CursorEntered { window: entity });
+                }
+                WindowEvent::CursorLeft => {
+                    window.cursor_position = None;
+                    cursor_events.left.send(CursorLeft { window: entity });
+                }
+                WindowEvent::MouseMotion(delta) => {
+                    input_events.mouse_motion.send(MouseMotion { delta });
+                }
+                WindowEvent::FileHovered(paths) => {
+                    file_drop_events
+                        .drag
+                        .send_batch(paths.into_iter().map(|path_buf| {
+                            FileDragAndDrop::HoveredFile {
+                                window: entity,
+                                path_buf,
+                            }
+                        }));
+                }
+                WindowEvent::FileDragMoved {
+                    position,
+                    widget_size,
+                } => {
+                    if let Some(position) = window.logical_frame_position(widget_size, position) {
+                        file_drop_events.hovered.send(AdwaitaFileHovered {
+                            window: entity,
+                            position,
+                        });
+                    }
+                }
+                WindowEvent::FileHoverCanceled => {
+                    file_drop_events
+                        .drag
+                        .send(FileDragAndDrop::HoveredFileCanceled { window: entity });
+                }
+                WindowEvent::FileDropped {
+                    paths,
+                    position,
+                    widget_size,
+                } => {
+                    file_drop_events
+                        .drag
+                        .send_batch(paths.iter().map(|path_buf| FileDragAndDrop::DroppedFile {
+                            window: entity,
+                            path_buf: path_buf.clone(),
+                        }));
+                    let position = window
+                        .logical_frame_position(widget_size, position)
+                        .unwrap_or_default();
+                    file_drop_events.dropped.send(AdwaitaFileDropped {
+                        window: entity,
+                        paths,
+                        position,
+                    });
+                }
+                WindowEvent::ImeEnabled(true) => {
+                    input_events.ime.send(Ime::Enabled { window: entity });
+                }
+                WindowEvent::ImeEnabled(false) => {
+                    input_events.ime.send(Ime::Disabled { window: entity });
+                }
+                WindowEvent::ImePreedit { value, cursor } => {
+                    input_events.ime.send(Ime::Preedit {
+                        window: entity,
+                        value,
+                        cursor: cursor.map(|cursor| (cursor, cursor)),
+                    });
+                }
+                WindowEvent::ImeCommit(value) => {
+                    input_events.ime.send(Ime::Commit {
+                        window: entity,
+                        value,
+                    });
+                }
+                WindowEvent::Touch {
+                    id,
+                    phase,
+                    position,
+                    widget_size,
+                } => {
+                    // mapped the same way as the cursor
+                    let Some(position) = window.logical_frame_position(widget_size, position)
+                    else {
+                        continue;
+                    };
+                    input_events.touch.send(TouchInput {
+                        phase,
+                        position,
+                        window: entity,
+                        force: None,
+                        id,
+                    });
+                }
+                WindowEvent::MouseButton { button, state } => {
+                    input_events.mouse_button.send(MouseButtonInput {
+                        button,
+                        state,
+                        window: entity,
+                    });
+                }
+                WindowEvent::FrameTick {
+                    frame_counter,
+                    frame_time,
+                    predicted_presentation_time,
+                    refresh_interval,
+                } => {
+                    window_events.frame_tick.send(AdwaitaFrameTick {
+                        window: entity,
+                        frame_counter,
+                        frame_time,
+                        predicted_presentation_time,
+                        refresh_interval,
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn poll_windows(
     mut commands: Commands,
     mut windows: Query<(Entity, &mut AdwaitaWindow)>,
     render_device: Res<RenderDevice>,
+    display: Res<SharedDisplay>,
     mut manual_texture_views: ResMut<ManualTextureViews>,
+    close_behavior: Res<AdwaitaCloseBehavior>,
+    mut closed_events: EventWriter<AdwaitaWindowClosed>,
+    mut close_requested_events: EventWriter<AdwaitaCloseRequested>,
+    mut window_close_requested_events: EventWriter<WindowCloseRequested>,
+    mut resized_events: EventWriter<WindowResized>,
 ) {
     for (entity, mut window) in &mut windows {
         if window.closed.load(Ordering::SeqCst) {
             info!("Adwaita window {entity} closed");
-            commands.entity(entity).despawn_recursive();
+            closed_events.send(AdwaitaWindowClosed { window: entity });
+            match *close_behavior {
+                AdwaitaCloseBehavior::DespawnRecursive => {
+                    commands.entity(entity).despawn_recursive();
+                }
+                AdwaitaCloseBehavior::Despawn => {
+                    commands.entity(entity).clear_children().despawn();
+                }
+                AdwaitaCloseBehavior::RemoveComponent => {
+                    commands.entity(entity).remove::<AdwaitaWindow>();
+                }
+            }
+            continue;
+        }
+        if window.close_requested.swap(false, Ordering::SeqCst) {
+            close_requested_events.send(AdwaitaCloseRequested { window: entity });
+            window_close_requested_events.send(WindowCloseRequested { window: entity });
+        }
+        // a new render target would replace the frame that GTK is holding on to,
+        // so wait until rendering is resumed to pick up the new size
+        if window.is_rendering_paused() {
             continue;
         }
 
-        let (width, height, scale_factor) = (
-            window.render_target_width.load(Ordering::SeqCst),
-            window.render_target_height.load(Ordering::SeqCst),
-            window.scale_factor.load(Ordering::SeqCst),
-        );
-        let (Ok(width), Ok(height), Ok(scale_factor)) = (
-            u32::try_from(width),
-            u32::try_from(height),
-            u32::try_from(scale_factor),
+        let (Ok(width), Ok(height)) = (
+            u32::try_from(window.render_target_width.load(Ordering::SeqCst)),
+            u32::try_from(window.render_target_height.load(Ordering::SeqCst)),
         ) else {
             continue;
         };
+        let scale_factor = if let Some(scale_factor) = window.render_scale_override {
+            scale_factor
+        } else {
+            let scale_factor = f32::from_bits(window.scale_factor.load(Ordering::SeqCst));
+            if scale_factor <= 0.0 {
+                continue;
+            }
+            scale_factor
+        };
+        window.last_scale_factor = scale_factor;
 
-        let size = UVec2::new(width.max(1) * scale_factor, height.max(1) * scale_factor);
-        if size == window.last_render_target_size {
-            continue;
+        // the widget can be smaller than its minimum size before it's first laid out
+        let min_size = *window.min_size.lock().expect("poisoned");
+        let max_size = *window.max_size.lock().expect("poisoned");
+        let window_size = UVec2::new(width, height)
+            .max(min_size)
+            .min(max_size)
+            .max(UVec2::ONE)
+            .as_vec2();
+        let content_size = match window.aspect_ratio {
+            Some(aspect_ratio) if window_size.x / window_size.y > aspect_ratio => {
+                Vec2::new(window_size.y * aspect_ratio, window_size.y)
+            }
+            Some(aspect_ratio) => Vec2::new(window_size.x, window_size.x / aspect_ratio),
+            None => window_size,
+        };
+        // not tied to the render target size, which also changes with the render
+        // scale, or when render targets are recreated
+        if content_size != window.last_content_size {
+            window.last_content_size = content_size;
+            resized_events.send(WindowResized {
+                window: entity,
+                width: content_size.x,
+                height: content_size.y,
+            });
+        }
+        let base_size = (content_size * scale_factor)
+            .round()
+            .as_uvec2()
+            .max(UVec2::ONE);
+        if base_size != window.last_base_size {
+            // targets of the old window size will never be used again
+            if !window.preallocate_render_target {
+                window.render_target = None;
+                window.render_target_pool.clear();
+            }
+            window.last_base_size = base_size;
+        }
+
+        let size = (base_size.as_vec2() * window.render_scale)
+            .round()
+            .as_uvec2()
+            .max(UVec2::ONE);
+        let max_import_size = window.max_import_size();
+        // GTK scales the smaller frame back up to fill the window
+        let size = match max_import_size {
+            Some(max) if size.cmpgt(max).any() => {
+                let scale = (max.as_vec2() / size.as_vec2()).min_element();
+                (size.as_vec2() * scale).floor().as_uvec2().max(UVec2::ONE)
+            }
+            _ => size,
+        };
+        if size != window.last_render_target_size {
+            info!("Window resized to {size}");
+            window.last_render_target_size = size;
+
+            let alloc_size = match window.max_render_target_size.get() {
+                Some(&max_size) if window.preallocate_render_target => {
+                    let max_size = max_import_size.map_or(max_size, |max| max_size.min(max));
+                    max_size.max(size)
+                }
+                _ => size,
+            };
+            if window
+                .render_target
+                .as_ref()
+                .is_none_or(|ring| ring.size != alloc_size)
+            {
+                let pooled_index = window
+                    .render_target_pool
+                    .iter()
+                    .position(|ring| ring.size == alloc_size);
+                let ring = match pooled_index {
+                    Some(index) => window.render_target_pool.remove(index),
+                    None => RenderTargetRing::new(alloc_size),
+                };
+                let previous = window.render_target.replace(ring);
+                // only keep other render targets around if we're likely to switch back to them
+                if let Some(previous) = previous.filter(|_| {
+                    window.dynamic_resolution.is_some() || window.preallocate_render_target
+                }) {
+                    if window.render_target_pool.len() >= RENDER_TARGET_POOL_SIZE {
+                        window.render_target_pool.remove(0);
+                    }
+                    window.render_target_pool.push(previous);
+                }
+            }
         }
-        info!("Window resized to {size}");
-        window.last_render_target_size = size;
-
-        let (manual_texture_view, dmabuf_fd) =
-            render::setup_render_target(size, render_device.as_ref());
-        // give a shared ref of this texture view to the Adwaita app
-        // so that, even if *we* drop it while the window is rendering this frame,
-        // the GPU resources won't be deallocated until the window *also* drops it
-        let texture_view = manual_texture_view.texture_view.clone();
-        manual_texture_views.insert(window.render_target_handle.clone(), manual_texture_view);
-        let next_frame_info = FrameInfo {
-            dmabuf: DmabufInfo {
-                size,
-                fd: dmabuf_fd,
-            },
-            _texture_view: texture_view,
+
+        // every frame is rendered into the next free image of the ring, so that we
+        // never draw over a frame which GTK is still showing
+        let window = &mut *window;
+        let Some(ring) = window.render_target.as_mut() else {
+            continue;
         };
-        info!("Stored next frame info {next_frame_info:?}");
+        let format = window.render_target_format;
+        let transparent = window.transparent;
+        let alternate = format
+            .alternate()
+            .filter(|_| window.alternate_view_handle.is_some());
+        let next_buffer_id = &mut window.next_buffer_id;
+        let image = ring.next_image(|size| {
+            let buffer_id = *next_buffer_id;
+            *next_buffer_id += 1;
+            // let the driver pick a tiled layout, as long as the display can import it
+            let modifiers = display
+                .0
+                .lock()
+                .expect("poisoned")
+                .as_ref()
+                .map(|display| {
+                    display
+                        .dmabuf_formats
+                        .iter()
+                        .filter(|(fourcc, _)| *fourcc == format.fourcc(transparent))
+                        .map(|(_, modifier)| *modifier)
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            RingImage::new(
+                buffer_id,
+                render::setup_render_target(
+                    size,
+                    format,
+                    alternate,
+                    &modifiers,
+                    render_device.as_ref(),
+                ),
+            )
+        });
+
+        manual_texture_views.insert(
+            window.render_target_handle,
+            image.target.manual_texture_view.clone(),
+        );
+        if let (Some(handle), Some(view)) =
+            (window.alternate_view_handle, &image.target.alternate_view)
+        {
+            manual_texture_views.insert(handle, view.clone());
+        }
+        // the frame info holds a shared ref of the texture view, so that even if
+        // *we* drop it while the window is rendering this frame, the GPU resources
+        // won't be deallocated until the window *also* drops it
+        let sequence = window.next_frame_sequence;
+        window.next_frame_sequence += 1;
+        let next_frame_info = image.frame_info(sequence, size, format, transparent);
         window
             .next_frame_to_render
             .store(Some(Box::new(next_frame_info)), Ordering::SeqCst);
     }
 }
 
+fn sync_window_sizes(
+    mut windows: Query<(&AdwaitaWindow, &mut AdwaitaWindowSize, Option<&mut Window>)>,
+) {
+    for (window, mut size, bevy_window) in &mut windows {
+        let physical_size = window.physical_size();
+        let scale_factor = window.scale_factor();
+        size.set_if_neq(AdwaitaWindowSize {
+            physical_size,
+            scale_factor,
+        });
+
+        // we're the windowing backend for this entity, so we fill in what winit would
+        let Some(mut bevy_window) = bevy_window else {
+            continue;
+        };
+        let resolution = &bevy_window.resolution;
+        if resolution.physical_size() != physical_size
+            || resolution.base_scale_factor() != scale_factor
+        {
+            let resolution = &mut bevy_window.resolution;
+            resolution.set_scale_factor(scale_factor);
+            resolution.set_physical_resolution(physical_size.x, physical_size.y);
+        }
+    }
+}
+
+fn expire_present_waiters(windows: Query<&AdwaitaWindow>) {
+    let now = Instant::now();
+    for window in &windows {
+        // dropping the sender wakes the task up with `None`
+        window
+            .present_waiters
+            .lock()
+            .expect("poisoned")
+            .retain(|waiter| waiter.deadline.is_none_or(|deadline| deadline > now));
+    }
+}
+
+fn sync_window_cursors(windows: Query<(&AdwaitaWindow, &Window), Changed<Window>>) {
+    for (window, bevy_window) in &windows {
+        window.set_cursor_icon(bevy_window.cursor.icon);
+        window.set_cursor_visible(bevy_window.cursor.visible);
+        window.set_cursor_grab(bevy_window.cursor.grab_mode);
+    }
+}
+
+fn sync_window_ime(windows: Query<(&AdwaitaWindow, &Window), Changed<Window>>) {
+    for (window, bevy_window) in &windows {
+        window.set_ime_enabled(bevy_window.ime_enabled);
+        if bevy_window.ime_enabled {
+            let position = bevy_window.ime_position * window.scale_factor();
+            window.set_ime_cursor_area(Rect::from_corners(position, position));
+        }
+    }
+}
+
+/// Shuts GTK down when the last app using it exits, rather than when the process
+/// exits, so that its windows close as soon as the app is done.
+fn release_main_thread_on_exit(mut commands: Commands, mut exits: EventReader<AppExit>) {
+    if exits.read().next().is_some() {
+        commands.remove_resource::<AppRegistration>();
+    }
+}
+
+/// Marks cameras deactivated by [`pause_cameras`], so that we only reactivate the
+/// cameras which we deactivated ourselves.
+#[derive(Debug, Component)]
+struct PausedCamera;
+
+fn pause_cameras(
+    mut commands: Commands,
+    mut windows: Query<&mut AdwaitaWindow>,
+    mut cameras: Query<(Entity, &mut Camera, Has<PausedCamera>)>,
+) {
+    for mut window in &mut windows {
+        // GTK hasn't picked up the last frame yet, so a new one would be wasted
+        window.dropping_frame = window.frame_backpressure == AdwaitaFrameBackpressure::DropNewest
+            && window
+                .consumed_frame
+                .is_behind(window.rendered_frame_count.load(Ordering::SeqCst));
+        let handle = window.render_target_handle;
+        let paused = window.is_rendering_paused() || window.dropping_frame;
+        for (entity, mut camera, was_paused) in &mut cameras {
+            if !matches!(camera.target, RenderTarget::TextureView(target) if target == handle) {
+                continue;
+            }
+            if paused && camera.is_active {
+                camera.is_active = false;
+                commands.entity(entity).insert(PausedCamera);
+            } else if !paused && was_paused {
+                camera.is_active = true;
+                commands.entity(entity).remove::<PausedCamera>();
+            }
+        }
+    }
+}
+
+fn update_preallocated_viewports(windows: Query<&AdwaitaWindow>, mut cameras: Query<&mut Camera>) {
+    for window in &windows {
+        // no render target has been created yet
+        if !window.preallocate_render_target || window.last_render_target_size == UVec2::ZERO {
+            continue;
+        }
+
+        let handle = window.render_target_handle;
+        let size = window.last_render_target_size;
+        for mut camera in &mut cameras {
+            if !matches!(camera.target, RenderTarget::TextureView(target) if target == handle) {
+                continue;
+            }
+            let up_to_date = camera.viewport.as_ref().is_some_and(|viewport| {
+                viewport.physical_position == UVec2::ZERO && viewport.physical_size == size
+            });
+            if !up_to_date {
+                camera.viewport = Some(Viewport {
+                    physical_position: UVec2::ZERO,
+                    physical_size: size,
+                    ..default()
+                });
+            }
+        }
+    }
+}
+
 #[derive(Debug, Component)]
 struct RenderWindow {
     shared_next_frame: Arc<AtomicOptionBox<FrameInfo>>,
     next_frame_to_render: Arc<AtomicOptionBox<FrameInfo>>,
     next_frame_to_send: Option<Box<FrameInfo>>,
+    shared_damage: Arc<Mutex<FrameDamage>>,
+    damage: Option<Vec<URect>>,
+    rendered_frame_count: Arc<AtomicU32>,
+    consumed_frame: SharedConsumedFrame,
+    frame_counters: Option<SharedFrameCounters>,
+    frame_backpressure: AdwaitaFrameBackpressure,
+    frame_count: u32,
+    // rendering is paused, or this frame is dropped due to backpressure
+    skip_frame: bool,
+    render_target_handle: ManualTextureViewHandle,
+    depth_requests: Vec<URect>,
+    depth_readbacks: SharedDepthReadbacks,
+    capture_requested: Arc<AtomicBool>,
+    frame_captures: SharedFrameCaptures,
 }
 
-fn extract_windows(mut commands: Commands, windows: Extract<Query<&AdwaitaWindow>>) {
-    info!("-- RUNNING extract_windows");
+fn extract_windows(
+    mut commands: Commands,
+    windows: Extract<Query<&AdwaitaWindow>>,
+    frame_count: Extract<Option<Res<FrameCount>>>,
+) {
+    let frame_count = frame_count.as_ref().map_or(0, |frame_count| frame_count.0);
     for window in &windows {
-        let Some(next_frame_to_send) = window.next_frame_to_render.take(Ordering::SeqCst) else {
-            continue;
-        };
-        info!("--extract: Got next frame info {next_frame_to_send:?}");
+        let next_frame_to_send = window.next_frame_to_render.take(Ordering::SeqCst);
+        if let Some(next_frame_to_send) = &next_frame_to_send {
+            trace!("--extract: Got next frame info {next_frame_to_send:?}");
+        }
 
         commands.spawn(RenderWindow {
             shared_next_frame: window.shared_next_frame.clone(),
             next_frame_to_render: window.next_frame_to_render.clone(),
-            next_frame_to_send: Some(next_frame_to_send),
+            next_frame_to_send,
+            shared_damage: window.shared_damage.clone(),
+            damage: window.frame_damage.lock().expect("poisoned").take(),
+            rendered_frame_count: window.rendered_frame_count.clone(),
+            consumed_frame: window.consumed_frame.clone(),
+            frame_counters: window.frame_counters.clone(),
+            frame_backpressure: window.frame_backpressure,
+            frame_count,
+            skip_frame: window.is_rendering_paused() || window.dropping_frame,
+            render_target_handle: window.render_target_handle,
+            depth_requests: std::mem::take(&mut *window.depth_requests.lock().expect("poisoned")),
+            depth_readbacks: window.depth_readbacks.clone(),
+            capture_requested: window.capture_requested.clone(),
+            frame_captures: window.frame_captures.clone(),
         });
     }
 }
 
 fn send_frame_to_windows(mut windows: Query<&mut RenderWindow>) {
-    info!("-- RUNNING send_frame_info_to_windows");
     for mut window in &mut windows {
+        // nothing was rendered, so GTK keeps its current texture as-is, which also
+        // keeps its `texture_view` alive
+        if window.skip_frame {
+            continue;
+        }
+
+        // this frame has now been rendered, so GTK can redraw what it changed
+        let damage = window.damage.take();
+        window.shared_damage.lock().expect("poisoned").merge(damage);
+        window
+            .rendered_frame_count
+            .store(window.frame_count, Ordering::SeqCst);
+
         let Some(next_frame_info) = window.next_frame_to_send.take() else {
             continue;
         };
 
-        info!("Sending next frame {next_frame_info:?} now.");
+        trace!("Sending next frame {next_frame_info:?} now.");
+        render::store_newest(&window.shared_next_frame, next_frame_info);
+        if let Some(counters) = &window.frame_counters {
+            counters.sent.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+fn throttle_frames(windows: Query<&RenderWindow>) {
+    for window in &windows {
+        if window.frame_backpressure != AdwaitaFrameBackpressure::Throttle || window.skip_frame {
+            continue;
+        }
+        // rendering now would overwrite the frame that GTK hasn't picked up yet
+        let rendered_frame_count = window.rendered_frame_count.load(Ordering::SeqCst);
         window
-            .shared_next_frame
-            .store(Some(next_frame_info), Ordering::SeqCst);
+            .consumed_frame
+            .wait(rendered_frame_count, render::THROTTLE_TIMEOUT);
     }
 }
 
 fn put_back_next_frame_if_not_sent(mut windows: Query<&mut RenderWindow>) {
     for mut window in &mut windows {
+        // the main world may have already made a newer frame since we took this one
         if let Some(frame_info) = window.next_frame_to_send.take() {
-            window
-                .next_frame_to_render
-                .store(Some(frame_info), Ordering::SeqCst);
+            render::store_newest(&window.next_frame_to_render, frame_info);
+            if let Some(counters) = &window.frame_counters {
+                counters.put_back.fetch_add(1, Ordering::Relaxed);
+            }
         }
     }
 }
@@ -416,6 +2746,294 @@ fn put_back_next_frame_if_not_sent(mut windows: Query<&mut RenderWindow>) {
 //                                  ^                      ^ ^
 //            take `next_to_render` |                      | | in `Last`:
 //          store in `next_to_send` |                      | | if we still have a `next_to_send`,
-//                                                         | | put it back
+//                                                         | | put it back, unless there's a newer one
 //                                 after RenderSet::Render |
 //                            take and send `next_to_send` |
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        core_pipeline::core_3d::{
+            extract_core_3d_camera_phases, prepare_core_3d_depth_textures, AlphaMask3d, Opaque3d,
+            Transmissive3d, Transparent3d,
+        },
+        ecs::system::RunSystemOnce,
+        render::{
+            batching::gpu_preprocessing::GpuPreprocessingSupport,
+            camera::{camera_system, extract_cameras},
+            render_phase::{ViewBinnedRenderPhases, ViewSortedRenderPhases},
+            settings::RenderCreation,
+            texture::TextureCache,
+            view::ViewDepthTexture,
+            MainWorld,
+        },
+        window::{WindowCreated, WindowScaleFactorChanged},
+    };
+
+    use super::*;
+
+    /// Opens `count` windows without a GTK main thread, and returns the requests
+    /// which would have been sent to it.
+    pub(crate) fn open_windows(world: &mut World, count: usize) -> Vec<(Entity, WindowOpen)> {
+        let (send, recv) = flume::unbounded();
+        world.insert_resource(SendWindowOpen(send));
+        world.init_resource::<ManualTextureViews>();
+        (0..count)
+            .map(|index| {
+                let entity = world.spawn_empty().id();
+                let config = AdwaitaWindowConfig {
+                    title: format!("Window {index}"),
+                    ..default()
+                };
+                AdwaitaWindow::open(config).apply(entity, world);
+                let Ok(MainThreadRequest::OpenWindow(request)) = recv.try_recv() else {
+                    panic!("window {index} wasn't sent to the main thread");
+                };
+                (entity, *request)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn windows_share_state_with_their_own_request() {
+        let mut world = World::new();
+        let windows = open_windows(&mut world, 2);
+        for (index, (entity, request)) in windows.iter().enumerate() {
+            let window = world.get::<AdwaitaWindow>(*entity).unwrap();
+            assert_eq!(request.config.title, format!("Window {index}"));
+            assert!(Arc::ptr_eq(&window.commands, &request.commands));
+            assert!(Arc::ptr_eq(
+                &window.shared_next_frame,
+                &request.shared_next_frame
+            ));
+            assert!(Arc::ptr_eq(&window.closed, &request.closed));
+
+            let (_, other) = &windows[1 - index];
+            assert!(!Arc::ptr_eq(&window.commands, &other.commands));
+            assert!(!Arc::ptr_eq(
+                &window.shared_next_frame,
+                &other.shared_next_frame
+            ));
+        }
+    }
+
+    #[test]
+    fn window_events_reach_their_own_window() {
+        let mut world = World::new();
+        let windows = open_windows(&mut world, 2);
+        let (first, first_request) = &windows[0];
+        let (second, _) = &windows[1];
+        first_request
+            .send_event
+            .send(WindowEvent::Focused(true))
+            .unwrap();
+
+        let first = world.get::<AdwaitaWindow>(*first).unwrap();
+        assert!(matches!(
+            first.recv_event.try_recv(),
+            Ok(WindowEvent::Focused(true))
+        ));
+        let second = world.get::<AdwaitaWindow>(*second).unwrap();
+        assert!(second.recv_event.try_recv().is_err());
+    }
+
+    #[test]
+    fn windows_get_their_own_render_targets() {
+        let mut world = World::new();
+        let windows = open_windows(&mut world, 2);
+        let handles = windows
+            .iter()
+            .map(|(entity, _)| {
+                world
+                    .get::<AdwaitaWindow>(*entity)
+                    .unwrap()
+                    .render_target_handle
+            })
+            .collect::<Vec<_>>();
+        assert_ne!(handles[0], handles[1]);
+    }
+
+    #[test]
+    fn closed_window_drops_its_event_sender() {
+        let mut world = World::new();
+        let mut windows = open_windows(&mut world, 2);
+        let (entity, request) = windows.remove(0);
+        // the GTK window is gone once its state is dropped
+        drop(request);
+
+        let window = world.get::<AdwaitaWindow>(entity).unwrap();
+        assert!(matches!(
+            window.recv_event.try_recv(),
+            Err(flume::TryRecvError::Disconnected)
+        ));
+        assert_eq!(Arc::strong_count(&window.commands), 1);
+    }
+
+    /// Opens `count` windows with a real render device, so that [`poll_windows`] can
+    /// allocate their render targets.
+    ///
+    /// Returns [`None`] if no Vulkan adapter can share render targets, since
+    /// render targets can only be allocated on a real device.
+    fn open_windows_with_device(count: usize) -> Option<(World, Vec<(Entity, WindowOpen)>)> {
+        let Ok(RenderCreation::Manual(render_device, ..)) =
+            render::create_renderer(WgpuSettings::default(), AdwaitaRenderConfig::default())
+        else {
+            eprintln!("No Vulkan adapter can share render targets, skipping");
+            return None;
+        };
+
+        let mut main_world = World::new();
+        let windows = open_windows(&mut main_world, count);
+        main_world.insert_resource(render_device);
+        main_world.insert_resource(SharedDisplay(SharedDisplayInfo::default()));
+        main_world.init_resource::<AdwaitaCloseBehavior>();
+        main_world.init_resource::<Events<AdwaitaWindowClosed>>();
+        main_world.init_resource::<Events<AdwaitaCloseRequested>>();
+        main_world.init_resource::<Events<WindowCloseRequested>>();
+        main_world.init_resource::<Events<WindowResized>>();
+        Some((main_world, windows))
+    }
+
+    /// Makes the GTK side of each window report the logical size and scale factor at
+    /// the same index of `sizes`.
+    fn resize_windows(windows: &[(Entity, WindowOpen)], sizes: &[(UVec2, f32)]) {
+        for ((_, request), (size, scale_factor)) in windows.iter().zip(sizes) {
+            request
+                .render_target_width
+                .store(size.x as i32, Ordering::SeqCst);
+            request
+                .render_target_height
+                .store(size.y as i32, Ordering::SeqCst);
+            request
+                .scale_factor
+                .store(scale_factor.to_bits(), Ordering::SeqCst);
+        }
+    }
+
+    /// Runs an extract `system` in `render_world`, reading from `main_world`.
+    fn run_extract<M>(
+        main_world: &mut World,
+        render_world: &mut World,
+        system: impl IntoSystem<(), (), M>,
+    ) {
+        if !render_world.contains_resource::<MainWorld>() {
+            render_world.init_resource::<MainWorld>();
+        }
+        std::mem::swap(&mut **render_world.resource_mut::<MainWorld>(), main_world);
+        render_world.run_system_once(system);
+        std::mem::swap(&mut **render_world.resource_mut::<MainWorld>(), main_world);
+    }
+
+    #[test]
+    fn windows_get_frames_of_their_own_size() {
+        let Some((mut main_world, windows)) = open_windows_with_device(2) else {
+            return;
+        };
+        resize_windows(
+            &windows,
+            &[(UVec2::new(200, 100), 1.0), (UVec2::new(320, 240), 2.0)],
+        );
+        main_world.run_system_once(poll_windows);
+        let mut render_world = World::new();
+        run_extract(&mut main_world, &mut render_world, extract_windows);
+        let entities = windows
+            .iter()
+            .map(|(entity, _)| *entity)
+            .collect::<Vec<_>>();
+
+        let mut render_windows = render_world.query::<&RenderWindow>();
+        let render_windows = render_windows.iter(&render_world).collect::<Vec<_>>();
+        assert_eq!(render_windows.len(), 2);
+        for (entity, expected_size) in entities
+            .iter()
+            .zip([UVec2::new(200, 100), UVec2::new(640, 480)])
+        {
+            let window = main_world.get::<AdwaitaWindow>(*entity).unwrap();
+            assert_eq!(window.last_render_target_size, expected_size);
+            assert_eq!(
+                window.render_target.as_ref().map(|ring| ring.size),
+                Some(expected_size)
+            );
+
+            // each window's frame is extracted for that window only
+            let render_window = render_windows
+                .iter()
+                .find(|render_window| {
+                    render_window.render_target_handle == window.render_target_handle
+                })
+                .expect("window wasn't extracted");
+            assert!(Arc::ptr_eq(
+                &render_window.next_frame_to_render,
+                &window.next_frame_to_render
+            ));
+            assert!(Arc::ptr_eq(
+                &render_window.shared_next_frame,
+                &window.shared_next_frame
+            ));
+            let frame = render_window
+                .next_frame_to_send
+                .as_ref()
+                .expect("no frame was extracted");
+            assert_eq!(frame.dmabuf.visible_size, expected_size);
+            assert_eq!(frame.dmabuf.size, expected_size);
+            // the frame was moved out, not copied
+            assert!(window.next_frame_to_render.take(Ordering::SeqCst).is_none());
+        }
+    }
+
+    #[test]
+    fn camera_depth_textures_match_render_target() {
+        let Some((mut main_world, windows)) = open_windows_with_device(1) else {
+            return;
+        };
+        main_world.init_resource::<Events<WindowCreated>>();
+        main_world.init_resource::<Events<WindowScaleFactorChanged>>();
+        main_world.init_resource::<Events<AssetEvent<Image>>>();
+        main_world.init_resource::<Assets<Image>>();
+        let window = main_world.get::<AdwaitaWindow>(windows[0].0).unwrap();
+        let handle = window.render_target_handle;
+        let camera = Camera {
+            target: window.render_target(),
+            ..default()
+        };
+        let camera = main_world
+            .spawn(Camera3dBundle {
+                camera,
+                ..default()
+            })
+            .id();
+
+        let mut render_world = World::new();
+        render_world.insert_resource(main_world.resource::<RenderDevice>().clone());
+        render_world.insert_resource(GpuPreprocessingSupport::None);
+        render_world.insert_resource(Msaa::Off);
+        render_world.init_resource::<TextureCache>();
+        render_world.init_resource::<ViewBinnedRenderPhases<Opaque3d>>();
+        render_world.init_resource::<ViewBinnedRenderPhases<AlphaMask3d>>();
+        render_world.init_resource::<ViewSortedRenderPhases<Transmissive3d>>();
+        render_world.init_resource::<ViewSortedRenderPhases<Transparent3d>>();
+
+        // the depth texture follows the render target when the ring is resized
+        for size in [UVec2::new(200, 100), UVec2::new(640, 480)] {
+            resize_windows(&windows, &[(size, 1.0)]);
+            main_world.run_system_once(poll_windows);
+            main_world.run_system_once(camera_system::<Projection>);
+            let view_size = main_world.resource::<ManualTextureViews>()[&handle].size;
+            assert_eq!(view_size, size);
+
+            run_extract(&mut main_world, &mut render_world, extract_cameras);
+            run_extract(
+                &mut main_world,
+                &mut render_world,
+                extract_core_3d_camera_phases,
+            );
+            // as extracted by `ExtractComponentPlugin<Camera3d>`
+            render_world.entity_mut(camera).insert(Camera3d::default());
+            render_world.run_system_once(prepare_core_3d_depth_textures);
+
+            let depth = render_world.get::<ViewDepthTexture>(camera).unwrap();
+            let depth_size = depth.texture.size();
+            assert_eq!(UVec2::new(depth_size.width, depth_size.height), view_size);
+        }
+    }
+}
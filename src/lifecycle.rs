@@ -0,0 +1,48 @@
+use bevy::{prelude::*, window::AppLifecycle};
+
+use crate::AdwaitaWindow;
+
+/// Sends [`AppLifecycle`] events based on whether GTK has suspended the open windows.
+///
+/// GTK suspends a window when it is minimized or otherwise not visible to the user,
+/// depending on what the compositor reports. The app is considered suspended once
+/// all of its windows are suspended:
+///
+/// | Adwaita windows                     | Events sent                        |
+/// |-------------------------------------|------------------------------------|
+/// | First seen, any not suspended       | [`Running`]                        |
+/// | First seen, all suspended           | [`Suspended`]                      |
+/// | All windows become suspended        | [`WillSuspend`], then [`Suspended`] |
+/// | Any window stops being suspended    | [`WillResume`], then [`Running`]    |
+///
+/// Since Adwaita windows keep rendering while suspended, nothing else about the
+/// app changes.
+///
+/// [`Running`]: AppLifecycle::Running
+/// [`Suspended`]: AppLifecycle::Suspended
+/// [`WillSuspend`]: AppLifecycle::WillSuspend
+/// [`WillResume`]: AppLifecycle::WillResume
+pub(crate) fn emit_app_lifecycle(
+    windows: Query<&AdwaitaWindow>,
+    mut last_suspended: Local<Option<bool>>,
+    mut lifecycle_events: EventWriter<AppLifecycle>,
+) {
+    if windows.is_empty() {
+        return;
+    }
+    let suspended = windows.iter().all(AdwaitaWindow::is_suspended);
+
+    let events: &[AppLifecycle] = match (*last_suspended, suspended) {
+        (None, false) => &[AppLifecycle::Running],
+        (None, true) => &[AppLifecycle::Suspended],
+        (Some(false), true) => &[AppLifecycle::WillSuspend, AppLifecycle::Suspended],
+        (Some(true), false) => &[AppLifecycle::WillResume, AppLifecycle::Running],
+        (Some(_), _) => &[],
+    };
+    *last_suspended = Some(suspended);
+
+    for event in events {
+        debug!("Sending app lifecycle event {event:?}");
+        lifecycle_events.send(*event);
+    }
+}
@@ -0,0 +1,108 @@
+use std::sync::{Arc, Mutex};
+
+use adw::gdk;
+use adw::prelude::*;
+use bevy::prelude::*;
+
+use crate::adwaita_app::WindowEvent;
+
+/// Monitor that an [`AdwaitaWindow`] is currently shown on.
+///
+/// See [`AdwaitaWindow::monitor`].
+///
+/// [`AdwaitaWindow`]: crate::AdwaitaWindow
+/// [`AdwaitaWindow::monitor`]: crate::AdwaitaWindow::monitor
+#[derive(Debug, Clone, PartialEq, Reflect)]
+pub struct AdwaitaMonitorInfo {
+    /// Area of the monitor in the coordinate space of the display, in logical
+    /// pixels.
+    ///
+    /// This covers the whole monitor, including any panels or docks, since GTK 4
+    /// doesn't expose the work area. On Wayland, the position is usually relative to
+    /// the other monitors, but may also always be at the origin.
+    pub geometry: IRect,
+    /// Scale factor of the monitor, which may be fractional, such as 1.25.
+    pub scale: f32,
+    /// Name of the connector that the monitor is plugged into, such as `DP-1`, if known.
+    pub connector: Option<String>,
+    /// Refresh rate of the monitor in Hz, or [`None`] if the compositor doesn't
+    /// report it.
+    pub refresh_rate: Option<f32>,
+    /// Whether variable refresh rate (adaptive sync) is active on the monitor, or
+    /// [`None`] if unknown.
+    ///
+    /// Neither GTK nor the Wayland and X11 protocols currently let clients query
+    /// this, so this is always [`None`] for now.
+    pub variable_refresh: Option<bool>,
+    /// Range of refresh rates in Hz that the monitor can switch between while
+    /// variable refresh rate is active, or [`None`] if unknown.
+    ///
+    /// Like [`variable_refresh`](Self::variable_refresh), this isn't reported by any
+    /// compositor yet.
+    pub variable_refresh_range: Option<(f32, f32)>,
+}
+
+impl AdwaitaMonitorInfo {
+    fn new(monitor: &gdk::Monitor) -> Self {
+        // GDK reports the refresh rate in mHz, or 0 if unknown
+        let refresh_rate = monitor.refresh_rate();
+        let geometry = monitor.geometry();
+        Self {
+            geometry: IRect::new(
+                geometry.x(),
+                geometry.y(),
+                geometry.x() + geometry.width(),
+                geometry.y() + geometry.height(),
+            ),
+            scale: monitor.scale() as f32,
+            connector: monitor.connector().map(String::from),
+            refresh_rate: (refresh_rate > 0).then(|| refresh_rate as f32 / 1000.0),
+            variable_refresh: None,
+            variable_refresh_range: None,
+        }
+    }
+}
+
+pub(crate) type SharedMonitorInfo = Arc<Mutex<Option<AdwaitaMonitorInfo>>>;
+
+/// Sent when an [`AdwaitaWindow`] moves onto a different monitor, including when
+/// it's first shown.
+///
+/// [`AdwaitaWindow`]: crate::AdwaitaWindow
+#[derive(Debug, Clone, PartialEq, Event, Reflect)]
+pub struct AdwaitaMonitorChanged {
+    /// Entity of the [`AdwaitaWindow`].
+    ///
+    /// [`AdwaitaWindow`]: crate::AdwaitaWindow
+    pub window: Entity,
+    pub monitor: AdwaitaMonitorInfo,
+}
+
+/// Keeps `shared` up to date with the monitor that `window` is shown on, sending
+/// a [`WindowEvent::MonitorChanged`] whenever it changes.
+pub(crate) fn watch_monitor(
+    window: &adw::Window,
+    shared: &SharedMonitorInfo,
+    send_event: &flume::Sender<WindowEvent>,
+) {
+    // the surface only exists once the window is realized
+    window.connect_realize({
+        let shared = shared.clone();
+        let send_event = send_event.clone();
+        move |window| {
+            let Some(surface) = window.surface() else {
+                return;
+            };
+            // if the window spans several monitors, report the last one it entered
+            surface.connect_enter_monitor({
+                let shared = shared.clone();
+                let send_event = send_event.clone();
+                move |_, monitor| {
+                    let info = AdwaitaMonitorInfo::new(monitor);
+                    *shared.lock().expect("poisoned") = Some(info.clone());
+                    _ = send_event.send(WindowEvent::MonitorChanged(info));
+                }
+            });
+        }
+    });
+}
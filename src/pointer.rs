@@ -0,0 +1,148 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use adw::prelude::*;
+use bevy::{
+    input::{mouse::MouseButton, ButtonState},
+    math::Vec2,
+};
+use gtk::{gdk, glib};
+
+use crate::adwaita_app::WindowEvent;
+use crate::cursor;
+use crate::grab::{self, PointerMotion, SharedPointerMotion};
+
+/// Adds pointer controllers to `widget`, which send [`WindowEvent::CursorMoved`]s
+/// and [`WindowEvent::MouseButton`]s.
+///
+/// Positions are sent in logical coordinates of `widget`, since only the app knows
+/// which part of the widget its frame is drawn in. While the cursor is grabbed,
+/// [`WindowEvent::MouseMotion`]s are also sent as set by `pointer_motion`.
+pub(crate) fn attach_pointer(
+    widget: &impl IsA<gtk::Widget>,
+    send_event: &flume::Sender<WindowEvent>,
+    pointer_motion: &SharedPointerMotion,
+) {
+    let last_position = Rc::new(Cell::new(None));
+    let motion = gtk::EventControllerMotion::new();
+    motion.connect_enter({
+        let send_event = send_event.clone();
+        let pointer_motion = pointer_motion.clone();
+        let last_position = last_position.clone();
+        move |controller, x, y| {
+            _ = send_event.send(WindowEvent::CursorEntered);
+            on_motion(
+                controller,
+                &send_event,
+                pointer_motion.get(),
+                &last_position,
+                x,
+                y,
+            );
+        }
+    });
+    motion.connect_motion({
+        let send_event = send_event.clone();
+        let pointer_motion = pointer_motion.clone();
+        let last_position = last_position.clone();
+        move |controller, x, y| {
+            on_motion(
+                controller,
+                &send_event,
+                pointer_motion.get(),
+                &last_position,
+                x,
+                y,
+            );
+        }
+    });
+    motion.connect_leave({
+        let send_event = send_event.clone();
+        let pointer_motion = pointer_motion.clone();
+        move |controller| {
+            _ = send_event.send(WindowEvent::CursorLeft);
+            let position = last_position.take();
+            if pointer_motion.get() == PointerMotion::Confined {
+                if let (Some(widget), Some(position)) = (controller.widget(), position) {
+                    cursor::warp_in_widget(&widget, position);
+                }
+            }
+        }
+    });
+    widget.add_controller(motion);
+
+    // `GestureClick` only follows one button at a time, and gives up on a press
+    // once the pointer moves, so we'd miss releases after dragging
+    let buttons = gtk::EventControllerLegacy::new();
+    buttons.connect_event({
+        let send_event = send_event.clone();
+        move |_, event| {
+            let state = match event.event_type() {
+                gdk::EventType::ButtonPress => ButtonState::Pressed,
+                gdk::EventType::ButtonRelease => ButtonState::Released,
+                _ => return glib::Propagation::Proceed,
+            };
+            if let Some(event) = event.downcast_ref::<gdk::ButtonEvent>() {
+                _ = send_event.send(WindowEvent::MouseButton {
+                    button: mouse_button(event.button()),
+                    state,
+                });
+            }
+            // gestures on the same widget still need to see the buttons
+            glib::Propagation::Proceed
+        }
+    });
+    widget.add_controller(buttons);
+}
+
+fn on_motion(
+    controller: &gtk::EventControllerMotion,
+    send_event: &flume::Sender<WindowEvent>,
+    pointer_motion: PointerMotion,
+    last_position: &Cell<Option<Vec2>>,
+    x: f64,
+    y: f64,
+) {
+    let Some(widget) = controller.widget() else {
+        return;
+    };
+    let position = Vec2::new(x as f32, y as f32);
+    let widget_size = Vec2::new(widget.width() as f32, widget.height() as f32);
+    match pointer_motion {
+        PointerMotion::Absolute => {}
+        PointerMotion::Relative | PointerMotion::Confined => {
+            if let Some(last_position) = last_position.get() {
+                grab::send_mouse_motion(&widget, send_event, position - last_position);
+            }
+        }
+        PointerMotion::Recentered => {
+            // warping moves the pointer to the nearest device pixel, which reports a
+            // motion of its own
+            let scale = widget.scale_factor() as f32;
+            let center = (widget_size * scale / 2.0).round() / scale;
+            let delta = position - center;
+            if delta.abs().max_element() > 0.01 {
+                grab::send_mouse_motion(&widget, send_event, delta);
+                cursor::warp_in_widget(&widget, center);
+            }
+            return;
+        }
+    }
+    last_position.set(Some(position));
+    _ = send_event.send(WindowEvent::CursorMoved {
+        position,
+        widget_size,
+    });
+}
+
+fn mouse_button(button: u32) -> MouseButton {
+    // GDK uses the X11 button numbering on every backend
+    match button {
+        gdk::BUTTON_PRIMARY => MouseButton::Left,
+        gdk::BUTTON_MIDDLE => MouseButton::Middle,
+        gdk::BUTTON_SECONDARY => MouseButton::Right,
+        8 => MouseButton::Back,
+        9 => MouseButton::Forward,
+        n => MouseButton::Other(u16::try_from(n).unwrap_or(u16::MAX)),
+    }
+}
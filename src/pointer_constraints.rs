@@ -0,0 +1,247 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use adw::prelude::*;
+use adw::{glib, gtk};
+use bevy::{math::Vec2, window::CursorGrabMode};
+use wayland_client::{
+    globals::{registry_queue_init, BindError, GlobalError, GlobalListContents},
+    protocol::{wl_pointer::WlPointer, wl_registry::WlRegistry, wl_surface::WlSurface},
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle,
+};
+use wayland_protocols::wp::{
+    pointer_constraints::zv1::client::{
+        zwp_confined_pointer_v1::ZwpConfinedPointerV1,
+        zwp_locked_pointer_v1::ZwpLockedPointerV1,
+        zwp_pointer_constraints_v1::{Lifetime, ZwpPointerConstraintsV1},
+    },
+    relative_pointer::zv1::client::{
+        zwp_relative_pointer_manager_v1::ZwpRelativePointerManagerV1,
+        zwp_relative_pointer_v1::{self, ZwpRelativePointerV1},
+    },
+};
+
+use crate::adwaita_app::WindowEvent;
+use crate::{grab, wayland};
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum ConstraintsError {
+    #[error("failed to list Wayland globals")]
+    Globals(#[from] GlobalError),
+    #[error("compositor doesn't support `{0}`")]
+    Unsupported(&'static str, #[source] BindError),
+    #[error("window has no Wayland surface, or seat has no pointer")]
+    InvalidObject,
+}
+
+/// Locks or confines the pointer with the `zwp_pointer_constraints_v1` protocol,
+/// sending motion from `zwp_relative_pointer_v1` as [`WindowEvent::MouseMotion`]s.
+///
+/// This runs on GDK's own Wayland connection, with a separate event queue. GDK reads
+/// every event from the connection, so the queue is dispatched on every frame
+/// clock tick of the window while a constraint is active.
+#[derive(Debug)]
+pub(crate) struct PointerConstraints {
+    window: adw::Window,
+    queue: Rc<RefCell<Queue>>,
+    surface: WlSurface,
+    pointer: WlPointer,
+    constraints: ZwpPointerConstraintsV1,
+    relative_pointers: ZwpRelativePointerManagerV1,
+    active: Option<ActiveConstraint>,
+}
+
+#[derive(Debug)]
+struct Queue {
+    queue: EventQueue<State>,
+    state: State,
+}
+
+#[derive(Debug)]
+struct State {
+    window: glib::WeakRef<adw::Window>,
+    send_event: flume::Sender<WindowEvent>,
+}
+
+#[derive(Debug)]
+struct ActiveConstraint {
+    constraint: Constraint,
+    relative_pointer: ZwpRelativePointerV1,
+    tick: gtk::TickCallbackId,
+}
+
+#[derive(Debug)]
+enum Constraint {
+    Locked(ZwpLockedPointerV1),
+    Confined(ZwpConfinedPointerV1),
+}
+
+impl PointerConstraints {
+    /// Binds the protocols for `window`, or returns [`None`] if it's not on Wayland.
+    ///
+    /// The window must be realized.
+    pub fn new(
+        window: &adw::Window,
+        send_event: &flume::Sender<WindowEvent>,
+    ) -> Result<Option<Self>, ConstraintsError> {
+        let display = WidgetExt::display(window);
+        let Some(conn) = wayland::connection(&display) else {
+            return Ok(None);
+        };
+        let surface = window
+            .surface()
+            .and_then(|surface| wayland::surface(&conn, &surface))
+            .ok_or(ConstraintsError::InvalidObject)?;
+        let pointer = display
+            .default_seat()
+            .and_then(|seat| seat.pointer())
+            .and_then(|device| wayland::pointer(&conn, &device))
+            .ok_or(ConstraintsError::InvalidObject)?;
+
+        let (globals, queue) = registry_queue_init::<State>(&conn)?;
+        let qh = queue.handle();
+        let constraints = globals
+            .bind(&qh, 1..=1, ())
+            .map_err(|err| ConstraintsError::Unsupported("zwp_pointer_constraints_v1", err))?;
+        let relative_pointers = globals
+            .bind(&qh, 1..=1, ())
+            .map_err(|err| ConstraintsError::Unsupported("zwp_relative_pointer_manager_v1", err))?;
+
+        Ok(Some(Self {
+            window: window.clone(),
+            queue: Rc::new(RefCell::new(Queue {
+                queue,
+                state: State {
+                    window: window.downgrade(),
+                    send_event: send_event.clone(),
+                },
+            })),
+            surface,
+            pointer,
+            constraints,
+            relative_pointers,
+            active: None,
+        }))
+    }
+
+    /// Replaces the active constraint with one for `mode`.
+    pub fn constrain(&mut self, mode: CursorGrabMode) {
+        self.release();
+        let qh = self.queue.borrow().queue.handle();
+        // the constraint only takes effect while the pointer is over the surface, and
+        // persistent ones come back whenever it returns
+        let constraint = match mode {
+            CursorGrabMode::None => return,
+            CursorGrabMode::Locked => Constraint::Locked(self.constraints.lock_pointer(
+                &self.surface,
+                &self.pointer,
+                None,
+                Lifetime::Persistent,
+                &qh,
+                (),
+            )),
+            CursorGrabMode::Confined => Constraint::Confined(self.constraints.confine_pointer(
+                &self.surface,
+                &self.pointer,
+                None,
+                Lifetime::Persistent,
+                &qh,
+                (),
+            )),
+        };
+        let relative_pointer = self
+            .relative_pointers
+            .get_relative_pointer(&self.pointer, &qh, ());
+        let tick = self.window.add_tick_callback({
+            let queue = self.queue.clone();
+            move |_, _| {
+                let Queue { queue, state } = &mut *queue.borrow_mut();
+                if let Err(err) = queue.dispatch_pending(state) {
+                    tracing::warn!("Failed to dispatch pointer constraint events: {err}");
+                }
+                glib::ControlFlow::Continue
+            }
+        });
+        self.active = Some(ActiveConstraint {
+            constraint,
+            relative_pointer,
+            tick,
+        });
+        self.flush();
+    }
+
+    /// Releases the active constraint, if any.
+    pub fn release(&mut self) {
+        let Some(active) = self.active.take() else {
+            return;
+        };
+        match active.constraint {
+            Constraint::Locked(locked) => locked.destroy(),
+            Constraint::Confined(confined) => confined.destroy(),
+        }
+        active.relative_pointer.destroy();
+        active.tick.remove();
+        self.flush();
+    }
+
+    fn flush(&self) {
+        if let Err(err) = self.queue.borrow().queue.flush() {
+            tracing::warn!("Failed to flush pointer constraint requests: {err}");
+        }
+    }
+}
+
+impl Drop for PointerConstraints {
+    fn drop(&mut self) {
+        self.release();
+        self.constraints.destroy();
+        self.relative_pointers.destroy();
+        self.flush();
+    }
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for State {
+    fn event(
+        _: &mut Self,
+        _: &WlRegistry,
+        _: <WlRegistry as Proxy>::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpRelativePointerV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZwpRelativePointerV1,
+        event: zwp_relative_pointer_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let zwp_relative_pointer_v1::Event::RelativeMotion {
+            dx_unaccel,
+            dy_unaccel,
+            ..
+        } = event
+        else {
+            return;
+        };
+        let Some(window) = state.window.upgrade() else {
+            return;
+        };
+        // like winit, which sends the motion of the device itself
+        grab::send_mouse_motion(
+            &window,
+            &state.send_event,
+            Vec2::new(dx_unaccel as f32, dy_unaccel as f32),
+        );
+    }
+}
+
+wayland_client::delegate_noop!(State: ZwpPointerConstraintsV1);
+wayland_client::delegate_noop!(State: ZwpRelativePointerManagerV1);
+wayland_client::delegate_noop!(State: ignore ZwpLockedPointerV1);
+wayland_client::delegate_noop!(State: ignore ZwpConfinedPointerV1);
@@ -0,0 +1,97 @@
+use adw::gdk;
+use adw::prelude::*;
+use bevy::math::IVec2;
+
+/// Moves `window` so that its top-left corner is at `position` on the screen.
+///
+/// Only X11 lets clients position their own windows, so this logs a warning
+/// everywhere else.
+pub fn set_position(window: &adw::Window, position: IVec2) {
+    let Some(surface) = window.surface() else {
+        tracing::warn!("Cannot move window, window is not realized yet");
+        return;
+    };
+    if !move_surface(&surface, position) {
+        tracing::warn!(
+            "Cannot move window, this is only supported on X11 with the `x11` feature enabled"
+        );
+    }
+}
+
+#[cfg(feature = "x11")]
+mod x11 {
+    use std::os::raw::{c_int, c_ulong, c_void};
+
+    use adw::gdk;
+    use adw::glib::translate::ToGlibPtr;
+    use adw::prelude::*;
+
+    #[link(name = "X11")]
+    extern "C" {
+        pub fn XMoveWindow(display: *mut c_void, w: c_ulong, x: c_int, y: c_int) -> c_int;
+        pub fn XTranslateCoordinates(
+            display: *mut c_void,
+            src_w: c_ulong,
+            dest_w: c_ulong,
+            src_x: c_int,
+            src_y: c_int,
+            dest_x: *mut c_int,
+            dest_y: *mut c_int,
+            child: *mut c_ulong,
+        ) -> c_int;
+        pub fn XDefaultRootWindow(display: *mut c_void) -> c_ulong;
+        pub fn XFlush(display: *mut c_void) -> c_int;
+    }
+
+    /// Xlib display and window of `surface`, if it's an X11 surface.
+    pub fn xwindow(surface: &gdk::Surface) -> Option<(*mut c_void, c_ulong)> {
+        let x11_surface = surface.downcast_ref::<gdk4_x11::X11Surface>()?;
+        let display = x11_surface
+            .display()
+            .downcast::<gdk4_x11::X11Display>()
+            .ok()?;
+        // SAFETY: the display is owned by GDK, and stays alive as long as the surface
+        let xdisplay = unsafe {
+            gdk4_x11::ffi::gdk_x11_display_get_xdisplay(display.to_glib_none().0).cast::<c_void>()
+        };
+        Some((xdisplay, x11_surface.xid()))
+    }
+}
+
+#[cfg(feature = "x11")]
+fn move_surface(surface: &gdk::Surface, position: IVec2) -> bool {
+    let Some((xdisplay, xid)) = x11::xwindow(surface) else {
+        return false;
+    };
+    // SAFETY: the display and window are owned by GDK, and stay alive for the
+    // duration of this call
+    unsafe {
+        x11::XMoveWindow(xdisplay, xid, position.x, position.y);
+        x11::XFlush(xdisplay);
+    }
+    true
+}
+
+#[cfg(not(feature = "x11"))]
+fn move_surface(_surface: &gdk::Surface, _position: IVec2) -> bool {
+    false
+}
+
+/// Position of the top-left corner of `surface` on the screen, if the windowing
+/// system tells us.
+#[cfg(feature = "x11")]
+pub fn surface_position(surface: &gdk::Surface) -> Option<IVec2> {
+    let (xdisplay, xid) = x11::xwindow(surface)?;
+    let (mut x, mut y, mut child) = (0, 0, 0);
+    // SAFETY: see `move_surface`
+    let translated = unsafe {
+        let root = x11::XDefaultRootWindow(xdisplay);
+        x11::XTranslateCoordinates(xdisplay, xid, root, 0, 0, &mut x, &mut y, &mut child)
+    };
+    (translated != 0).then_some(IVec2::new(x, y))
+}
+
+#[cfg(not(feature = "x11"))]
+pub fn surface_position(_surface: &gdk::Surface) -> Option<IVec2> {
+    None
+}
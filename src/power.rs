@@ -0,0 +1,105 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+use adw::prelude::*;
+use adw::{gio, glib};
+use bevy::prelude::*;
+
+/// Power state of the system, as reported by UPower.
+///
+/// This is updated whenever the system switches between battery and AC power, or
+/// the battery level changes, so that apps can lower their frame rate or quality
+/// while on battery. If UPower isn't running, this keeps its default value of being
+/// on AC power with no battery.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Resource, Reflect)]
+#[reflect(Resource, Default)]
+pub struct AdwaitaPowerState {
+    /// Whether the system is running on battery power.
+    pub on_battery: bool,
+    /// Charge of the system's batteries combined, from 0 to 1, or [`None`] if the
+    /// system has no battery.
+    pub battery_level: Option<f32>,
+}
+
+/// Power state read on the GTK thread, if it changed since Bevy last read it.
+#[derive(Debug, Clone, Default, Resource, Deref)]
+pub(crate) struct SharedPowerState(Arc<Mutex<Option<AdwaitaPowerState>>>);
+
+const UPOWER_NAME: &str = "org.freedesktop.UPower";
+const UPOWER_PATH: &str = "/org/freedesktop/UPower";
+// aggregate of all batteries, which UPower provides for exactly this purpose
+const DISPLAY_DEVICE_PATH: &str = "/org/freedesktop/UPower/devices/DisplayDevice";
+const DEVICE_INTERFACE: &str = "org.freedesktop.UPower.Device";
+
+/// Must be called on the GTK thread.
+pub(crate) fn watch_power_state(shared: SharedPowerState) {
+    let state = Rc::new(Cell::new(AdwaitaPowerState::default()));
+    let update = move |f: &dyn Fn(&mut AdwaitaPowerState)| {
+        let mut new_state = state.get();
+        f(&mut new_state);
+        state.set(new_state);
+        *shared.lock().expect("poisoned") = Some(new_state);
+    };
+    let update = Rc::new(update);
+
+    watch_proxy(UPOWER_PATH, UPOWER_NAME, {
+        let update = update.clone();
+        move |proxy| {
+            let on_battery = property::<bool>(proxy, "OnBattery").unwrap_or(false);
+            update(&|state| state.on_battery = on_battery);
+        }
+    });
+    watch_proxy(DISPLAY_DEVICE_PATH, DEVICE_INTERFACE, move |proxy| {
+        let battery_level = property::<bool>(proxy, "IsPresent")
+            .unwrap_or(false)
+            .then(|| property::<f64>(proxy, "Percentage"))
+            .flatten()
+            .map(|percentage| (percentage / 100.0).clamp(0.0, 1.0) as f32);
+        update(&|state| state.battery_level = battery_level);
+    });
+}
+
+fn watch_proxy(path: &'static str, interface: &str, update: impl Fn(&gio::DBusProxy) + 'static) {
+    gio::DBusProxy::for_bus(
+        gio::BusType::System,
+        gio::DBusProxyFlags::DO_NOT_CONNECT_SIGNALS,
+        None,
+        UPOWER_NAME,
+        path,
+        interface,
+        None::<&gio::Cancellable>,
+        move |result| {
+            let proxy = match result {
+                Ok(proxy) => proxy,
+                Err(err) => {
+                    tracing::debug!(
+                        "Failed to connect to UPower at {path}, power state won't be reported: {err}"
+                    );
+                    return;
+                }
+            };
+            update(&proxy);
+            // the handler keeps the proxy alive, so we keep watching for the
+            // rest of the process
+            let proxy_ref = proxy.clone();
+            proxy.connect_local("g-properties-changed", false, move |_| {
+                update(&proxy_ref);
+                None
+            });
+        },
+    );
+}
+
+fn property<T: glib::variant::FromVariant>(proxy: &gio::DBusProxy, name: &str) -> Option<T> {
+    proxy.cached_property(name)?.get()
+}
+
+pub(crate) fn sync_power_state(
+    shared: Res<SharedPowerState>,
+    mut power_state: ResMut<AdwaitaPowerState>,
+) {
+    if let Some(state) = shared.lock().expect("poisoned").take() {
+        *power_state = state;
+    }
+}
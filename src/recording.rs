@@ -0,0 +1,165 @@
+use std::{os::fd::BorrowedFd, path::Path, time::Instant};
+
+use bevy::math::UVec2;
+use gst::prelude::*;
+
+use crate::render::{self, AdwaitaRenderTargetFormat, DmabufInfo};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RecordingError {
+    #[error("failed to initialize GStreamer")]
+    Init(#[source] gst::glib::Error),
+    #[error("failed to create pipeline")]
+    Pipeline(#[from] gst::glib::BoolError),
+    #[error("failed to change pipeline state")]
+    StateChange(#[from] gst::StateChangeError),
+    #[error("failed to duplicate dmabuf fd")]
+    DupFd(#[source] std::io::Error),
+    #[error("failed to push frame")]
+    Flow(#[from] gst::FlowError),
+    #[error("render target format {0:?} can't be recorded")]
+    UnsupportedFormat(AdwaitaRenderTargetFormat),
+    #[error("render target with modifier {0:#x} can't be recorded, only linear ones can")]
+    UnsupportedModifier(u64),
+}
+
+/// Encodes the frames of a window into an H.264 Matroska file.
+#[derive(Debug)]
+pub struct Recording {
+    pipeline: gst::Pipeline,
+    appsrc: gst_app::AppSrc,
+    size_filter: gst::Element,
+    allocator: gst_allocators::DmaBufAllocator,
+    start: Instant,
+    size: Option<UVec2>,
+}
+
+impl Recording {
+    pub fn start(path: &Path) -> Result<Self, RecordingError> {
+        gst::init().map_err(RecordingError::Init)?;
+
+        let appsrc = gst_app::AppSrc::builder()
+            .is_live(true)
+            .format(gst::Format::Time)
+            .build();
+        // the output keeps the size of the first frame, and later frames are scaled to it
+        let size_filter = gst::ElementFactory::make("capsfilter").build()?;
+        let sink = gst::ElementFactory::make("filesink")
+            .property("location", path.to_string_lossy().as_ref())
+            .build()?;
+        let elements = [
+            appsrc.upcast_ref(),
+            &gst::ElementFactory::make("videoconvert").build()?,
+            &gst::ElementFactory::make("videoscale").build()?,
+            &size_filter,
+            &gst::ElementFactory::make("x264enc")
+                .property_from_str("tune", "zerolatency")
+                .build()?,
+            &gst::ElementFactory::make("matroskamux").build()?,
+            &sink,
+        ];
+
+        let pipeline = gst::Pipeline::new();
+        pipeline.add_many(elements)?;
+        gst::Element::link_many(elements)?;
+        pipeline.set_state(gst::State::Playing)?;
+
+        Ok(Self {
+            pipeline,
+            appsrc,
+            size_filter,
+            allocator: gst_allocators::DmaBufAllocator::new(),
+            start: Instant::now(),
+            size: None,
+        })
+    }
+
+    pub fn push_frame(&mut self, info: &DmabufInfo) -> Result<(), RecordingError> {
+        let &DmabufInfo {
+            size,
+            visible_size,
+            format,
+            fd,
+            layout,
+            // the encoder has no alpha channel either way
+            transparent: _,
+        } = info;
+        // GStreamer would read tiled memory as if it were linear
+        if layout.modifier != render::DMABUF_MODIFIER {
+            return Err(RecordingError::UnsupportedModifier(layout.modifier));
+        }
+        let video_format = match format {
+            AdwaitaRenderTargetFormat::Srgb | AdwaitaRenderTargetFormat::Linear => {
+                gst_video::VideoFormat::Rgbx
+            }
+            AdwaitaRenderTargetFormat::BgraSrgb => gst_video::VideoFormat::Bgrx,
+            AdwaitaRenderTargetFormat::Float16 => {
+                return Err(RecordingError::UnsupportedFormat(format));
+            }
+        };
+
+        if self.size != Some(visible_size) {
+            let caps = gst_video::VideoInfo::builder(video_format, visible_size.x, visible_size.y)
+                .build()?
+                .to_caps()?;
+            self.appsrc.set_caps(Some(&caps));
+            if self.size.is_none() {
+                self.size_filter.set_property(
+                    "caps",
+                    gst::Caps::builder("video/x-raw")
+                        .field("width", visible_size.x as i32)
+                        .field("height", visible_size.y as i32)
+                        .build(),
+                );
+            }
+            self.size = Some(visible_size);
+        }
+
+        // GStreamer takes ownership of the fd, and the memory stays alive for as long
+        // as the fd does, even if the render target is dropped in the meantime
+        let fd = unsafe { BorrowedFd::borrow_raw(fd) }
+            .try_clone_to_owned()
+            .map_err(RecordingError::DupFd)?;
+        let plane = layout.planes[0];
+        let memory = unsafe {
+            self.allocator.alloc(
+                fd,
+                plane.offset as usize + plane.stride as usize * size.y as usize,
+            )?
+        };
+
+        let mut buffer = gst::Buffer::new();
+        {
+            let buffer = buffer.get_mut().expect("buffer was just created");
+            buffer.append_memory(memory);
+            buffer.set_pts(gst::ClockTime::from_nseconds(
+                self.start.elapsed().as_nanos() as u64,
+            ));
+            gst_video::VideoMeta::add_full(
+                buffer,
+                gst_video::VideoFrameFlags::empty(),
+                video_format,
+                visible_size.x,
+                visible_size.y,
+                &[plane.offset as usize],
+                &[plane.stride as i32],
+            )?;
+        }
+        self.appsrc.push_buffer(buffer)?;
+        Ok(())
+    }
+}
+
+impl Drop for Recording {
+    // finishes writing the file, blocking until all frames are encoded
+    fn drop(&mut self) {
+        _ = self.appsrc.end_of_stream();
+        if let Some(bus) = self.pipeline.bus() {
+            _ = bus.timed_pop_filtered(
+                gst::ClockTime::from_seconds(5),
+                &[gst::MessageType::Eos, gst::MessageType::Error],
+            );
+        }
+        _ = self.pipeline.set_state(gst::State::Null);
+    }
+}
@@ -1,15 +1,21 @@
 use std::{
-    fs::File,
-    os::{fd::FromRawFd, raw::c_void},
-    sync::Arc,
+    ffi::CStr,
+    fs,
+    os::{
+        fd::{FromRawFd, OwnedFd},
+        raw::c_void,
+    },
+    sync::{atomic::Ordering, Arc, Condvar, Mutex},
+    time::{Duration, Instant},
 };
 
 use ash::vk;
+use atomicbox::AtomicOptionBox;
 use bevy::{
     prelude::*,
     render::{
         camera::ManualTextureView,
-        render_resource::TextureView,
+        render_resource::{Texture, TextureView},
         renderer::{
             RenderAdapter, RenderAdapterInfo, RenderDevice, RenderInstance, RenderQueue,
             WgpuWrapper,
@@ -17,37 +23,487 @@ use bevy::{
         settings::{RenderCreation, WgpuSettings},
     },
 };
-use gtk::{gdk, prelude::Cast};
+use gtk::gdk;
 use wgpu::TextureFormat;
 use wgpu_hal::{vulkan, Instance};
 
-use crate::hal_custom;
+use crate::{hal_custom, AdwaitaRenderConfig};
 
 #[derive(Debug)]
 pub struct DmabufInfo {
+    /// Size that the buffer is allocated at.
     pub size: UVec2,
+    /// Size of the region in the top-left of the buffer which is displayed.
+    pub visible_size: UVec2,
+    pub format: AdwaitaRenderTargetFormat,
+    /// Whether GTK composites the alpha channel, instead of treating the buffer as
+    /// opaque.
+    pub transparent: bool,
     pub fd: i32,
+    pub layout: DmabufLayout,
+}
+
+impl DmabufInfo {
+    /// DRM fourcc that GTK imports the dmabuf as.
+    #[must_use]
+    pub const fn fourcc(&self) -> u32 {
+        self.format.fourcc(self.transparent)
+    }
+}
+
+/// Most memory planes that an image with a DRM format modifier can have.
+pub const MAX_DMABUF_PLANES: usize = 4;
+
+/// How the image behind a dmabuf is laid out in memory, as chosen by the driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DmabufLayout {
+    /// DRM format modifier, describing the tiling and compression of the image.
+    pub modifier: u64,
+    /// All planes are stored in the same dmabuf, and only the first `plane_count`
+    /// are used.
+    pub planes: [DmabufPlane; MAX_DMABUF_PLANES],
+    pub plane_count: usize,
+}
+
+impl DmabufLayout {
+    #[must_use]
+    pub fn planes(&self) -> &[DmabufPlane] {
+        &self.planes[..self.plane_count]
+    }
+}
+
+/// Where a memory plane of a dmabuf starts, in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DmabufPlane {
+    pub offset: u32,
+    /// Bytes per row, which may be more than the width of the image.
+    pub stride: u32,
 }
 
 #[derive(Debug)]
 pub struct FrameInfo {
+    /// Increases with every frame info created for a window, so that an older frame
+    /// never replaces a newer one.
+    pub sequence: u64,
+    pub buffer_id: u64,
     pub dmabuf: DmabufInfo,
-    // just keep this around so that we own the view while we're drawing it
-    // as soon as we drop this, the GPU image + texture + dmabuf is freed
-    pub _texture_view: TextureView,
+    /// The render target itself, for copying out of it.
+    pub texture: Texture,
+    /// Keeps the render target alive while we're drawing it.
+    ///
+    /// Every GTK texture created from this frame holds its own clone, so the
+    /// [`ExportedImage`] is only freed once both we and GTK are done with it.
+    pub texture_view: TextureView,
+    /// Marks the image as in use for as long as this frame may still be drawn, see
+    /// [`RenderTargetRing`].
+    pub lease: Arc<()>,
 }
 
-pub fn create_renderer(settings: WgpuSettings) -> RenderCreation {
-    let do_async = async move {
-        let instance = unsafe {
-            vulkan::Instance::init(&wgpu_hal::InstanceDescriptor {
-                name: "bevy_mod_adwaita", // app name
-                flags: settings.instance_flags,
-                dx12_shader_compiler: settings.dx12_shader_compiler.clone(),
-                gles_minor_version: settings.gles3_minor_version,
+/// Most images that frames of one size rotate between.
+///
+/// GTK shows one image while the next one waits to be picked up, Bevy renders into
+/// a third, and with pipelined rendering the main world already hands out a fourth.
+/// Images are only allocated once all others are in use, so a ring which GTK keeps
+/// up with stays smaller.
+pub const RENDER_TARGET_RING_SIZE: usize = 4;
+
+/// Render target images of one size, which frames rotate between so that Bevy never
+/// renders into an image that GTK may still be reading from.
+#[derive(Debug)]
+pub struct RenderTargetRing {
+    pub size: UVec2,
+    images: Vec<RingImage>,
+    // index of the image that was most recently handed out
+    last: usize,
+}
+
+#[derive(Debug)]
+pub struct RingImage {
+    pub buffer_id: u64,
+    pub target: ExportedRenderTarget,
+    // cloned into every frame info and GTK texture of this image, so the image is
+    // free once the ring holds the only reference
+    lease: Arc<()>,
+}
+
+impl RingImage {
+    pub fn new(buffer_id: u64, target: ExportedRenderTarget) -> Self {
+        Self {
+            buffer_id,
+            target,
+            lease: Arc::new(()),
+        }
+    }
+
+    pub fn frame_info(
+        &self,
+        sequence: u64,
+        visible_size: UVec2,
+        format: AdwaitaRenderTargetFormat,
+        transparent: bool,
+    ) -> FrameInfo {
+        FrameInfo {
+            sequence,
+            buffer_id: self.buffer_id,
+            dmabuf: DmabufInfo {
+                size: self.target.manual_texture_view.size,
+                visible_size,
+                format,
+                transparent,
+                fd: self.target.dmabuf_fd,
+                layout: self.target.layout,
+            },
+            texture: self.target.texture.clone(),
+            texture_view: self.target.manual_texture_view.texture_view.clone(),
+            lease: self.lease.clone(),
+        }
+    }
+}
+
+impl RenderTargetRing {
+    pub const fn new(size: UVec2) -> Self {
+        Self {
+            size,
+            images: Vec::new(),
+            last: 0,
+        }
+    }
+
+    /// Picks the image to render the next frame into, calling `create` with the size
+    /// of the ring if every image is in use and the ring can still grow.
+    pub fn next_image(&mut self, create: impl FnOnce(UVec2) -> RingImage) -> &RingImage {
+        let len = self.images.len();
+        // prefer the least recently used free image
+        let free = (1..=len)
+            .map(|offset| (self.last + offset) % len)
+            .find(|&index| Arc::strong_count(&self.images[index].lease) == 1);
+        let index = match free {
+            Some(index) => index,
+            None if len < RENDER_TARGET_RING_SIZE => {
+                self.images.push(create(self.size));
+                len
+            }
+            None => {
+                debug!("All {len} render target images are in use, reusing the oldest one");
+                (self.last + 1) % len
+            }
+        };
+        self.last = index;
+        &self.images[index]
+    }
+}
+
+/// What to do when the compositor picks up frames slower than Bevy renders them.
+///
+/// GTK only picks up a frame when the compositor is ready for a new one, so a busy
+/// or throttled compositor can fall behind Bevy.
+///
+/// To choose this the same way as a swapchain's present mode, see
+/// [`AdwaitaPresentMode`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[reflect(Default)]
+pub enum AdwaitaFrameBackpressure {
+    /// Keeps rendering, and replaces frames that GTK hasn't picked up yet.
+    ///
+    /// GTK always draws the newest frame, which gives the lowest latency, but the
+    /// replaced frames are wasted GPU time.
+    #[default]
+    DropOldest,
+    /// Skips rendering while GTK hasn't picked up the last frame yet.
+    ///
+    /// The app keeps updating at full rate, but no GPU time is spent on frames that
+    /// are never shown. The frame that GTK draws can be up to a frame older than
+    /// with [`AdwaitaFrameBackpressure::DropOldest`].
+    DropNewest,
+    /// Blocks the render app until GTK has picked up the last frame, which slows the
+    /// whole app down to the rate of the compositor.
+    ///
+    /// GTK stops picking up frames while the window is hidden, so the render app
+    /// waits at most [`THROTTLE_TIMEOUT`] per frame.
+    Throttle,
+}
+
+/// How frames are handed to GTK, in terms of the present modes of a swapchain.
+///
+/// There is no swapchain: GTK picks up frames from Bevy, and the compositor decides
+/// when they reach the screen, never before vblank. Each mode sets the
+/// [`AdwaitaFrameBackpressure`] and when GTK picks up new frames to match the
+/// present mode as closely as possible.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[reflect(Default)]
+pub enum AdwaitaPresentMode {
+    /// Every frame is shown, and the app is slowed down to the rate of the
+    /// compositor.
+    ///
+    /// Frames are picked up on each tick of the window's [`gdk::FrameClock`], with
+    /// [`AdwaitaFrameBackpressure::Throttle`].
+    Fifo,
+    /// The newest frame is shown, and frames rendered in between are dropped.
+    ///
+    /// Frames are picked up on each tick of the window's [`gdk::FrameClock`], with
+    /// [`AdwaitaFrameBackpressure::DropOldest`].
+    #[default]
+    Mailbox,
+    /// Frames are picked up as soon as they're rendered, instead of on the next tick
+    /// of the window's [`gdk::FrameClock`], with
+    /// [`AdwaitaFrameBackpressure::DropOldest`].
+    ///
+    /// This can show a frame one tick earlier than with
+    /// [`AdwaitaPresentMode::Mailbox`], at the cost of GTK redrawing more often. The
+    /// compositor still never tears.
+    Immediate,
+}
+
+impl AdwaitaPresentMode {
+    /// The [`AdwaitaFrameBackpressure`] which this mode uses.
+    #[must_use]
+    pub const fn backpressure(self) -> AdwaitaFrameBackpressure {
+        match self {
+            Self::Fifo => AdwaitaFrameBackpressure::Throttle,
+            Self::Mailbox | Self::Immediate => AdwaitaFrameBackpressure::DropOldest,
+        }
+    }
+}
+
+/// Longest time that [`AdwaitaFrameBackpressure::Throttle`] blocks the render app
+/// for.
+pub const THROTTLE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Bevy frame count of the frame that GTK last picked up to draw.
+#[derive(Debug, Default)]
+pub struct ConsumedFrame {
+    frame_count: Mutex<u32>,
+    picked_up: Condvar,
+}
+
+impl ConsumedFrame {
+    pub fn consume(&self, frame_count: u32) {
+        *self.frame_count.lock().expect("poisoned") = frame_count;
+        self.picked_up.notify_all();
+    }
+
+    /// Whether GTK hasn't picked up the frame with `rendered_frame_count` yet.
+    pub fn is_behind(&self, rendered_frame_count: u32) -> bool {
+        *self.frame_count.lock().expect("poisoned") != rendered_frame_count
+    }
+
+    /// Blocks until GTK has picked up the frame with `rendered_frame_count`, or until
+    /// `timeout` has passed.
+    pub fn wait(&self, rendered_frame_count: u32, timeout: Duration) {
+        let frame_count = self.frame_count.lock().expect("poisoned");
+        let (frame_count, result) = self
+            .picked_up
+            .wait_timeout_while(frame_count, timeout, |frame_count| {
+                *frame_count != rendered_frame_count
             })
+            .expect("poisoned");
+        drop(frame_count);
+        if result.timed_out() {
+            debug!("Timed out waiting for GTK to pick up frame {rendered_frame_count}");
         }
-        .expect("failed to create vulkan instance");
+    }
+}
+
+pub type SharedConsumedFrame = Arc<ConsumedFrame>;
+
+/// Value which is ordered by when it was created, such as a [`FrameInfo`].
+pub trait Sequenced {
+    /// Increases with every value created, so a higher sequence is newer.
+    fn sequence(&self) -> u64;
+}
+
+impl Sequenced for FrameInfo {
+    fn sequence(&self) -> u64 {
+        self.sequence
+    }
+}
+
+/// Stores `value` in `slot`, unless `slot` already holds a newer value.
+///
+/// Both the main and render world write to the slots of frames, so a plain store
+/// could replace a frame with an older one, which would then be presented out of
+/// order.
+pub fn store_newest<T: Sequenced>(slot: &AtomicOptionBox<T>, value: Box<T>) {
+    let mut value = value;
+    loop {
+        let sequence = value.sequence();
+        let Some(previous) = slot.swap(Some(value), Ordering::SeqCst) else {
+            return;
+        };
+        if previous.sequence() <= sequence {
+            // the older value is dropped here
+            return;
+        }
+        // we replaced a newer value, so put that one back instead, and check
+        // whatever it displaces in turn
+        value = previous;
+    }
+}
+
+/// Frame which GTK has most recently painted to the screen.
+///
+/// See [`AdwaitaWindow::presented_frame`].
+///
+/// [`AdwaitaWindow::presented_frame`]: crate::AdwaitaWindow::presented_frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub struct AdwaitaPresentedFrame {
+    /// Identifies the render target buffer that was displayed.
+    ///
+    /// This is unique per window. Frames rotate between a few buffers of each size,
+    /// so the same ID comes up again whenever its buffer is reused.
+    pub buffer_id: u64,
+    /// Size of the displayed region of the render target buffer, in physical pixels.
+    pub size: UVec2,
+    /// [`FrameCount`] of the last Bevy frame rendered into the buffer before it was
+    /// displayed.
+    ///
+    /// [`FrameCount`]: bevy::core::FrameCount
+    pub frame_count: u32,
+}
+
+/// Task waiting for the next [`AdwaitaPresentedFrame`] of a window.
+#[derive(Debug)]
+pub struct PresentWaiter {
+    pub send: flume::Sender<AdwaitaPresentedFrame>,
+    /// Once this passes, the waiter is dropped, and its task stops waiting.
+    pub deadline: Option<Instant>,
+}
+
+pub type SharedPresentWaiters = Arc<Mutex<Vec<PresentWaiter>>>;
+
+/// Region of the render target which changed in a frame.
+#[derive(Debug, Clone, Default)]
+pub enum FrameDamage {
+    #[default]
+    Full,
+    Partial(Vec<URect>),
+}
+
+impl FrameDamage {
+    pub const fn none() -> Self {
+        Self::Partial(Vec::new())
+    }
+
+    /// Adds the damage of another frame, where `None` means the whole frame.
+    pub fn merge(&mut self, other: Option<Vec<URect>>) {
+        match (&mut *self, other) {
+            (Self::Full, _) => {}
+            (Self::Partial(_), None) => *self = Self::Full,
+            (Self::Partial(rects), Some(other)) => rects.extend(other),
+        }
+    }
+}
+
+/// Why the renderer couldn't be created, see
+/// [`AdwaitaWindowPlugin::render_plugin_with_config`].
+///
+/// [`AdwaitaWindowPlugin::render_plugin_with_config`]: crate::AdwaitaWindowPlugin::render_plugin_with_config
+#[derive(Debug, thiserror::Error)]
+pub enum AdwaitaRenderError {
+    #[error("failed to create Vulkan instance")]
+    Instance(#[source] wgpu_hal::InstanceError),
+    #[error("no Vulkan adapters are available")]
+    NoAdapter,
+    #[error("no adapter matches {selection:?}, the available adapters are {available:?}")]
+    NoMatchingAdapter {
+        selection: AdwaitaAdapterSelection,
+        available: Vec<String>,
+    },
+    #[error(
+        "adapter \"{name}\" is a software rasterizer - set \
+         `AdwaitaRenderConfig::allow_software_adapter` to use it anyway"
+    )]
+    SoftwareAdapter { name: String },
+    #[error("adapter \"{name}\" doesn't support the required extensions {missing:?}")]
+    MissingExtensions {
+        name: String,
+        missing: Vec<&'static CStr>,
+    },
+    #[error("adapter \"{name}\" doesn't support the requested features {unsupported:?}")]
+    UnsupportedFeatures {
+        name: String,
+        unsupported: wgpu::Features,
+    },
+    #[error("adapter \"{name}\" doesn't support the requested limits: {}", unsupported.join(", "))]
+    UnsupportedLimits {
+        name: String,
+        /// Each limit which is out of range, with the requested and allowed value.
+        unsupported: Vec<String>,
+    },
+    #[error("failed to open Vulkan device")]
+    OpenDevice(#[source] wgpu_hal::DeviceError),
+    #[error("failed to create device")]
+    CreateDevice(#[source] wgpu::RequestDeviceError),
+}
+
+// for exporting render targets as dmabufs
+const REQUIRED_EXTENSIONS: [&CStr; 2] = [
+    ash::extensions::khr::GetMemoryRequirements2::name(),
+    ash::extensions::khr::ExternalMemoryFd::name(),
+];
+
+// enabled if available, so that render targets can use a tiled layout the display
+// can import, instead of being linear
+const MODIFIER_EXTENSIONS: [&CStr; 2] = [
+    vk::ExtImageDrmFormatModifierFn::name(),
+    vk::ExtExternalMemoryDmaBufFn::name(),
+];
+
+fn create_instance(settings: &WgpuSettings) -> Result<vulkan::Instance, AdwaitaRenderError> {
+    unsafe {
+        vulkan::Instance::init(&wgpu_hal::InstanceDescriptor {
+            name: "bevy_mod_adwaita", // app name
+            flags: settings.instance_flags,
+            dx12_shader_compiler: settings.dx12_shader_compiler.clone(),
+            gles_minor_version: settings.gles3_minor_version,
+        })
+    }
+    .map_err(AdwaitaRenderError::Instance)
+}
+
+fn missing_extensions(adapter: &vulkan::Adapter) -> Vec<&'static CStr> {
+    let capabilities = adapter.physical_device_capabilities();
+    REQUIRED_EXTENSIONS
+        .into_iter()
+        .filter(|extension| !capabilities.supports_extension(extension))
+        .collect()
+}
+
+/// Device extensions to enable on an adapter which supports the
+/// [`REQUIRED_EXTENSIONS`], given which extensions it supports.
+fn device_extensions(supports: &dyn Fn(&CStr) -> bool) -> Vec<&'static CStr> {
+    let mut extensions = REQUIRED_EXTENSIONS.to_vec();
+    if MODIFIER_EXTENSIONS
+        .iter()
+        .all(|extension| supports(extension))
+    {
+        extensions.extend(MODIFIER_EXTENSIONS);
+    } else {
+        info!("Adapter can't export tiled dmabufs, falling back to linear render targets");
+    }
+    extensions
+}
+
+/// Whether any Vulkan adapter has the extensions needed to share render targets with
+/// GTK, without opening a device.
+pub fn is_supported() -> bool {
+    let Ok(instance) = create_instance(&WgpuSettings::default()) else {
+        return false;
+    };
+    let adapters = unsafe { instance.enumerate_adapters() };
+    adapters
+        .iter()
+        .any(|adapter| missing_extensions(&adapter.adapter).is_empty())
+}
+
+pub fn create_renderer(
+    settings: WgpuSettings,
+    config: AdwaitaRenderConfig,
+) -> Result<RenderCreation, AdwaitaRenderError> {
+    let do_async = async move {
+        let instance = create_instance(&settings)?;
 
         // validation works
         // let instance = unsafe { wgpu::Instance::from_hal::<vulkan::Api>(instance) };
@@ -63,62 +519,687 @@ pub fn create_renderer(settings: WgpuSettings) -> RenderCreation {
         // .await;
 
         // validation fails
-        let adapter = unsafe { instance.enumerate_adapters() }
-            .into_iter()
-            .next()
-            .expect("no adapters");
+        let mut adapters = unsafe { instance.enumerate_adapters() };
+        if adapters.is_empty() {
+            return Err(AdwaitaRenderError::NoAdapter);
+        }
+        let adapter =
+            adapters.swap_remove(pick_adapter(&adapters, &config, settings.power_preference)?);
+        let missing = missing_extensions(&adapter.adapter);
+        if !missing.is_empty() {
+            return Err(AdwaitaRenderError::MissingExtensions {
+                name: adapter.info.name,
+                missing,
+            });
+        }
+        let capabilities = adapter.adapter.physical_device_capabilities();
+        let extensions = device_extensions(&|extension| capabilities.supports_extension(extension));
+        // the device is opened by us, so it has to enable the same features that wgpu
+        // is told about
+        let adapter_limits = &adapter.capabilities.limits;
+        let device_descriptor = hal_custom::make_device_descriptor(
+            &settings,
+            adapter.info.device_type,
+            adapter.features,
+            adapter_limits,
+        );
+        check_device_descriptor(
+            &adapter.info.name,
+            adapter.features,
+            adapter_limits,
+            &device_descriptor,
+        )?;
         let device = unsafe {
             hal_custom::open_adapter(
                 &adapter.adapter,
-                settings.features.clone(),
-                [
-                    ash::extensions::khr::GetMemoryRequirements2::name(),
-                    ash::extensions::khr::ExternalMemoryFd::name(),
-                ],
+                device_descriptor.required_features,
+                extensions,
             )
-            .expect("failed to open device")
-        };
+        }
+        .map_err(AdwaitaRenderError::OpenDevice)?;
         let instance = unsafe { wgpu::Instance::from_hal::<vulkan::Api>(instance) };
         let adapter = unsafe { instance.create_adapter_from_hal(adapter) };
         let adapter_info = adapter.get_info();
-        let device_descriptor =
-            hal_custom::make_device_descriptor(&settings, &adapter, &adapter_info);
         let (device, queue) =
             unsafe { adapter.create_device_from_hal(device, &device_descriptor, None) }
-                .expect("failed to create device");
+                .map_err(AdwaitaRenderError::CreateDevice)?;
         let device = RenderDevice::from(device);
         let queue = RenderQueue(Arc::new(WgpuWrapper::new(queue)));
         let adapter_info = RenderAdapterInfo(WgpuWrapper::new(adapter_info));
         let adapter = RenderAdapter(Arc::new(WgpuWrapper::new(adapter)));
 
-        RenderCreation::Manual(
+        Ok(RenderCreation::Manual(
             device,
             queue,
             adapter_info,
             adapter,
             RenderInstance(Arc::new(WgpuWrapper::new(instance))),
-        )
+        ))
     };
 
     futures_lite::future::block_on(do_async)
 }
 
+/// Which Vulkan adapter to render on, see [`AdwaitaRenderConfig::adapter`].
+///
+/// If the selected adapter doesn't have the extensions needed to share render
+/// targets with GTK, creating the renderer fails with
+/// [`AdwaitaRenderError::MissingExtensions`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum AdwaitaAdapterSelection {
+    /// The adapter which the display is connected to, or the first hardware
+    /// adapter if that can't be determined.
+    #[default]
+    Display,
+    /// The first adapter whose name contains this, ignoring case.
+    Name(String),
+    /// The adapter with this PCI vendor and device ID, as shown by `lspci -nn`.
+    PciId { vendor: u32, device: u32 },
+    /// The adapter at this index in the order that Vulkan lists them.
+    Index(usize),
+    /// The first discrete GPU for [`PowerPreference::HighPerformance`], or the first
+    /// integrated GPU for [`PowerPreference::LowPower`].
+    ///
+    /// If there's no such adapter, or for [`PowerPreference::None`], this is the
+    /// same as [`AdwaitaAdapterSelection::Display`].
+    ///
+    /// [`PowerPreference::HighPerformance`]: wgpu::PowerPreference::HighPerformance
+    /// [`PowerPreference::LowPower`]: wgpu::PowerPreference::LowPower
+    /// [`PowerPreference::None`]: wgpu::PowerPreference::None
+    PowerPreference(wgpu::PowerPreference),
+}
+
+/// Checks that the adapter called `name` supports everything that `descriptor`
+/// requests.
+fn check_device_descriptor(
+    name: &str,
+    adapter_features: wgpu::Features,
+    adapter_limits: &wgpu::Limits,
+    descriptor: &wgpu::DeviceDescriptor,
+) -> Result<(), AdwaitaRenderError> {
+    let unsupported = descriptor.required_features - adapter_features;
+    if !unsupported.is_empty() {
+        return Err(AdwaitaRenderError::UnsupportedFeatures {
+            name: name.to_owned(),
+            unsupported,
+        });
+    }
+
+    let mut unsupported = Vec::new();
+    descriptor.required_limits.check_limits_with_fail_fn(
+        adapter_limits,
+        false,
+        |name, requested, allowed| {
+            unsupported.push(format!("{name} (requested {requested}, allowed {allowed})"));
+        },
+    );
+    if !unsupported.is_empty() {
+        return Err(AdwaitaRenderError::UnsupportedLimits {
+            name: name.to_owned(),
+            unsupported,
+        });
+    }
+    Ok(())
+}
+
+/// Major and minor device number of a DRM device node, i.e. `/dev/dri/card*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub struct AdwaitaDrmNode {
+    pub major: i64,
+    pub minor: i64,
+}
+
+fn pick_adapter(
+    adapters: &[wgpu_hal::ExposedAdapter<vulkan::Api>],
+    config: &AdwaitaRenderConfig,
+    power_preference: wgpu::PowerPreference,
+) -> Result<usize, AdwaitaRenderError> {
+    let display = display_drm_node().map_or(DisplayAdapter::Unknown, |display_node| {
+        adapters
+            .iter()
+            .position(|adapter| {
+                adapter.info.device_type != wgpu::DeviceType::Cpu
+                    && adapter_drm_node(&adapter.adapter) == Some(display_node)
+            })
+            .map_or(
+                DisplayAdapter::NotFound(display_node),
+                DisplayAdapter::Found,
+            )
+    });
+    let infos = adapters
+        .iter()
+        .map(|adapter| adapter.info.clone())
+        .collect::<Vec<_>>();
+    select_adapter(&infos, config, power_preference, display)
+}
+
+/// Which of the adapters the display is connected to, see [`display_drm_node`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DisplayAdapter {
+    /// The display's DRM device couldn't be determined.
+    Unknown,
+    /// No hardware adapter is on the display's DRM device.
+    NotFound(AdwaitaDrmNode),
+    /// The index of the hardware adapter on the display's DRM device.
+    Found(usize),
+}
+
+fn select_adapter(
+    adapters: &[wgpu::AdapterInfo],
+    config: &AdwaitaRenderConfig,
+    power_preference: wgpu::PowerPreference,
+    display: DisplayAdapter,
+) -> Result<usize, AdwaitaRenderError> {
+    let find = |matches: &dyn Fn(&wgpu::AdapterInfo) -> bool| adapters.iter().position(matches);
+    let index = match &config.adapter {
+        AdwaitaAdapterSelection::Display => {
+            return select_display_adapter(adapters, config, power_preference, display)
+        }
+        AdwaitaAdapterSelection::Name(name) => {
+            let name = name.to_lowercase();
+            find(&|info| info.name.to_lowercase().contains(&name))
+        }
+        AdwaitaAdapterSelection::PciId { vendor, device } => {
+            find(&|info| info.vendor == *vendor && info.device == *device)
+        }
+        AdwaitaAdapterSelection::Index(index) => Some(*index).filter(|&i| i < adapters.len()),
+        AdwaitaAdapterSelection::PowerPreference(preference) => {
+            let Some(index) = preferred_device_type(*preference)
+                .and_then(|device_type| find(&|info| info.device_type == device_type))
+            else {
+                info!("No adapter matches {preference:?}, using the display's adapter");
+                return select_display_adapter(adapters, config, *preference, display);
+            };
+            Some(index)
+        }
+    };
+
+    let Some(index) = index else {
+        return Err(AdwaitaRenderError::NoMatchingAdapter {
+            selection: config.adapter.clone(),
+            available: adapters.iter().map(|info| info.name.clone()).collect(),
+        });
+    };
+    let info = &adapters[index];
+    if info.device_type == wgpu::DeviceType::Cpu && !config.allow_software_adapter {
+        return Err(AdwaitaRenderError::SoftwareAdapter {
+            name: info.name.clone(),
+        });
+    }
+    if matches!(display, DisplayAdapter::Found(display_index) if display_index != index) {
+        warn!(
+            "Selected adapter \"{}\" isn't the display's adapter, the compositor may be \
+             unable to show its frames",
+            info.name
+        );
+    }
+    Ok(index)
+}
+
+// on multi-GPU systems the compositor can typically only import dmabufs
+// allocated on the GPU it's displaying on, so we try to render on that one too
+fn select_display_adapter(
+    adapters: &[wgpu::AdapterInfo],
+    config: &AdwaitaRenderConfig,
+    power_preference: wgpu::PowerPreference,
+    display: DisplayAdapter,
+) -> Result<usize, AdwaitaRenderError> {
+    // software rasterizers can technically export dmabufs, but they're unusably slow,
+    // and compositors may refuse to import their buffers
+    let hardware = adapters
+        .iter()
+        .enumerate()
+        .filter(|(_, info)| info.device_type != wgpu::DeviceType::Cpu)
+        .collect::<Vec<_>>();
+    if hardware.is_empty() {
+        let name = &adapters[0].name;
+        if !config.allow_software_adapter {
+            return Err(AdwaitaRenderError::SoftwareAdapter { name: name.clone() });
+        }
+        warn!(
+            "Using software rasterizer \"{name}\" since no hardware adapter is available - \
+             expect very poor performance"
+        );
+        return Ok(0);
+    }
+
+    // without knowing the display's adapter, `WgpuSettings::power_preference` is
+    // the best guess
+    let preferred = || {
+        let device_type = preferred_device_type(power_preference);
+        hardware
+            .iter()
+            .find(|(_, info)| Some(info.device_type) == device_type)
+            .unwrap_or(&hardware[0])
+            .0
+    };
+
+    let index = match display {
+        DisplayAdapter::Found(index) => index,
+        DisplayAdapter::Unknown => {
+            info!(
+                "Could not determine the display's DRM device, using the hardware adapter \
+                 preferred by {power_preference:?}"
+            );
+            preferred()
+        }
+        DisplayAdapter::NotFound(display_node) => {
+            warn!(
+                "No adapter matches the display's DRM device {display_node:?}, \
+                 using the hardware adapter preferred by {power_preference:?}"
+            );
+            preferred()
+        }
+    };
+    Ok(index)
+}
+
+const fn preferred_device_type(preference: wgpu::PowerPreference) -> Option<wgpu::DeviceType> {
+    match preference {
+        wgpu::PowerPreference::HighPerformance => Some(wgpu::DeviceType::DiscreteGpu),
+        wgpu::PowerPreference::LowPower => Some(wgpu::DeviceType::IntegratedGpu),
+        wgpu::PowerPreference::None => None,
+    }
+}
+
+/// Gets the DRM primary node of a Vulkan adapter, if the driver supports
+/// `VK_EXT_physical_device_drm`.
+pub fn adapter_drm_node(adapter: &vulkan::Adapter) -> Option<AdwaitaDrmNode> {
+    if !adapter
+        .physical_device_capabilities()
+        .supports_extension(vk::ExtPhysicalDeviceDrmFn::name())
+    {
+        return None;
+    }
+
+    let mut drm_props = vk::PhysicalDeviceDrmPropertiesEXT::default();
+    let mut props = vk::PhysicalDeviceProperties2 {
+        p_next: &mut drm_props as *mut _ as *mut c_void,
+        ..default()
+    };
+    unsafe {
+        adapter
+            .shared_instance()
+            .raw_instance()
+            .get_physical_device_properties2(adapter.raw_physical_device(), &mut props);
+    }
+
+    (drm_props.has_primary == vk::TRUE).then_some(AdwaitaDrmNode {
+        major: drm_props.primary_major,
+        minor: drm_props.primary_minor,
+    })
+}
+
+/// Gets the DRM node of the GPU which the firmware used for display output, which
+/// is almost always the one the compositor displays on.
+pub fn display_drm_node() -> Option<AdwaitaDrmNode> {
+    fs::read_dir("/sys/class/drm").ok()?.find_map(|entry| {
+        let path = entry.ok()?.path();
+        let boot_vga = fs::read_to_string(path.join("device/boot_vga")).ok()?;
+        if boot_vga.trim() != "1" {
+            return None;
+        }
+
+        // e.g. `226:0`
+        let dev = fs::read_to_string(path.join("dev")).ok()?;
+        let (major, minor) = dev.trim().split_once(':')?;
+        Some(AdwaitaDrmNode {
+            major: major.parse().ok()?,
+            minor: minor.parse().ok()?,
+        })
+    })
+}
+
+/// Modifier of render targets which the driver couldn't create with one of the
+/// modifiers the display supports.
 // https://github.com/dzfranklin/drm-fourcc-rs/blob/main/src/consts.rs
 // const DMABUF_MODIFIER: u64 = 0xff_ffff_ffff_ffff; // invalid
-const DMABUF_MODIFIER: u64 = 0; // DRM_FORMAT_MOD_LINEAR
+pub const DMABUF_MODIFIER: u64 = 0; // DRM_FORMAT_MOD_LINEAR
 
 // https://github.com/torvalds/linux/blob/master/include/uapi/drm/drm_fourcc.h
-// Why isn't this RGBA8? I don't know! But this works!
-const DMABUF_FORMAT: u32 = u32::from_le_bytes(*b"AB24"); // ABGR8888
-const VK_FORMAT: vk::Format = vk::Format::R8G8B8A8_SRGB;
-const TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+// The alpha channel is ignored (XBGR8888 instead of ABGR8888), so that GTK treats
+// the rendered content as opaque. Otherwise Bevy's straight alpha gets interpreted
+// as premultiplied, and overlays like toasts or `OverContent` window controls
+// composite with a dark halo wherever the content isn't fully opaque.
+pub const DMABUF_FORMAT: u32 = u32::from_le_bytes(*b"XB24"); // XBGR8888
+/// How Bevy's output is encoded in the render target, see
+/// [`AdwaitaWindowConfig::render_target_format`].
+///
+/// [`AdwaitaWindowConfig::render_target_format`]: crate::AdwaitaWindowConfig::render_target_format
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[reflect(Default)]
+pub enum AdwaitaRenderTargetFormat {
+    /// `Rgba8UnormSrgb`: the GPU encodes Bevy's output to sRGB when writing it, and
+    /// GTK displays it as-is.
+    ///
+    /// This matches how Bevy looks when rendering to a winit window.
+    #[default]
+    Srgb,
+    /// `Rgba8Unorm`: Bevy's output is written without any encoding, and GTK
+    /// converts it from linear sRGB when compositing.
+    ///
+    /// Use this if you encode colors yourself, such as in a custom post-processing
+    /// pass. Since only 8 bits are stored per channel, dark gradients show more
+    /// banding than with [`AdwaitaRenderTargetFormat::Srgb`].
+    Linear,
+    /// `Bgra8UnormSrgb`: the same as [`AdwaitaRenderTargetFormat::Srgb`], with the
+    /// channels in the other order.
+    ///
+    /// Some drivers and compositors only handle this order efficiently, since it's
+    /// the native order of most displays.
+    BgraSrgb,
+    /// `Rgba16Float`: Bevy's output is written linearly with 16 bits per channel,
+    /// and GTK converts it from linear sRGB when compositing.
+    ///
+    /// This avoids banding and keeps values above `1.0` for HDR workflows, at twice
+    /// the memory bandwidth of the 8-bit formats. Recording doesn't support it.
+    Float16,
+}
+
+impl AdwaitaRenderTargetFormat {
+    /// Every format that a render target can be created in.
+    pub(crate) const ALL: [Self; 4] = [Self::Srgb, Self::Linear, Self::BgraSrgb, Self::Float16];
+
+    // the Vulkan format, wgpu format and fourcc of a format must all describe the
+    // same memory layout, or GTK shows garbage
+
+    const fn vk_format(self) -> vk::Format {
+        match self {
+            Self::Srgb => vk::Format::R8G8B8A8_SRGB,
+            Self::Linear => vk::Format::R8G8B8A8_UNORM,
+            Self::BgraSrgb => vk::Format::B8G8R8A8_SRGB,
+            Self::Float16 => vk::Format::R16G16B16A16_SFLOAT,
+        }
+    }
+
+    const fn texture_format(self) -> TextureFormat {
+        match self {
+            Self::Srgb => TextureFormat::Rgba8UnormSrgb,
+            Self::Linear => TextureFormat::Rgba8Unorm,
+            Self::BgraSrgb => TextureFormat::Bgra8UnormSrgb,
+            Self::Float16 => TextureFormat::Rgba16Float,
+        }
+    }
+
+    /// DRM fourcc that GTK imports the dmabuf as, which only keeps the alpha
+    /// channel if `alpha` is set.
+    pub(crate) const fn fourcc(self, alpha: bool) -> u32 {
+        match (self, alpha) {
+            (Self::Srgb | Self::Linear, false) => DMABUF_FORMAT,
+            (Self::Srgb | Self::Linear, true) => u32::from_le_bytes(*b"AB24"), // ABGR8888
+            // DRM formats are named from the most significant bit, so a BGRA memory
+            // layout is XRGB8888
+            (Self::BgraSrgb, false) => u32::from_le_bytes(*b"XR24"),
+            (Self::BgraSrgb, true) => u32::from_le_bytes(*b"AR24"), // ARGB8888
+            (Self::Float16, false) => u32::from_le_bytes(*b"XB4H"), // XBGR16161616F
+            (Self::Float16, true) => u32::from_le_bytes(*b"AB4H"),  // ABGR16161616F
+        }
+    }
+
+    // whether GTK has to be told that the colors aren't sRGB-encoded
+    const fn is_linear(self) -> bool {
+        matches!(self, Self::Linear | Self::Float16)
+    }
 
-pub fn setup_render_target(size: UVec2, render_device: &RenderDevice) -> (ManualTextureView, i32) {
+    /// Format of the extra view created for
+    /// [`AdwaitaWindowConfig::alternate_format_view`], if this format has one.
+    ///
+    /// [`AdwaitaWindowConfig::alternate_format_view`]: crate::AdwaitaWindowConfig::alternate_format_view
+    pub(crate) const fn alternate(self) -> Option<Self> {
+        match self {
+            Self::Srgb => Some(Self::Linear),
+            Self::Linear => Some(Self::Srgb),
+            Self::BgraSrgb | Self::Float16 => None,
+        }
+    }
+}
+
+/// Gets the DRM format modifiers which the Vulkan adapter can render to for our
+/// render target format, if the driver supports `VK_EXT_image_drm_format_modifier`.
+pub fn adapter_format_modifiers(adapter: &vulkan::Adapter) -> Vec<u64> {
+    if !adapter
+        .physical_device_capabilities()
+        .supports_extension(vk::ExtImageDrmFormatModifierFn::name())
+    {
+        return Vec::new();
+    }
+
+    // only negotiated for `DMABUF_FORMAT`, which the sRGB and linear formats share
+    format_modifier_properties(
+        adapter.shared_instance().raw_instance(),
+        adapter.raw_physical_device(),
+        AdwaitaRenderTargetFormat::default().vk_format(),
+    )
+    .into_iter()
+    .filter(|props| {
+        props
+            .drm_format_modifier_tiling_features
+            .contains(vk::FormatFeatureFlags::COLOR_ATTACHMENT)
+    })
+    .map(|props| props.drm_format_modifier)
+    .collect()
+}
+
+fn format_modifier_properties(
+    instance: &ash::Instance,
+    physical_device: vk::PhysicalDevice,
+    format: vk::Format,
+) -> Vec<vk::DrmFormatModifierPropertiesEXT> {
+    let query = |modifier_list: &mut vk::DrmFormatModifierPropertiesListEXT| {
+        let mut props = vk::FormatProperties2 {
+            p_next: modifier_list as *mut _ as *mut c_void,
+            ..default()
+        };
+        unsafe {
+            instance.get_physical_device_format_properties2(physical_device, format, &mut props);
+        }
+    };
+
+    let mut modifier_list = vk::DrmFormatModifierPropertiesListEXT::default();
+    query(&mut modifier_list);
+    let mut modifier_props = vec![
+        vk::DrmFormatModifierPropertiesEXT::default();
+        modifier_list.drm_format_modifier_count as usize
+    ];
+    modifier_list.p_drm_format_modifier_properties = modifier_props.as_mut_ptr();
+    query(&mut modifier_list);
+    modifier_props.truncate(modifier_list.drm_format_modifier_count as usize);
+    modifier_props
+}
+
+/// Modifiers out of `candidates` which the device can create exportable render
+/// targets with, along with how many memory planes each of them uses.
+///
+/// Empty if the device was opened without [`MODIFIER_EXTENSIONS`].
+fn supported_modifiers(
+    hal_device: &vulkan::Device,
+    image_create: &vk::ImageCreateInfo,
+    view_formats: Option<&[vk::Format]>,
+    candidates: &[u64],
+) -> Vec<(u64, u32)> {
+    let enabled = hal_device.enabled_device_extensions();
+    if !MODIFIER_EXTENSIONS
+        .iter()
+        .all(|extension| enabled.contains(extension))
+    {
+        return Vec::new();
+    }
+
+    let instance = hal_device.shared_instance().raw_instance();
+    let physical_device = hal_device.raw_physical_device();
+    format_modifier_properties(instance, physical_device, image_create.format)
+        .into_iter()
+        .filter(|props| {
+            candidates.contains(&props.drm_format_modifier)
+                && props.drm_format_modifier_plane_count as usize <= MAX_DMABUF_PLANES
+                && props
+                    .drm_format_modifier_tiling_features
+                    .contains(vk::FormatFeatureFlags::COLOR_ATTACHMENT)
+        })
+        .filter(|props| {
+            // a modifier may still not support our usage, flags or image size
+            let format_list = view_formats.map(|view_formats| vk::ImageFormatListCreateInfo {
+                view_format_count: view_formats.len() as u32,
+                p_view_formats: view_formats.as_ptr(),
+                ..default()
+            });
+            let modifier_info = vk::PhysicalDeviceImageDrmFormatModifierInfoEXT {
+                p_next: format_list
+                    .as_ref()
+                    .map_or(std::ptr::null(), |format_list| {
+                        format_list as *const _ as *const c_void
+                    }),
+                drm_format_modifier: props.drm_format_modifier,
+                sharing_mode: vk::SharingMode::EXCLUSIVE,
+                ..default()
+            };
+            let external_info = vk::PhysicalDeviceExternalImageFormatInfo {
+                p_next: &modifier_info as *const _ as *const c_void,
+                handle_type: vk::ExternalMemoryHandleTypeFlags::DMA_BUF_EXT,
+                ..default()
+            };
+            let format_info = vk::PhysicalDeviceImageFormatInfo2 {
+                p_next: &external_info as *const _ as *const c_void,
+                format: image_create.format,
+                ty: image_create.image_type,
+                tiling: vk::ImageTiling::DRM_FORMAT_MODIFIER_EXT,
+                usage: image_create.usage,
+                flags: image_create.flags,
+                ..default()
+            };
+            let mut external_props = vk::ExternalImageFormatProperties::default();
+            let mut props = vk::ImageFormatProperties2 {
+                p_next: &mut external_props as *mut _ as *mut c_void,
+                ..default()
+            };
+            let result = unsafe {
+                instance.get_physical_device_image_format_properties2(
+                    physical_device,
+                    &format_info,
+                    &mut props,
+                )
+            };
+            let max_extent = props.image_format_properties.max_extent;
+            result.is_ok()
+                && image_create.extent.width <= max_extent.width
+                && image_create.extent.height <= max_extent.height
+                && external_props
+                    .external_memory_properties
+                    .external_memory_features
+                    .contains(vk::ExternalMemoryFeatureFlags::EXPORTABLE)
+        })
+        .map(|props| {
+            (
+                props.drm_format_modifier,
+                props.drm_format_modifier_plane_count,
+            )
+        })
+        .collect()
+}
+
+// memory planes of an image with a DRM format modifier, which aren't the same as
+// the planes of a multi-planar format
+const MEMORY_PLANE_ASPECTS: [vk::ImageAspectFlags; MAX_DMABUF_PLANES] = [
+    vk::ImageAspectFlags::MEMORY_PLANE_0_EXT,
+    vk::ImageAspectFlags::MEMORY_PLANE_1_EXT,
+    vk::ImageAspectFlags::MEMORY_PLANE_2_EXT,
+    vk::ImageAspectFlags::MEMORY_PLANE_3_EXT,
+];
+
+fn plane_layout(
+    vk_device: &ash::Device,
+    image: vk::Image,
+    aspect_mask: vk::ImageAspectFlags,
+) -> DmabufPlane {
+    let layout = unsafe {
+        vk_device.get_image_subresource_layout(
+            image,
+            vk::ImageSubresource {
+                aspect_mask,
+                mip_level: 0,
+                array_layer: 0,
+            },
+        )
+    };
+    DmabufPlane {
+        offset: layout.offset as u32,
+        stride: layout.row_pitch as u32,
+    }
+}
+
+/// Whether the Vulkan adapter can create render targets of `format` which are
+/// exportable as dmabufs.
+pub fn adapter_supports_export(
+    adapter: &vulkan::Adapter,
+    format: AdwaitaRenderTargetFormat,
+) -> bool {
+    let instance = adapter.shared_instance().raw_instance();
+    let external_info = vk::PhysicalDeviceExternalImageFormatInfo {
+        handle_type: vk::ExternalMemoryHandleTypeFlags::OPAQUE_FD,
+        ..default()
+    };
+    // must match how `create_target_from_hal` creates the image
+    let format_info = vk::PhysicalDeviceImageFormatInfo2 {
+        p_next: &external_info as *const _ as *const c_void,
+        format: format.vk_format(),
+        ty: vk::ImageType::TYPE_2D,
+        tiling: vk::ImageTiling::LINEAR,
+        usage: vk::ImageUsageFlags::TRANSFER_SRC | vk::ImageUsageFlags::COLOR_ATTACHMENT,
+        ..default()
+    };
+    let mut external_props = vk::ExternalImageFormatProperties::default();
+    let mut props = vk::ImageFormatProperties2 {
+        p_next: &mut external_props as *mut _ as *mut c_void,
+        ..default()
+    };
+    let result = unsafe {
+        instance.get_physical_device_image_format_properties2(
+            adapter.raw_physical_device(),
+            &format_info,
+            &mut props,
+        )
+    };
+    result.is_ok()
+        && external_props
+            .external_memory_properties
+            .external_memory_features
+            .contains(vk::ExternalMemoryFeatureFlags::EXPORTABLE)
+}
+
+/// Render target created by [`setup_render_target`].
+#[derive(Debug)]
+pub struct ExportedRenderTarget {
+    pub texture: Texture,
+    pub manual_texture_view: ManualTextureView,
+    /// View of the render target in the alternate format, if one was requested.
+    pub alternate_view: Option<ManualTextureView>,
+    pub dmabuf_fd: i32,
+    pub layout: DmabufLayout,
+}
+
+/// Creates a render target, and if `alternate` is given, a second view of it in that
+/// format, which must share a memory layout with `format`.
+///
+/// Only the color image is exported. Cameras rendering to it allocate their own
+/// depth and prepass textures at the size of the [`ManualTextureView`], the same as
+/// for any other render target, and reallocate them when the ring is resized.
+///
+/// The driver picks whichever of `modifiers` it renders to best, which should be
+/// the ones the display can import for the fourcc of `format`. If it supports none
+/// of them, the render target is linear.
+pub fn setup_render_target(
+    size: UVec2,
+    format: AdwaitaRenderTargetFormat,
+    alternate: Option<AdwaitaRenderTargetFormat>,
+    modifiers: &[u64],
+    render_device: &RenderDevice,
+) -> ExportedRenderTarget {
     let wgpu_device = render_device.wgpu_device();
-    let (texture, dmabuf_fd) = unsafe {
+    let (texture, dmabuf_fd, layout) = unsafe {
         let r = wgpu_device.as_hal::<vulkan::Api, _, _>(|hal_device| {
             let hal_device = hal_device.expect("`RenderDevice` is not a vulkan device");
-            create_target_from_hal(wgpu_device, hal_device, size.x, size.y)
+            create_target_from_hal(wgpu_device, hal_device, size, format, alternate, modifiers)
         });
         r.unwrap()
     };
@@ -128,48 +1209,88 @@ pub fn setup_render_target(size: UVec2, render_device: &RenderDevice) -> (Manual
     let manual_texture_view = ManualTextureView {
         texture_view: texture_view.into(),
         size,
-        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        format: format.texture_format(),
     };
+    let alternate_view = alternate.map(|alternate| {
+        let format = alternate.texture_format();
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("adwaita_render_target_alternate"),
+            format: Some(format),
+            ..default()
+        });
+        ManualTextureView {
+            texture_view: texture_view.into(),
+            size,
+            format,
+        }
+    });
 
-    (manual_texture_view, dmabuf_fd)
+    ExportedRenderTarget {
+        texture: texture.into(),
+        manual_texture_view,
+        alternate_view,
+        dmabuf_fd,
+        layout,
+    }
 }
 
-fn create_target_from_hal(
-    wgpu_device: &wgpu::Device,
-    hal_device: &vulkan::Device,
-    width: u32,
-    height: u32,
-) -> (wgpu::Texture, i32) {
-    struct DropGuard {
-        device: ash::Device,
-        memory: vk::DeviceMemory,
-        image: vk::Image,
-        dmabuf_fd: i32,
-    }
-
-    impl Drop for DropGuard {
-        fn drop(&mut self) {
-            unsafe {
-                self.device.destroy_image(self.image, None);
-                self.device.free_memory(self.memory, None);
-            }
+/// Vulkan image exported as a dmabuf, which backs a render target.
+///
+/// This is handed to wgpu-hal as the drop guard of the texture wrapping the image,
+/// so it is dropped once wgpu destroys that texture. wgpu only does this after the
+/// last texture view is dropped and the GPU has finished all work using it, so by
+/// then nothing can access the image anymore.
+///
+/// The dmabuf fd is closed after the memory is freed. GTK never owns the fd, it
+/// only keeps the texture alive through a [`FrameInfo::texture_view`] while it may
+/// read from the dmabuf.
+struct ExportedImage {
+    device: ash::Device,
+    image: vk::Image,
+    memory: vk::DeviceMemory,
+    // only held so that it's closed on drop
+    _dmabuf: OwnedFd,
+}
 
-            let dmabuf = unsafe { File::from_raw_fd(self.dmabuf_fd) };
-            drop(dmabuf);
+impl Drop for ExportedImage {
+    fn drop(&mut self) {
+        // SAFETY: see the type docs, the GPU no longer uses these, and they were
+        // created from this device
+        unsafe {
+            self.device.destroy_image(self.image, None);
+            self.device.free_memory(self.memory, None);
         }
+        // `_dmabuf` is closed after this
     }
+}
 
+fn create_target_from_hal(
+    wgpu_device: &wgpu::Device,
+    hal_device: &vulkan::Device,
+    UVec2 {
+        x: width,
+        y: height,
+    }: UVec2,
+    format: AdwaitaRenderTargetFormat,
+    alternate: Option<AdwaitaRenderTargetFormat>,
+    candidate_modifiers: &[u64],
+) -> (wgpu::Texture, i32, DmabufLayout) {
     let vk_device = hal_device.raw_device();
     let instance = hal_device.shared_instance().raw_instance();
 
-    let external_memory_image_create = vk::ExternalMemoryImageCreateInfo {
-        handle_types: vk::ExternalMemoryHandleTypeFlags::OPAQUE_FD,
-        ..default()
+    let (flags, extra_usage) = if alternate.is_some() {
+        (
+            vk::ImageCreateFlags::MUTABLE_FORMAT,
+            // so that tools can sample the alternate view
+            vk::ImageUsageFlags::SAMPLED,
+        )
+    } else {
+        (vk::ImageCreateFlags::empty(), vk::ImageUsageFlags::empty())
     };
-    let image_create = vk::ImageCreateInfo {
-        p_next: &external_memory_image_create as *const _ as *const c_void,
+    let mut image_create = vk::ImageCreateInfo {
+        flags,
         image_type: vk::ImageType::TYPE_2D,
-        format: VK_FORMAT,
+        format: format.vk_format(),
         extent: vk::Extent3D {
             width,
             height,
@@ -177,13 +1298,66 @@ fn create_target_from_hal(
         },
         mip_levels: 1,
         array_layers: 1,
+        // this is never the MSAA attachment: with `Msaa` enabled, Bevy renders into
+        // its own multisampled texture, resolves that into its main texture, and
+        // only then writes the result into this one
         samples: vk::SampleCountFlags::TYPE_1,
-        tiling: vk::ImageTiling::LINEAR, // or OPTIMAL?
-        usage: vk::ImageUsageFlags::TRANSFER_SRC | vk::ImageUsageFlags::COLOR_ATTACHMENT,
+        tiling: vk::ImageTiling::LINEAR,
+        usage: vk::ImageUsageFlags::TRANSFER_SRC
+            | vk::ImageUsageFlags::COLOR_ATTACHMENT
+            | extra_usage,
         sharing_mode: vk::SharingMode::EXCLUSIVE,
         initial_layout: vk::ImageLayout::UNDEFINED,
         ..default()
     };
+
+    // the alternate view needs a mutable format, and listing both formats lets the
+    // driver keep optimizations that only work for known view formats
+    let view_formats = [format.vk_format(), alternate.unwrap_or(format).vk_format()];
+    let modifiers = supported_modifiers(
+        hal_device,
+        &image_create,
+        alternate.map(|_| &view_formats[..]),
+        candidate_modifiers,
+    );
+    // without a modifier both sides agree on, only a linear image can be shared
+    let handle_type = if modifiers.is_empty() {
+        vk::ExternalMemoryHandleTypeFlags::OPAQUE_FD
+    } else {
+        vk::ExternalMemoryHandleTypeFlags::DMA_BUF_EXT
+    };
+
+    let external_memory_image_create = vk::ExternalMemoryImageCreateInfo {
+        handle_types: handle_type,
+        ..default()
+    };
+    let format_list = vk::ImageFormatListCreateInfo {
+        p_next: &external_memory_image_create as *const _ as *const c_void,
+        view_format_count: view_formats.len() as u32,
+        p_view_formats: view_formats.as_ptr(),
+        ..default()
+    };
+    let p_next = if alternate.is_some() {
+        &format_list as *const _ as *const c_void
+    } else {
+        &external_memory_image_create as *const _ as *const c_void
+    };
+    let modifier_values = modifiers
+        .iter()
+        .map(|(modifier, _)| *modifier)
+        .collect::<Vec<_>>();
+    let modifier_list = vk::ImageDrmFormatModifierListCreateInfoEXT {
+        p_next,
+        drm_format_modifier_count: modifier_values.len() as u32,
+        p_drm_format_modifiers: modifier_values.as_ptr(),
+        ..default()
+    };
+    if modifiers.is_empty() {
+        image_create.p_next = p_next;
+    } else {
+        image_create.p_next = &modifier_list as *const _ as *const c_void;
+        image_create.tiling = vk::ImageTiling::DRM_FORMAT_MODIFIER_EXT;
+    }
     let image =
         unsafe { vk_device.create_image(&image_create, None) }.expect("failed to create image");
 
@@ -199,7 +1373,7 @@ fn create_target_from_hal(
     let dedicated_alloc_info = vk::MemoryDedicatedAllocateInfo { image, ..default() };
     let export_info = vk::ExportMemoryAllocateInfo {
         p_next: &dedicated_alloc_info as *const _ as *const c_void,
-        handle_types: vk::ExternalMemoryHandleTypeFlags::OPAQUE_FD,
+        handle_types: handle_type,
         ..default()
     };
     let alloc_info = vk::MemoryAllocateInfo {
@@ -220,7 +1394,7 @@ fn create_target_from_hal(
 
     let get_memory_info = vk::MemoryGetFdInfoKHR {
         memory,
-        handle_type: vk::ExternalMemoryHandleTypeFlags::OPAQUE_FD,
+        handle_type,
         ..default()
     };
     let dmabuf_fd = unsafe {
@@ -229,6 +1403,50 @@ fn create_target_from_hal(
     }
     .expect("failed to get fd for allocated memory");
 
+    let mut layout = DmabufLayout {
+        modifier: DMABUF_MODIFIER,
+        planes: [DmabufPlane::default(); MAX_DMABUF_PLANES],
+        plane_count: 1,
+    };
+    if modifiers.is_empty() {
+        layout.planes[0] = plane_layout(vk_device, image, vk::ImageAspectFlags::COLOR);
+    } else {
+        let mut modifier_props = vk::ImageDrmFormatModifierPropertiesEXT::default();
+        unsafe {
+            ash::extensions::ext::ImageDrmFormatModifier::new(instance, vk_device)
+                .get_image_drm_format_modifier_properties(image, &mut modifier_props)
+        }
+        .expect("failed to get modifier of image");
+        layout.modifier = modifier_props.drm_format_modifier;
+        layout.plane_count = modifiers
+            .iter()
+            .find(|(modifier, _)| *modifier == layout.modifier)
+            .map_or(1, |(_, plane_count)| *plane_count as usize);
+        for (plane, aspect_mask) in layout.planes[..layout.plane_count]
+            .iter_mut()
+            .zip(MEMORY_PLANE_ASPECTS)
+        {
+            *plane = plane_layout(vk_device, image, aspect_mask);
+        }
+    }
+    debug!(
+        "Created {width}x{height} render target with modifier {:#x} and {} planes",
+        layout.modifier, layout.plane_count,
+    );
+
+    let (view_formats, hal_usage, usage) = if let Some(alternate) = alternate {
+        (
+            vec![alternate.texture_format()],
+            wgpu_hal::TextureUses::RESOURCE,
+            wgpu::TextureUsages::TEXTURE_BINDING,
+        )
+    } else {
+        (
+            Vec::new(),
+            wgpu_hal::TextureUses::empty(),
+            wgpu::TextureUsages::empty(),
+        )
+    };
     let texture_desc = wgpu_hal::TextureDescriptor {
         label: Some("adwaita_render_target"),
         size: wgpu::Extent3d {
@@ -239,17 +1457,18 @@ fn create_target_from_hal(
         mip_level_count: 1,
         sample_count: 1,
         dimension: wgpu::TextureDimension::D2,
-        format: TEXTURE_FORMAT,
-        usage: wgpu_hal::TextureUses::COPY_SRC | wgpu_hal::TextureUses::COLOR_TARGET,
+        format: format.texture_format(),
+        usage: wgpu_hal::TextureUses::COPY_SRC | wgpu_hal::TextureUses::COLOR_TARGET | hal_usage,
         memory_flags: wgpu_hal::MemoryFlags::empty(),
-        view_formats: Vec::new(),
+        view_formats: view_formats.clone(),
     };
 
-    let drop_guard = Box::new(DropGuard {
+    let drop_guard = Box::new(ExportedImage {
         device: hal_device.raw_device().clone(),
-        memory,
         image,
-        dmabuf_fd,
+        memory,
+        // SAFETY: Vulkan transfers ownership of the fd to us
+        _dmabuf: unsafe { OwnedFd::from_raw_fd(dmabuf_fd) },
     });
     let texture =
         unsafe { vulkan::Device::texture_from_raw(image, &texture_desc, Some(drop_guard)) };
@@ -267,36 +1486,479 @@ fn create_target_from_hal(
                 mip_level_count: 1,
                 sample_count: 1,
                 dimension: wgpu::TextureDimension::D2,
-                format: TEXTURE_FORMAT,
-                usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
-                view_formats: &[],
+                format: format.texture_format(),
+                usage: wgpu::TextureUsages::COPY_SRC
+                    | wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | usage,
+                view_formats: &view_formats,
             },
         )
     };
 
-    (texture, dmabuf_fd)
+    (texture, dmabuf_fd, layout)
 }
 
-pub fn create_dmabuf_texture(info: &DmabufInfo) -> gdk::Paintable {
-    let &DmabufInfo { size, fd } = info;
+/// Creates a texture for the current contents of the dmabuf.
+///
+/// If `previous` is given, GTK only needs to redraw the damaged parts of it.
+/// Fails if the compositor can't import the buffer, which some compositors do for
+/// buffers that are too large, even if Vulkan could allocate them.
+pub fn create_dmabuf_texture(
+    frame: &FrameInfo,
+    previous: Option<(&gdk::Texture, &[URect])>,
+) -> Result<gdk::Texture, gtk::glib::Error> {
+    let DmabufInfo {
+        visible_size,
+        format,
+        transparent,
+        fd,
+        layout,
+        ..
+    } = frame.dmabuf;
 
     // https://docs.gtk.org/gdk4/class.DmabufTextureBuilder.html
 
+    // the texture only covers the visible region, but rows are still laid out
+    // according to the full size of the buffer
     let builder = gdk::DmabufTextureBuilder::new();
-    builder.set_width(size.x);
-    builder.set_height(size.y);
-    builder.set_fourcc(DMABUF_FORMAT);
-    builder.set_modifier(DMABUF_MODIFIER);
-
-    builder.set_n_planes(1);
-    builder.set_fd(0, fd);
-    builder.set_offset(0, 0);
-
-    // what the fuck?
-    const VAL: u32 = 64;
-    builder.set_stride(0, (size.x / VAL) * VAL * 4); // bytes per row
-
-    unsafe { builder.build() }
-        .expect("should be a valid dmabuf texture")
-        .upcast()
+    builder.set_width(visible_size.x);
+    builder.set_height(visible_size.y);
+    builder.set_fourcc(frame.dmabuf.fourcc());
+    if transparent {
+        // Bevy's output isn't premultiplied, see `DMABUF_FORMAT`
+        builder.set_premultiplied(false);
+    }
+    builder.set_modifier(layout.modifier);
+
+    builder.set_n_planes(layout.plane_count as u32);
+    for (index, plane) in layout.planes().iter().enumerate() {
+        let index = index as u32;
+        builder.set_fd(index, fd);
+        builder.set_offset(index, plane.offset);
+        builder.set_stride(index, plane.stride);
+    }
+    if format.is_linear() {
+        // not wrapped by gtk-rs yet, and defaults to sRGB
+        unsafe {
+            gdk::ffi::gdk_dmabuf_texture_builder_set_color_state(
+                gtk::glib::object::ObjectType::as_ptr(&builder),
+                gdk::ffi::gdk_color_state_get_srgb_linear(),
+            );
+        }
+    }
+
+    if let Some((previous, damage)) = previous {
+        let rects = damage
+            .iter()
+            .map(|rect| {
+                let min = rect.min.as_ivec2();
+                let size = rect.size().as_ivec2();
+                gdk::cairo::RectangleInt::new(min.x, min.y, size.x, size.y)
+            })
+            .collect::<Vec<_>>();
+        builder.set_update_texture(Some(previous));
+        builder.set_update_region(Some(&gdk::cairo::Region::create_rectangles(&rects)));
+    }
+
+    // GTK may keep reading from the dmabuf after we've moved on to another frame,
+    // for example while the compositor still shows it, so the texture keeps the
+    // render target alive, and out of rotation, until GTK releases it
+    let held = Arc::new(Mutex::new(Some((
+        frame.texture_view.clone(),
+        frame.lease.clone(),
+    ))));
+    let result = unsafe {
+        builder.build_with_release_func({
+            let held = held.clone();
+            move || drop(held.lock().expect("poisoned").take())
+        })
+    };
+    if result.is_err() {
+        // GTK leaks the release function without calling it if building fails, so
+        // release the render target here, instead of keeping it alive forever
+        held.lock().expect("poisoned").take();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct Value(u64);
+
+    impl Sequenced for Value {
+        fn sequence(&self) -> u64 {
+            self.0
+        }
+    }
+
+    fn sequence_in(slot: &AtomicOptionBox<Value>) -> Option<u64> {
+        slot.take(Ordering::SeqCst).map(|value| value.0)
+    }
+
+    #[test]
+    fn store_newest_keeps_newer_value() {
+        let slot = AtomicOptionBox::none();
+        store_newest(&slot, Box::new(Value(2)));
+        store_newest(&slot, Box::new(Value(1)));
+        assert_eq!(sequence_in(&slot), Some(2));
+    }
+
+    #[test]
+    fn store_newest_replaces_older_value() {
+        let slot = AtomicOptionBox::none();
+        store_newest(&slot, Box::new(Value(1)));
+        store_newest(&slot, Box::new(Value(2)));
+        assert_eq!(sequence_in(&slot), Some(2));
+    }
+
+    #[test]
+    fn store_newest_out_of_order() {
+        let slot = AtomicOptionBox::none();
+        for sequence in [5, 3, 8, 1, 7, 8, 2] {
+            store_newest(&slot, Box::new(Value(sequence)));
+        }
+        assert_eq!(sequence_in(&slot), Some(8));
+    }
+
+    #[test]
+    fn store_newest_from_two_threads() {
+        const VALUES_PER_THREAD: u64 = 10_000;
+
+        for _ in 0..16 {
+            let slot = AtomicOptionBox::none();
+            thread::scope(|scope| {
+                // one thread stores the even sequences and the other the odd ones,
+                // each from newest to oldest, so they keep displacing each other
+                for parity in 0..2 {
+                    let slot = &slot;
+                    scope.spawn(move || {
+                        for i in (0..VALUES_PER_THREAD).rev() {
+                            store_newest(slot, Box::new(Value(i * 2 + parity)));
+                        }
+                    });
+                }
+            });
+            assert_eq!(sequence_in(&slot), Some(VALUES_PER_THREAD * 2 - 1));
+        }
+    }
+
+    fn adapter_info(name: &str, device: u32, device_type: wgpu::DeviceType) -> wgpu::AdapterInfo {
+        wgpu::AdapterInfo {
+            name: name.to_owned(),
+            vendor: 0x1002,
+            device,
+            device_type,
+            driver: String::new(),
+            driver_info: String::new(),
+            backend: wgpu::Backend::Vulkan,
+        }
+    }
+
+    // integrated, discrete, then software
+    fn adapter_infos() -> Vec<wgpu::AdapterInfo> {
+        vec![
+            adapter_info(
+                "AMD Radeon Graphics",
+                0x1681,
+                wgpu::DeviceType::IntegratedGpu,
+            ),
+            adapter_info(
+                "AMD Radeon RX 7900 XTX",
+                0x744c,
+                wgpu::DeviceType::DiscreteGpu,
+            ),
+            adapter_info("llvmpipe (LLVM 17.0.6, 256 bits)", 0, wgpu::DeviceType::Cpu),
+        ]
+    }
+
+    fn select(
+        adapter: AdwaitaAdapterSelection,
+        display: DisplayAdapter,
+    ) -> Result<usize, AdwaitaRenderError> {
+        let config = AdwaitaRenderConfig {
+            adapter,
+            ..default()
+        };
+        select_adapter(
+            &adapter_infos(),
+            &config,
+            wgpu::PowerPreference::None,
+            display,
+        )
+    }
+
+    #[test]
+    fn select_adapter_by_name() {
+        let selection = AdwaitaAdapterSelection::Name("rx 7900".to_owned());
+        assert_eq!(select(selection, DisplayAdapter::Unknown).unwrap(), 1);
+
+        let selection = AdwaitaAdapterSelection::Name("nvidia".to_owned());
+        assert!(matches!(
+            select(selection, DisplayAdapter::Unknown),
+            Err(AdwaitaRenderError::NoMatchingAdapter { available, .. }) if available.len() == 3
+        ));
+    }
+
+    #[test]
+    fn select_adapter_by_pci_id() {
+        let selection = AdwaitaAdapterSelection::PciId {
+            vendor: 0x1002,
+            device: 0x1681,
+        };
+        assert_eq!(select(selection, DisplayAdapter::Found(1)).unwrap(), 0);
+
+        let selection = AdwaitaAdapterSelection::PciId {
+            vendor: 0x10de,
+            device: 0x1681,
+        };
+        assert!(matches!(
+            select(selection, DisplayAdapter::Unknown),
+            Err(AdwaitaRenderError::NoMatchingAdapter { .. })
+        ));
+    }
+
+    #[test]
+    fn select_adapter_by_index() {
+        let selection = AdwaitaAdapterSelection::Index(1);
+        assert_eq!(select(selection, DisplayAdapter::Found(0)).unwrap(), 1);
+
+        let selection = AdwaitaAdapterSelection::Index(3);
+        assert!(matches!(
+            select(selection, DisplayAdapter::Unknown),
+            Err(AdwaitaRenderError::NoMatchingAdapter { .. })
+        ));
+    }
+
+    #[test]
+    fn select_adapter_by_power_preference() {
+        let selection = AdwaitaAdapterSelection::PowerPreference(wgpu::PowerPreference::LowPower);
+        assert_eq!(select(selection, DisplayAdapter::Found(1)).unwrap(), 0);
+
+        let selection =
+            AdwaitaAdapterSelection::PowerPreference(wgpu::PowerPreference::HighPerformance);
+        assert_eq!(select(selection, DisplayAdapter::Found(0)).unwrap(), 1);
+    }
+
+    #[test]
+    fn select_adapter_by_power_preference_falls_back_to_display() {
+        let config = AdwaitaRenderConfig {
+            adapter: AdwaitaAdapterSelection::PowerPreference(
+                wgpu::PowerPreference::HighPerformance,
+            ),
+            ..default()
+        };
+        // no discrete GPU, so the display's adapter is used
+        let adapters = [
+            adapter_info("Intel Graphics", 0xa7a0, wgpu::DeviceType::IntegratedGpu),
+            adapter_info(
+                "Intel Arc Graphics",
+                0x7d55,
+                wgpu::DeviceType::IntegratedGpu,
+            ),
+        ];
+        let power_preference = wgpu::PowerPreference::None;
+        assert_eq!(
+            select_adapter(
+                &adapters,
+                &config,
+                power_preference,
+                DisplayAdapter::Found(1)
+            )
+            .unwrap(),
+            1
+        );
+        assert_eq!(
+            select_adapter(
+                &adapters,
+                &config,
+                power_preference,
+                DisplayAdapter::Unknown
+            )
+            .unwrap(),
+            0
+        );
+
+        // `PowerPreference::None` has no device type to look for
+        let selection = AdwaitaAdapterSelection::PowerPreference(wgpu::PowerPreference::None);
+        assert_eq!(select(selection, DisplayAdapter::Found(1)).unwrap(), 1);
+    }
+
+    #[test]
+    fn select_adapter_on_display() {
+        let selection = AdwaitaAdapterSelection::Display;
+        assert_eq!(
+            select(selection.clone(), DisplayAdapter::Found(1)).unwrap(),
+            1
+        );
+        let not_found = DisplayAdapter::NotFound(AdwaitaDrmNode {
+            major: 226,
+            minor: 2,
+        });
+        assert_eq!(select(selection, not_found).unwrap(), 0);
+
+        // without the display's adapter, `WgpuSettings::power_preference` decides
+        let config = AdwaitaRenderConfig::default();
+        assert_eq!(
+            select_adapter(
+                &adapter_infos(),
+                &config,
+                wgpu::PowerPreference::HighPerformance,
+                DisplayAdapter::Unknown,
+            )
+            .unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn select_adapter_rejects_software_adapter() {
+        let selection = AdwaitaAdapterSelection::Name("llvmpipe".to_owned());
+        assert!(matches!(
+            select(selection.clone(), DisplayAdapter::Unknown),
+            Err(AdwaitaRenderError::SoftwareAdapter { .. })
+        ));
+        let config = AdwaitaRenderConfig {
+            adapter: selection,
+            allow_software_adapter: true,
+        };
+        assert_eq!(
+            select_adapter(
+                &adapter_infos(),
+                &config,
+                wgpu::PowerPreference::None,
+                DisplayAdapter::Unknown,
+            )
+            .unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn select_display_adapter_rejects_software_adapter() {
+        let adapters = [adapter_info("llvmpipe", 0, wgpu::DeviceType::Cpu)];
+        let power_preference = wgpu::PowerPreference::None;
+        let config = AdwaitaRenderConfig::default();
+        assert!(matches!(
+            select_adapter(
+                &adapters,
+                &config,
+                power_preference,
+                DisplayAdapter::Unknown
+            ),
+            Err(AdwaitaRenderError::SoftwareAdapter { .. })
+        ));
+        let config = AdwaitaRenderConfig {
+            allow_software_adapter: true,
+            ..default()
+        };
+        assert_eq!(
+            select_adapter(
+                &adapters,
+                &config,
+                power_preference,
+                DisplayAdapter::Unknown
+            )
+            .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn device_extensions_always_include_required() {
+        let extensions = device_extensions(&|_| false);
+        assert_eq!(extensions, REQUIRED_EXTENSIONS);
+        assert!(extensions.contains(&ash::extensions::khr::ExternalMemoryFd::name()));
+        assert!(extensions.contains(&ash::extensions::khr::GetMemoryRequirements2::name()));
+
+        let extensions = device_extensions(&|_| true);
+        for extension in REQUIRED_EXTENSIONS.iter().chain(&MODIFIER_EXTENSIONS) {
+            assert!(extensions.contains(extension));
+        }
+
+        // the modifier extensions are only useful together
+        let extensions =
+            device_extensions(&|extension| extension == vk::ExtImageDrmFormatModifierFn::name());
+        assert_eq!(extensions, REQUIRED_EXTENSIONS);
+    }
+
+    fn device_descriptor(
+        features: wgpu::Features,
+        limits: wgpu::Limits,
+    ) -> wgpu::DeviceDescriptor<'static> {
+        wgpu::DeviceDescriptor {
+            label: None,
+            required_features: features,
+            required_limits: limits,
+        }
+    }
+
+    #[test]
+    fn check_device_descriptor_accepts_supported() {
+        let descriptor = device_descriptor(wgpu::Features::PUSH_CONSTANTS, wgpu::Limits::default());
+        let features = wgpu::Features::PUSH_CONSTANTS | wgpu::Features::POLYGON_MODE_LINE;
+        check_device_descriptor("GPU", features, &wgpu::Limits::default(), &descriptor).unwrap();
+    }
+
+    #[test]
+    fn check_device_descriptor_rejects_unsupported_features() {
+        let descriptor = device_descriptor(
+            wgpu::Features::PUSH_CONSTANTS | wgpu::Features::POLYGON_MODE_LINE,
+            wgpu::Limits::default(),
+        );
+        let result = check_device_descriptor(
+            "GPU",
+            wgpu::Features::PUSH_CONSTANTS,
+            &wgpu::Limits::default(),
+            &descriptor,
+        );
+        assert!(matches!(
+            result,
+            Err(AdwaitaRenderError::UnsupportedFeatures { name, unsupported })
+                if name == "GPU" && unsupported == wgpu::Features::POLYGON_MODE_LINE
+        ));
+    }
+
+    #[test]
+    fn check_device_descriptor_rejects_unsupported_limits() {
+        let descriptor = device_descriptor(
+            wgpu::Features::empty(),
+            wgpu::Limits {
+                max_bind_groups: 8,
+                ..wgpu::Limits::default()
+            },
+        );
+        let result = check_device_descriptor(
+            "GPU",
+            wgpu::Features::empty(),
+            &wgpu::Limits::default(),
+            &descriptor,
+        );
+        let Err(AdwaitaRenderError::UnsupportedLimits { name, unsupported }) = result else {
+            panic!("expected unsupported limits, got {result:?}");
+        };
+        assert_eq!(name, "GPU");
+        assert_eq!(unsupported.len(), 1);
+        assert!(unsupported[0].starts_with("max_bind_groups"));
+    }
+
+    #[test]
+    fn present_modes_map_to_backpressure() {
+        assert_eq!(
+            AdwaitaPresentMode::Fifo.backpressure(),
+            AdwaitaFrameBackpressure::Throttle
+        );
+        assert_eq!(
+            AdwaitaPresentMode::Mailbox.backpressure(),
+            AdwaitaFrameBackpressure::DropOldest
+        );
+        assert_eq!(
+            AdwaitaPresentMode::Immediate.backpressure(),
+            AdwaitaFrameBackpressure::DropOldest
+        );
+    }
 }
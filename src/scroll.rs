@@ -0,0 +1,89 @@
+use std::{cell::Cell, rc::Rc};
+
+use adw::prelude::*;
+use bevy::{input::mouse::MouseScrollUnit, prelude::*};
+use gtk::{gdk, glib};
+
+use crate::adwaita_app::WindowEvent;
+
+/// Filter applied to smooth scrolling, such as from touchpads, before it is sent
+/// to Bevy as [`MouseWheel`] events.
+///
+/// Touchpads report many tiny deltas, which can make scroll-driven controls like
+/// camera zoom feel jittery. Scrolling from a mouse wheel is never filtered.
+///
+/// [`MouseWheel`]: bevy::input::mouse::MouseWheel
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[reflect(Default)]
+pub struct AdwaitaScrollFilter {
+    /// Deltas smaller than this, in physical pixels, are dropped on each axis.
+    pub deadzone: f32,
+    /// How much of the previous delta is blended into each new one, from `0.0`
+    /// (no smoothing) up to but excluding `1.0`.
+    ///
+    /// Smoothing is reset whenever the user lifts their fingers, so it only adds
+    /// latency within a single scroll.
+    pub smoothing: f32,
+}
+
+impl Default for AdwaitaScrollFilter {
+    fn default() -> Self {
+        Self {
+            deadzone: 1.0,
+            smoothing: 0.3,
+        }
+    }
+}
+
+/// Adds a scroll controller to `widget`, which sends [`WindowEvent::Scroll`]s.
+pub(crate) fn attach_scroll(
+    widget: &impl IsA<gtk::Widget>,
+    send_event: &flume::Sender<WindowEvent>,
+    filter: Option<AdwaitaScrollFilter>,
+) {
+    let controller = gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::BOTH_AXES);
+    let smoothed = Rc::new(Cell::new(Vec2::ZERO));
+
+    controller.connect_scroll({
+        let send_event = send_event.clone();
+        let smoothed = smoothed.clone();
+        move |controller, dx, dy| {
+            // GTK scrolls down for positive deltas, Bevy scrolls up
+            let delta = -Vec2::new(dx as f32, dy as f32);
+            let (unit, delta) = match controller.unit() {
+                gdk::ScrollUnit::Surface => {
+                    let scale = controller
+                        .widget()
+                        .and_then(|widget| widget.native())
+                        .and_then(|native| native.surface())
+                        .map_or(1.0, |surface| surface.scale());
+                    let delta = delta * scale as f32;
+                    let delta = match filter {
+                        Some(filter) => apply_filter(filter, &smoothed, delta),
+                        None => delta,
+                    };
+                    (MouseScrollUnit::Pixel, delta)
+                }
+                _ => (MouseScrollUnit::Line, delta),
+            };
+            if delta != Vec2::ZERO {
+                _ = send_event.send(WindowEvent::Scroll { unit, delta });
+            }
+            glib::Propagation::Stop
+        }
+    });
+    controller.connect_scroll_end(move |_| smoothed.set(Vec2::ZERO));
+    widget.add_controller(controller);
+}
+
+fn apply_filter(filter: AdwaitaScrollFilter, smoothed: &Cell<Vec2>, delta: Vec2) -> Vec2 {
+    let smoothing = filter.smoothing.clamp(0.0, 0.99);
+    let value = smoothed.get().lerp(delta, 1.0 - smoothing);
+    smoothed.set(value);
+    Vec2::select(
+        value.abs().cmplt(Vec2::splat(filter.deadzone)),
+        Vec2::ZERO,
+        value,
+    )
+}
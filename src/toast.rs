@@ -0,0 +1,106 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use adw::glib;
+use bevy::prelude::*;
+
+use crate::adwaita_app::WindowEvent;
+
+/// Short notification shown over the content of a window with
+/// [`AdwaitaWindow::show_toast`].
+///
+/// [`AdwaitaWindow::show_toast`]: crate::AdwaitaWindow::show_toast
+#[derive(Debug, Clone, PartialEq, Eq, Reflect)]
+#[reflect(Default)]
+pub struct AdwaitaToast {
+    pub title: String,
+    /// How long the toast is shown for, or [`None`] to show it until the user
+    /// dismisses it.
+    ///
+    /// GTK only supports whole seconds, so this is rounded up.
+    pub timeout: Option<Duration>,
+    /// Label of a button on the toast, which dismisses it when clicked.
+    pub button_label: Option<String>,
+}
+
+impl Default for AdwaitaToast {
+    fn default() -> Self {
+        Self {
+            title: String::new(),
+            // same as `adw::Toast`
+            timeout: Some(Duration::from_secs(5)),
+            button_label: None,
+        }
+    }
+}
+
+impl AdwaitaToast {
+    #[must_use]
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            ..default()
+        }
+    }
+
+    #[must_use]
+    pub fn with_button(mut self, label: impl Into<String>) -> Self {
+        self.button_label = Some(label.into());
+        self
+    }
+}
+
+/// Identifies a toast shown with [`AdwaitaWindow::show_toast`].
+///
+/// [`AdwaitaWindow::show_toast`]: crate::AdwaitaWindow::show_toast
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub struct AdwaitaToastId(pub(crate) u64);
+
+/// Sent when a toast shown with [`AdwaitaWindow::show_toast`] goes away.
+///
+/// [`AdwaitaWindow::show_toast`]: crate::AdwaitaWindow::show_toast
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event, Reflect)]
+pub struct AdwaitaToastDismissed {
+    /// Entity of the [`AdwaitaWindow`] the toast was shown on.
+    ///
+    /// [`AdwaitaWindow`]: crate::AdwaitaWindow
+    pub window: Entity,
+    pub id: AdwaitaToastId,
+    /// Whether the toast was dismissed by clicking its button, rather than timing
+    /// out or being closed.
+    pub button_clicked: bool,
+}
+
+/// Shows `toast` in `overlay`, sending a [`WindowEvent::ToastDismissed`] once it
+/// goes away.
+pub(crate) fn show_toast(
+    overlay: &adw::ToastOverlay,
+    id: AdwaitaToastId,
+    toast: AdwaitaToast,
+    send_event: flume::Sender<WindowEvent>,
+) {
+    // the title is parsed as Pango markup
+    let adw_toast = adw::Toast::new(&glib::markup_escape_text(&toast.title));
+    adw_toast.set_timeout(toast.timeout.map_or(0, |timeout| {
+        // 0 would show the toast forever
+        (timeout.as_secs_f64().ceil() as u32).max(1)
+    }));
+
+    let button_clicked = Rc::new(Cell::new(false));
+    if let Some(label) = &toast.button_label {
+        adw_toast.set_button_label(Some(label));
+        adw_toast.connect_button_clicked({
+            let button_clicked = button_clicked.clone();
+            move |_| button_clicked.set(true)
+        });
+    }
+    // also emitted after the button is clicked
+    adw_toast.connect_dismissed(move |_| {
+        _ = send_event.send(WindowEvent::ToastDismissed {
+            id,
+            button_clicked: button_clicked.get(),
+        });
+    });
+    overlay.add_toast(adw_toast);
+}
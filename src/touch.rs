@@ -0,0 +1,85 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use adw::prelude::*;
+use bevy::{input::touch::TouchPhase, math::Vec2};
+use gtk::{gdk, glib, graphene};
+
+use crate::adwaita_app::WindowEvent;
+
+/// Adds a controller to `widget` which sends a [`WindowEvent::Touch`] for every
+/// touch point on a touchscreen.
+///
+/// GDK identifies touch points by an [`gdk::EventSequence`], which is only valid
+/// while the touch lasts, so every sequence is given its own ID when it begins. IDs
+/// are never reused, so a finger lifted and put back down is a new touch.
+///
+/// Like [`attach_pointer`](crate::pointer::attach_pointer), positions are sent in
+/// logical coordinates of `widget`.
+pub(crate) fn attach_touch(
+    widget: &impl IsA<gtk::Widget>,
+    send_event: &flume::Sender<WindowEvent>,
+) {
+    let ids = RefCell::new(HashMap::<usize, u64>::new());
+    let next_id = Cell::new(0);
+
+    // touch gestures only report the sequences they've claimed, and we want every
+    // finger, so read the raw events instead
+    let controller = gtk::EventControllerLegacy::new();
+    controller.connect_event({
+        let send_event = send_event.clone();
+        move |controller, event| {
+            let phase = match event.event_type() {
+                gdk::EventType::TouchBegin => TouchPhase::Started,
+                gdk::EventType::TouchUpdate => TouchPhase::Moved,
+                gdk::EventType::TouchEnd => TouchPhase::Ended,
+                gdk::EventType::TouchCancel => TouchPhase::Canceled,
+                _ => return glib::Propagation::Proceed,
+            };
+            let Some(widget) = controller.widget() else {
+                return glib::Propagation::Proceed;
+            };
+            // sequences are opaque pointers, which stay the same for the whole touch
+            let sequence = event.event_sequence().as_ptr() as usize;
+
+            let id = match phase {
+                TouchPhase::Started => {
+                    let id = next_id.get();
+                    next_id.set(id + 1);
+                    ids.borrow_mut().insert(sequence, id);
+                    Some(id)
+                }
+                TouchPhase::Moved => ids.borrow().get(&sequence).copied(),
+                TouchPhase::Ended | TouchPhase::Canceled => ids.borrow_mut().remove(&sequence),
+            };
+            // a touch which began before the widget was mapped
+            let Some(id) = id else {
+                return glib::Propagation::Proceed;
+            };
+
+            if let Some(position) = widget_position(&widget, event) {
+                _ = send_event.send(WindowEvent::Touch {
+                    id,
+                    phase,
+                    position,
+                    widget_size: Vec2::new(widget.width() as f32, widget.height() as f32),
+                });
+            }
+            // gestures on the same widget still need to see the touches
+            glib::Propagation::Proceed
+        }
+    });
+    widget.add_controller(controller);
+}
+
+/// Position of `event` in `widget`, since raw events are relative to the surface.
+fn widget_position(widget: &gtk::Widget, event: &gdk::Event) -> Option<Vec2> {
+    let (x, y) = event.position()?;
+    let native = widget.native()?;
+    let (offset_x, offset_y) = native.surface_transform();
+    let point = graphene::Point::new((x - offset_x) as f32, (y - offset_y) as f32);
+    let point = native
+        .upcast_ref::<gtk::Widget>()
+        .compute_point(widget, &point)?;
+    Some(Vec2::new(point.x(), point.y()))
+}
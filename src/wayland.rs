@@ -0,0 +1,76 @@
+//! Access to the Wayland objects behind GDK's, for protocols which GTK doesn't
+//! expose.
+
+use std::os::raw::c_void;
+
+use adw::gdk;
+use adw::glib::translate::ToGlibPtr;
+use adw::prelude::*;
+use wayland_client::{
+    backend::{Backend, ObjectId},
+    protocol::{wl_pointer::WlPointer, wl_surface::WlSurface},
+    Connection, Proxy,
+};
+
+// not wrapped by gtk-rs, but part of libgtk-4 on every build with the Wayland
+// backend
+extern "C" {
+    fn gdk_wayland_display_get_wl_display(display: *mut gdk::ffi::GdkDisplay) -> *mut c_void;
+    fn gdk_wayland_surface_get_wl_surface(surface: *mut gdk::ffi::GdkSurface) -> *mut c_void;
+    fn gdk_wayland_device_get_wl_pointer(device: *mut gdk::ffi::GdkDevice) -> *mut c_void;
+}
+
+pub(crate) fn is_wayland(display: &gdk::Display) -> bool {
+    display.type_().name().starts_with("GdkWayland")
+}
+
+/// GDK's connection to the compositor, or [`None`] if `display` isn't on Wayland.
+///
+/// Objects created on the connection should use their own event queue, since GDK
+/// dispatches its own queue.
+pub(crate) fn connection(display: &gdk::Display) -> Option<Connection> {
+    if !is_wayland(display) {
+        return None;
+    }
+    // SAFETY: the display is a Wayland display, whose `wl_display` is owned by GDK
+    // and lives as long as the process, since GDK never closes the default display
+    unsafe {
+        let wl_display = gdk_wayland_display_get_wl_display(display.to_glib_none().0);
+        Some(Connection::from_backend(Backend::from_foreign_display(
+            wl_display.cast(),
+        )))
+    }
+}
+
+/// The `wl_surface` of `surface`, which must be on the display of `conn`.
+pub(crate) fn surface(conn: &Connection, surface: &gdk::Surface) -> Option<WlSurface> {
+    if !is_wayland(&surface.display()) {
+        return None;
+    }
+    // SAFETY: the surface is a Wayland surface, whose `wl_surface` lives as long as
+    // the GDK surface
+    unsafe {
+        let wl_surface = gdk_wayland_surface_get_wl_surface(surface.to_glib_none().0);
+        ObjectId::from_ptr(WlSurface::interface(), wl_surface.cast())
+            .and_then(|id| WlSurface::from_id(conn, id))
+            .ok()
+    }
+}
+
+/// The `wl_pointer` of `device`, which must be on the display of `conn`.
+pub(crate) fn pointer(conn: &Connection, device: &gdk::Device) -> Option<WlPointer> {
+    if !is_wayland(&device.display()) {
+        return None;
+    }
+    // SAFETY: the device is a Wayland device, whose `wl_pointer` lives as long as
+    // the seat it belongs to
+    unsafe {
+        let wl_pointer = gdk_wayland_device_get_wl_pointer(device.to_glib_none().0);
+        if wl_pointer.is_null() {
+            return None;
+        }
+        ObjectId::from_ptr(WlPointer::interface(), wl_pointer.cast())
+            .and_then(|id| WlPointer::from_id(conn, id))
+            .ok()
+    }
+}